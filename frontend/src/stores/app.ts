@@ -12,13 +12,30 @@ export interface ImgInfo {
   live_photo_url?: string
 }
 
+export interface MusicInfo {
+  title: string
+  author: string
+}
+
+export interface SubtitleTrack {
+  lang: string
+  lang_name: string
+  ai: boolean
+  url: string
+  cues: { from: number; to: number; content: string }[]
+}
+
 export interface VideoParseInfo {
+  source?: string
+  content_id?: string
   author: Author
   title: string
   video_url?: string
   music_url?: string
+  music?: MusicInfo
   cover_url?: string
   images?: ImgInfo[]
+  subtitles?: SubtitleTrack[]
 }
 
 export interface ParseResult {
@@ -30,6 +47,10 @@ export interface ParseResult {
   platform?: string
   images?: ImgInfo[]
   music_url?: string
+  music?: MusicInfo
+  source?: string
+  content_id?: string
+  subtitles?: SubtitleTrack[]
 }
 
 export const useAppStore = defineStore('app', () => {
@@ -73,6 +94,10 @@ export const useAppStore = defineStore('app', () => {
           cover: videoInfo.cover_url,
           video_url: videoInfo.video_url,
           music_url: videoInfo.music_url,
+          music: videoInfo.music,
+          source: videoInfo.source,
+          content_id: videoInfo.content_id,
+          subtitles: videoInfo.subtitles || [],
           images: videoInfo.images || [],
           description: videoInfo.images && videoInfo.images.length > 0 
             ? `包含 ${videoInfo.images.length} 张图片` 
@@ -0,0 +1,197 @@
+//! 解析失败时的调试记录
+//!
+//! 默认关闭，可用 `DEBUG_CAPTURE` 对所有请求开启；配置了 `ADMIN_TOKEN` 时也可在单次请求中传
+//! `debug=true` 开启，未配置时管理接口关闭、记录无法取回，忽略该参数。
+//! 开启后解析过程中的上游请求经 [`replay`] 录制，Cookie、令牌和签名等敏感信息在录制时已去除；
+//! 解析失败时连同错误信息保存到记录目录，成功时丢弃。记录文件与测试夹具格式相同，
+//! 下载后可用 `parse --replay` 直接回放复现。目录中只保留最近的若干条记录。
+
+use crate::config::config;
+use crate::replay::{self, Cassette, Fixture};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 未配置 `DEBUG_CAPTURE_DIR` 时的记录目录
+const DEFAULT_DIR: &str = "debug_captures";
+
+/// 未配置 `DEBUG_CAPTURE_KEEP` 时保留的记录数
+const DEFAULT_KEEP: usize = 100;
+
+/// 一次失败解析的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capture {
+    pub id: String,
+    pub time: String,
+    pub error: String,
+    #[serde(flatten)]
+    pub fixture: Fixture,
+}
+
+/// 记录列表中的一项，不含请求内容
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureSummary {
+    pub id: String,
+    pub time: String,
+    pub url: String,
+    pub error: String,
+    pub requests: usize,
+}
+
+/// 执行解析 `f`，请求中指定 `requested` 或配置中全局开启时录制其上游请求，
+/// 失败时保存记录，返回解析结果和记录ID
+///
+/// `url` 为回放时使用的分享链接。已处于录制或回放中时不再嵌套录制。
+pub async fn run<T, F>(requested: bool, url: &str, f: F) -> (Result<T>, Option<String>)
+where
+    F: Future<Output = Result<T>>,
+{
+    let requested = requested && config().admin_token.is_some();
+    if !(requested || config().debug_capture) || replay::active().is_some() {
+        return (f.await, None);
+    }
+
+    let cassette = Arc::new(Cassette::record());
+    let result = replay::scope(cassette.clone(), f).await;
+    let Err(e) = &result else {
+        return (result, None);
+    };
+
+    let now = chrono::Utc::now();
+    let capture = Capture {
+        id: format!("{}-{:06x}", now.format("%Y%m%d%H%M%S"), rand::random::<u32>() & 0xFF_FFFF),
+        time: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        error: e.to_string(),
+        fixture: Fixture { url: url.to_string(), synthetic: false, exchanges: cassette.exchanges() },
+    };
+    let (id, requests) = (capture.id.clone(), capture.fixture.exchanges.len());
+    let (dir, keep) = (capture_dir(), keep());
+    let saved = tokio::task::spawn_blocking(move || save(&dir, keep, &capture))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|saved| saved);
+    match saved {
+        Ok(()) => {
+            tracing::info!("已保存解析失败记录 {} ({} 个请求)", id, requests);
+            (result, Some(id))
+        }
+        Err(save_error) => {
+            tracing::warn!("保存解析失败记录失败: {}", save_error);
+            (result, None)
+        }
+    }
+}
+
+/// 最近的记录，从新到旧
+pub async fn list() -> Result<Vec<CaptureSummary>> {
+    let dir = capture_dir();
+    tokio::task::spawn_blocking(move || list_in(&dir)).await?
+}
+
+/// 读取记录文件原文
+pub async fn load(id: &str) -> Result<String> {
+    let (dir, id) = (capture_dir(), id.to_string());
+    tokio::task::spawn_blocking(move || load_from(&dir, &id)).await?
+}
+
+fn capture_dir() -> PathBuf {
+    config()
+        .debug_capture_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR))
+}
+
+fn keep() -> usize {
+    config().debug_capture_keep.unwrap_or(DEFAULT_KEEP).max(1)
+}
+
+/// 记录ID只含数字、字母和 `-`，防止越出记录目录
+fn capture_path(dir: &Path, id: &str) -> Result<PathBuf> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(anyhow!("无效的记录ID: {}", id));
+    }
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+/// 保存记录并删除超出数量的旧记录
+fn save(dir: &Path, keep: usize, capture: &Capture) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(capture_path(dir, &capture.id)?, serde_json::to_string_pretty(capture)? + "\n")?;
+
+    // 记录ID以时间开头，按文件名排序即按时间排序
+    let files = capture_files(dir)?;
+    for path in files.iter().take(files.len().saturating_sub(keep)) {
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::warn!("删除旧记录 {} 失败: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// 目录中的记录文件，从旧到新
+fn capture_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    files.sort();
+    Ok(files)
+}
+
+fn list_in(dir: &Path) -> Result<Vec<CaptureSummary>> {
+    Ok(capture_files(dir)?
+        .iter()
+        .rev()
+        .filter_map(|path| {
+            let capture: Capture = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+            Some(CaptureSummary {
+                id: capture.id,
+                time: capture.time,
+                url: capture.fixture.url,
+                error: capture.error,
+                requests: capture.fixture.exchanges.len(),
+            })
+        })
+        .collect())
+}
+
+fn load_from(dir: &Path, id: &str) -> Result<String> {
+    std::fs::read_to_string(capture_path(dir, id)?).map_err(|_| anyhow!("记录 {} 不存在", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("capture-test-{}", std::process::id()));
+        let capture = |id: &str| Capture {
+            id: id.to_string(),
+            time: "2024-01-01T00:00:00Z".to_string(),
+            error: "无法解析视频数据".to_string(),
+            fixture: Fixture { url: format!("https://v.douyin.com/{}/", id), synthetic: false, exchanges: Vec::new() },
+        };
+        for id in ["20240101000001-aaaaaa", "20240101000002-bbbbbb", "20240101000003-cccccc"] {
+            save(&dir, 2, &capture(id)).unwrap();
+        }
+
+        let ids: Vec<String> = list_in(&dir).unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, ["20240101000003-cccccc", "20240101000002-bbbbbb"]);
+
+        // 记录文件可以直接作为夹具回放
+        let content = load_from(&dir, "20240101000002-bbbbbb").unwrap();
+        let fixture: Fixture = serde_json::from_str(&content).unwrap();
+        assert_eq!(fixture.url, "https://v.douyin.com/20240101000002-bbbbbb/");
+
+        assert!(load_from(&dir, "20240101000001-aaaaaa").is_err());
+        assert!(load_from(&dir, "../Cargo").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
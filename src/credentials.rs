@@ -0,0 +1,521 @@
+//! 平台 Cookie 凭据存储
+//!
+//! 每个平台可以配置多个账号，凭据来自 `COOKIES_DIR` 目录下的 Netscape
+//! `cookies.txt` 文件或 `COOKIE_<平台>` 环境变量。账号以 reqwest 的
+//! [`CookieStore`] 形式挂到平台的HTTP客户端上，响应中的 `Set-Cookie`
+//! 会写回账号并持久化到原文件；请求失败时轮换到下一个账号。
+//! 环境变量中的 Cookie 不带域名，只发往该平台的 [`VideoSource::cookie_domains`]。
+
+use crate::config::config;
+use crate::models::VideoSource;
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredCookie {
+    /// 不带前导点的域名，为空时不随任何请求发送
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// 过期时间（Unix 秒），`None` 为会话 Cookie
+    pub expires: Option<i64>,
+    pub name: String,
+    pub value: String,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires, Some(expires) if expires > 0 && expires <= now)
+    }
+
+    fn matches(&self, url: &Url, now: i64) -> bool {
+        if self.is_expired(now) {
+            return false;
+        }
+        if self.secure && url.scheme() != "https" {
+            return false;
+        }
+        if !url.path().starts_with(&self.path) {
+            return false;
+        }
+        if self.domain.is_empty() {
+            return false;
+        }
+
+        let host = url.host_str().unwrap_or("");
+        host == self.domain || (self.include_subdomains && is_subdomain(host, &self.domain))
+    }
+
+    fn same_key(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+/// `host` 是否为 `domain` 的子域名
+fn is_subdomain(host: &str, domain: &str) -> bool {
+    host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+}
+
+/// 解析 Netscape 格式的 cookies.txt
+pub fn parse_netscape(content: &str) -> Vec<StoredCookie> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches('\r');
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(rest) => (rest, true),
+                None => (line, false),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+
+            let expires: i64 = fields[4].trim().parse().unwrap_or(0);
+            Some(StoredCookie {
+                domain: fields[0].trim().trim_start_matches('.').to_string(),
+                include_subdomains: fields[1].eq_ignore_ascii_case("TRUE"),
+                path: fields[2].to_string(),
+                secure: fields[3].eq_ignore_ascii_case("TRUE"),
+                http_only,
+                expires: (expires > 0).then_some(expires),
+                name: fields[5].to_string(),
+                value: fields[6..].join("\t"),
+            })
+        })
+        .collect()
+}
+
+/// 序列化为 Netscape 格式，会话 Cookie 的过期时间写为 0
+pub fn to_netscape(cookies: &[StoredCookie]) -> String {
+    let mut out = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in cookies {
+        let domain = if cookie.include_subdomains && !cookie.domain.is_empty() {
+            format!(".{}", cookie.domain)
+        } else {
+            cookie.domain.clone()
+        };
+        out.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only { "#HttpOnly_" } else { "" },
+            domain,
+            if cookie.include_subdomains { "TRUE" } else { "FALSE" },
+            cookie.path,
+            if cookie.secure { "TRUE" } else { "FALSE" },
+            cookie.expires.unwrap_or(0),
+            cookie.name,
+            cookie.value,
+        ));
+    }
+    out
+}
+
+/// 解析 `a=1; b=2` 形式的 Cookie 请求头，生成的 Cookie 没有域名，需经 [`scope_cookies`] 指定后才会发送
+pub fn parse_cookie_header(header: &str) -> Vec<StoredCookie> {
+    header
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| StoredCookie {
+                domain: String::new(),
+                include_subdomains: true,
+                path: "/".to_string(),
+                secure: false,
+                http_only: false,
+                expires: None,
+                name: name.to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 把 Cookie 复制到每个域名及其子域名下
+pub fn scope_cookies(cookies: &[StoredCookie], domains: &[&str]) -> Vec<StoredCookie> {
+    domains
+        .iter()
+        .flat_map(|domain| {
+            cookies.iter().map(move |cookie| StoredCookie {
+                domain: domain.to_string(),
+                include_subdomains: true,
+                ..cookie.clone()
+            })
+        })
+        .collect()
+}
+
+/// 解析 `Set-Cookie` 响应头，`Domain` 不是请求域名本身或其上级域名时丢弃该 Cookie
+pub fn parse_set_cookie(header: &str, url: &Url, now: i64) -> Option<StoredCookie> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.trim().split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let host = url.host_str()?.to_ascii_lowercase();
+    let mut cookie = StoredCookie {
+        domain: host.clone(),
+        include_subdomains: false,
+        path: "/".to_string(),
+        secure: false,
+        http_only: false,
+        expires: None,
+        name: name.to_string(),
+        value: value.trim().trim_matches('"').to_string(),
+    };
+
+    let mut max_age = None;
+    for attr in parts {
+        let (key, val) = match attr.trim().split_once('=') {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => (attr.trim(), ""),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "domain" if !val.is_empty() => {
+                let domain = val.trim_start_matches('.').to_ascii_lowercase();
+                // 不接受顶级域名，也不接受与请求无关的域名
+                if !domain.contains('.') || !(host == domain || is_subdomain(&host, &domain)) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.include_subdomains = true;
+            }
+            "path" if val.starts_with('/') => cookie.path = val.to_string(),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "max-age" => max_age = val.parse::<i64>().ok(),
+            "expires" => cookie.expires = parse_cookie_date(val),
+            _ => {}
+        }
+    }
+
+    // Max-Age 优先于 Expires，非正数表示立即删除
+    if let Some(max_age) = max_age {
+        cookie.expires = Some(if max_age <= 0 { 1 } else { now + max_age });
+    }
+
+    Some(cookie)
+}
+
+fn parse_cookie_date(s: &str) -> Option<i64> {
+    const FORMATS: [&str; 3] = [
+        "%a, %d %b %Y %H:%M:%S GMT",
+        "%a, %d-%b-%Y %H:%M:%S GMT",
+        "%A, %d-%b-%y %H:%M:%S GMT",
+    ];
+    FORMATS
+        .iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(s, fmt).ok())
+        .map(|dt| dt.and_utc().timestamp())
+}
+
+fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// 一个平台账号的 Cookie 集合
+#[derive(Debug)]
+pub struct Account {
+    pub name: String,
+    file: Option<PathBuf>,
+    cookies: RwLock<Vec<StoredCookie>>,
+    /// Cookie 每次修改后的版本号，与修改在同一把锁内递增
+    version: AtomicUsize,
+    /// 已写入文件的版本号，写文件时持有，较旧的快照不会覆盖较新的
+    saved_version: Arc<Mutex<usize>>,
+}
+
+impl Account {
+    pub fn new(name: impl Into<String>, cookies: Vec<StoredCookie>, file: Option<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            file,
+            cookies: RwLock::new(cookies),
+            version: AtomicUsize::new(0),
+            saved_version: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn load_file(name: String, path: &Path) -> Option<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Some(Self::new(name, parse_netscape(&content), Some(path.to_path_buf()))),
+            Err(e) => {
+                tracing::warn!("读取Cookie文件失败 {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// 以 `a=1; b=2` 形式返回请求指定URL时会携带的 Cookie
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let now = unix_now();
+        let cookies = self.cookies.read().unwrap_or_else(|e| e.into_inner());
+        let header = cookies
+            .iter()
+            .filter(|c| c.matches(url, now))
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+
+    /// 写入或替换 Cookie，已过期的 Cookie 会被移除
+    ///
+    /// 有文件的账号随后保存到文件；在异步运行时中调用时放到阻塞线程池执行。
+    pub fn store(&self, new_cookies: Vec<StoredCookie>) {
+        if new_cookies.is_empty() {
+            return;
+        }
+
+        let now = unix_now();
+        let (snapshot, version) = {
+            let mut cookies = self.cookies.write().unwrap_or_else(|e| e.into_inner());
+            for cookie in new_cookies {
+                cookies.retain(|c| !c.same_key(&cookie));
+                if !cookie.is_expired(now) {
+                    cookies.push(cookie);
+                }
+            }
+            (cookies.clone(), self.version.fetch_add(1, Ordering::Relaxed) + 1)
+        };
+
+        let Some(file) = self.file.clone() else {
+            return;
+        };
+        let saved_version = self.saved_version.clone();
+        let save = move || save_cookie_file(&file, &saved_version, version, &snapshot);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => drop(handle.spawn_blocking(save)),
+            Err(_) => save(),
+        }
+    }
+}
+
+/// 先写临时文件再替换，保证文件内容完整；已保存更新的版本时跳过
+fn save_cookie_file(file: &Path, saved_version: &Mutex<usize>, version: usize, cookies: &[StoredCookie]) {
+    let mut saved = saved_version.lock().unwrap_or_else(|e| e.into_inner());
+    if *saved >= version {
+        return;
+    }
+
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    match std::fs::write(&temp, to_netscape(cookies)).and_then(|()| std::fs::rename(&temp, file)) {
+        Ok(()) => *saved = version,
+        Err(e) => tracing::warn!("保存Cookie文件失败 {}: {}", file.display(), e),
+    }
+}
+
+impl CookieStore for Account {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let now = unix_now();
+        let cookies = cookie_headers
+            .filter_map(|h| h.to_str().ok())
+            .filter_map(|h| parse_set_cookie(h, url, now))
+            .collect();
+        self.store(cookies);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.cookie_header(url)
+            .and_then(|header| HeaderValue::from_str(&header).ok())
+    }
+}
+
+#[derive(Debug, Default)]
+struct PlatformAccounts {
+    accounts: Vec<Arc<Account>>,
+    cursor: AtomicUsize,
+}
+
+/// 所有平台的账号池
+#[derive(Debug, Default)]
+pub struct CredentialStore {
+    platforms: HashMap<VideoSource, PlatformAccounts>,
+}
+
+impl CredentialStore {
+    /// 从 `COOKIES_DIR` 和 `COOKIE_<平台>` 环境变量加载账号
+    ///
+    /// 目录下的 `<平台>.txt` 和 `<平台>/*.txt` 各算一个账号；环境变量中
+    /// 多个账号用 `|` 分隔，这类账号刷新的 Cookie 只保存在内存中。
+    pub fn load() -> Self {
+        let config = config();
+        let mut store = Self::default();
+
+        for source in VideoSource::ALL {
+            let mut accounts = Vec::new();
+
+            if let Some(dir) = &config.cookies_dir {
+                let single = dir.join(format!("{}.txt", source.as_str()));
+                if single.is_file() {
+                    accounts.extend(Account::load_file(source.as_str().to_string(), &single));
+                }
+
+                let mut files: Vec<PathBuf> = std::fs::read_dir(dir.join(source.as_str()))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                    .collect();
+                files.sort();
+                for path in files {
+                    let name = path
+                        .file_stem()
+                        .map(|s| format!("{}/{}", source.as_str(), s.to_string_lossy()))
+                        .unwrap_or_default();
+                    accounts.extend(Account::load_file(name, &path));
+                }
+            }
+
+            if let Some(value) = config.cookie_strings.get(&source) {
+                for (i, header) in value.split('|').filter(|s| !s.trim().is_empty()).enumerate() {
+                    accounts.push(Account::new(
+                        format!("env:{}#{}", source.as_str(), i + 1),
+                        scope_cookies(&parse_cookie_header(header), source.cookie_domains()),
+                        None,
+                    ));
+                }
+            }
+
+            if !accounts.is_empty() {
+                tracing::info!("🍪 {} 已加载 {} 个账号", source.display_name(), accounts.len());
+                store.platforms.insert(
+                    source,
+                    PlatformAccounts {
+                        accounts: accounts.into_iter().map(Arc::new).collect(),
+                        cursor: AtomicUsize::new(0),
+                    },
+                );
+            }
+        }
+
+        store
+    }
+
+    /// 平台当前使用的账号
+    pub fn current(&self, source: VideoSource) -> Option<Arc<Account>> {
+        let platform = self.platforms.get(&source)?;
+        let cursor = platform.cursor.load(Ordering::Relaxed);
+        platform
+            .accounts
+            .get(cursor % platform.accounts.len())
+            .cloned()
+    }
+
+    /// 切换到平台的下一个账号
+    pub fn rotate(&self, source: VideoSource) {
+        if let Some(platform) = self.platforms.get(&source) {
+            if platform.accounts.len() > 1 {
+                let cursor = platform.cursor.fetch_add(1, Ordering::Relaxed) + 1;
+                let account = &platform.accounts[cursor % platform.accounts.len()];
+                tracing::info!("🔀 {} 切换账号: {}", source.display_name(), account.name);
+            }
+        }
+    }
+}
+
+/// 全局凭据存储，首次访问时加载
+pub fn credentials() -> &'static CredentialStore {
+    static STORE: OnceLock<CredentialStore> = OnceLock::new();
+    STORE.get_or_init(CredentialStore::load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_netscape_roundtrip() {
+        let content = "# Netscape HTTP Cookie File\n\
+            .weibo.com\tTRUE\t/\tFALSE\t1999999999\tSUB\tabc\n\
+            #HttpOnly_.weibo.com\tTRUE\t/\tTRUE\t0\tSUBP\txyz\n\
+            # comment\n";
+        let cookies = parse_netscape(content);
+
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].domain, "weibo.com");
+        assert_eq!(cookies[0].expires, Some(1999999999));
+        assert!(cookies[1].http_only && cookies[1].secure);
+        assert_eq!(cookies[1].expires, None);
+        assert_eq!(parse_netscape(&to_netscape(&cookies)), cookies);
+    }
+
+    #[test]
+    fn test_account_cookie_matching() {
+        let account = Account::new("test", parse_netscape(".weibo.com\tTRUE\t/\tFALSE\t0\tSUB\tabc\n"), None);
+        let url = Url::parse("https://m.weibo.cn/statuses/show").unwrap();
+        assert_eq!(account.cookie_header(&url), None);
+
+        let url = Url::parse("https://h5.video.weibo.com/show").unwrap();
+        assert_eq!(account.cookie_header(&url).as_deref(), Some("SUB=abc"));
+
+        let set_cookie = "SUB=new; Domain=.weibo.com; Path=/; Max-Age=3600; HttpOnly";
+        account.store(vec![parse_set_cookie(set_cookie, &url, unix_now()).unwrap()]);
+        assert_eq!(account.cookie_header(&url).as_deref(), Some("SUB=new"));
+
+        let expired = "SUB=gone; Domain=.weibo.com; Path=/; Expires=Thu, 01 Jan 1970 00:00:01 GMT";
+        account.store(vec![parse_set_cookie(expired, &url, unix_now()).unwrap()]);
+        assert_eq!(account.cookie_header(&url), None);
+    }
+
+    #[test]
+    fn test_set_cookie_domain_match() {
+        let url = Url::parse("https://h5.video.weibo.com/show").unwrap();
+        let now = unix_now();
+        assert_eq!(parse_set_cookie("a=1; Domain=video.weibo.com", &url, now).unwrap().domain, "video.weibo.com");
+        assert_eq!(parse_set_cookie("a=1", &url, now).unwrap().domain, "h5.video.weibo.com");
+
+        // 其他站点、顶级域名和仅后缀相同的域名都不接受
+        assert!(parse_set_cookie("a=1; Domain=bilibili.com", &url, now).is_none());
+        assert!(parse_set_cookie("a=1; Domain=.com", &url, now).is_none());
+        assert!(parse_set_cookie("a=1; Domain=o.weibo.com", &url, now).is_none());
+        assert!(parse_set_cookie("a=1; Domain=deo.weibo.com", &url, now).is_none());
+    }
+
+    #[test]
+    fn test_env_cookies_scoped() {
+        let cookies = parse_cookie_header("SESSDATA=abc");
+        let unscoped = Account::new("unscoped", cookies.clone(), None);
+        let account = Account::new("env", scope_cookies(&cookies, VideoSource::BiliBili.cookie_domains()), None);
+
+        let api = Url::parse("https://api.bilibili.com/x/web-interface/view").unwrap();
+        assert_eq!(account.cookie_header(&api).as_deref(), Some("SESSDATA=abc"));
+        assert_eq!(unscoped.cookie_header(&api), None);
+        for other in ["https://evil.example.com/", "https://notbilibili.com/"] {
+            assert_eq!(account.cookie_header(&Url::parse(other).unwrap()), None);
+        }
+    }
+
+    #[test]
+    fn test_cookie_file_saved_in_order() {
+        let file = std::env::temp_dir().join(format!("cookie-test-{}.txt", std::process::id()));
+        let account = Account::new("file", Vec::new(), Some(file.clone()));
+        let url = Url::parse("https://www.bilibili.com/").unwrap();
+        account.store(vec![parse_set_cookie("a=1; Domain=bilibili.com", &url, unix_now()).unwrap()]);
+        account.store(vec![parse_set_cookie("a=2; Domain=bilibili.com", &url, unix_now()).unwrap()]);
+        assert_eq!(parse_netscape(&std::fs::read_to_string(&file).unwrap())[0].value, "2");
+
+        // 较旧的快照晚到时不覆盖文件
+        save_cookie_file(&file, &account.saved_version, 1, &[]);
+        assert_eq!(parse_netscape(&std::fs::read_to_string(&file).unwrap()).len(), 1);
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! 弹幕转 ASS 字幕：滚动、顶部、底部弹幕各自分配轨道，互不遮挡
+//!
+//! 生成的字幕与视频同名放在一起即可被 mpv 等播放器自动加载。
+
+use crate::models::{Danmaku, DanmakuMode};
+use std::fmt::Write;
+
+/// 平台标准字号，其他字号按比例缩放
+const STANDARD_SIZE: f64 = 25.0;
+
+/// 标准弹幕字号范围
+pub const FONT_SIZE_RANGE: std::ops::RangeInclusive<u32> = 8..=200;
+
+/// 画布宽高上限
+pub const MAX_CANVAS_SIZE: u32 = 8192;
+
+/// 每种弹幕的轨道数上限
+const MAX_LANES: usize = 1024;
+
+/// ASS 生成参数
+#[derive(Debug, Clone)]
+pub struct AssOptions {
+    /// 画布宽度（PlayResX）
+    pub width: u32,
+    /// 画布高度（PlayResY）
+    pub height: u32,
+    pub font_name: String,
+    /// 标准弹幕字号，大号、小号弹幕按比例缩放
+    pub font_size: u32,
+    /// 滚动弹幕横穿屏幕的时长（秒）
+    pub scroll_duration: f64,
+    /// 顶部、底部弹幕停留时长（秒）
+    pub fixed_duration: f64,
+    /// 弹幕可占用的屏幕高度比例（0-1），轨道排满时多余弹幕会被丢弃
+    pub density: f64,
+    /// 不透明度（0-1）
+    pub opacity: f64,
+}
+
+impl Default for AssOptions {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            font_name: "Microsoft YaHei".to_string(),
+            font_size: 48,
+            scroll_duration: 8.0,
+            fixed_duration: 4.0,
+            density: 0.75,
+            opacity: 0.8,
+        }
+    }
+}
+
+impl AssOptions {
+    /// 把参数限制在合理范围内，无效的时长使用默认值
+    pub fn clamped(self) -> Self {
+        let defaults = Self::default();
+        Self {
+            width: self.width.clamp(1, MAX_CANVAS_SIZE),
+            height: self.height.clamp(1, MAX_CANVAS_SIZE),
+            font_size: self.font_size.clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end()),
+            scroll_duration: Some(self.scroll_duration)
+                .filter(|d| d.is_finite() && *d > 0.0)
+                .unwrap_or(defaults.scroll_duration),
+            fixed_duration: Some(self.fixed_duration)
+                .filter(|d| d.is_finite() && *d > 0.0)
+                .unwrap_or(defaults.fixed_duration),
+            density: if self.density.is_nan() { defaults.density } else { self.density.clamp(0.05, 1.0) },
+            opacity: if self.opacity.is_nan() { defaults.opacity } else { self.opacity.clamp(0.0, 1.0) },
+            ..self
+        }
+    }
+}
+
+/// 一条滚动轨道上最后一条弹幕的出现时间和宽度
+#[derive(Clone, Copy)]
+struct ScrollSlot {
+    start: f64,
+    width: f64,
+}
+
+/// 轨道分配器
+struct Lanes {
+    width: f64,
+    duration: f64,
+    scroll: Vec<Option<ScrollSlot>>,
+    /// 顶部、底部轨道的空闲时间
+    top: Vec<f64>,
+    bottom: Vec<f64>,
+}
+
+impl Lanes {
+    fn new(count: usize, width: f64, duration: f64) -> Self {
+        Self {
+            width,
+            duration,
+            scroll: vec![None; count],
+            top: vec![0.0; count],
+            bottom: vec![0.0; count],
+        }
+    }
+
+    /// 新弹幕需等上一条完全进入屏幕，且在上一条离开前追不上它
+    fn scroll_fits(&self, slot: ScrollSlot, start: f64, width: f64) -> bool {
+        let prev_speed = (self.width + slot.width) / self.duration;
+        let entered = start >= slot.start + slot.width / prev_speed;
+        let speed = (self.width + width) / self.duration;
+        let head_when_prev_leaves = self.width - (slot.start + self.duration - start) * speed;
+        entered && head_when_prev_leaves >= 0.0
+    }
+
+    fn take_scroll(&mut self, start: f64, width: f64) -> Option<usize> {
+        let lane = self.scroll.iter().position(|slot| match slot {
+            Some(slot) => self.scroll_fits(*slot, start, width),
+            None => true,
+        })?;
+        self.scroll[lane] = Some(ScrollSlot { start, width });
+        Some(lane)
+    }
+
+    fn take_fixed(&mut self, mode: DanmakuMode, start: f64, end: f64) -> Option<usize> {
+        let lanes = if mode == DanmakuMode::Top { &mut self.top } else { &mut self.bottom };
+        let lane = lanes.iter().position(|free_at| *free_at <= start)?;
+        lanes[lane] = end;
+        Some(lane)
+    }
+}
+
+/// 把弹幕转换为 ASS 字幕文本，弹幕需已按时间排序
+pub fn to_ass(items: &[Danmaku], options: &AssOptions) -> String {
+    let options = &options.clone().clamped();
+    let width = options.width as f64;
+    let height = options.height as f64;
+    let lane_height = options.font_size as f64 * 1.2;
+    let lane_count = ((height * options.density / lane_height) as usize).clamp(1, MAX_LANES);
+    let alpha = 255 - (options.opacity * 255.0).round() as u8;
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: {w}\n\
+         PlayResY: {h}\n\
+         WrapStyle: 2\n\
+         ScaledBorderAndShadow: yes\n\
+         \n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+         Style: Danmaku,{font},{size},&H{a:02X}FFFFFF,&H{a:02X}FFFFFF,&H{a:02X}000000,&H{a:02X}000000,1,0,0,0,100,100,0,0,1,1.5,0,7,0,0,0,1\n\
+         \n\
+         [Events]\n\
+         Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        w = options.width,
+        h = options.height,
+        font = options.font_name,
+        size = options.font_size,
+        a = alpha,
+    );
+
+    let mut lanes = Lanes::new(lane_count, width, options.scroll_duration);
+    for item in items {
+        let text = escape_text(&item.text);
+        if text.is_empty() || item.time < 0.0 {
+            continue;
+        }
+        let font_size = (options.font_size as f64 * item.size.max(1) as f64 / STANDARD_SIZE).round();
+        let text_width = estimate_width(&text, font_size);
+
+        let (end, position) = match item.mode {
+            DanmakuMode::Scroll => {
+                let Some(lane) = lanes.take_scroll(item.time, text_width) else {
+                    continue;
+                };
+                let y = lane as f64 * lane_height;
+                (
+                    item.time + options.scroll_duration,
+                    format!("\\move({:.0},{:.0},{:.0},{:.0})", width, y, -text_width, y),
+                )
+            }
+            mode => {
+                let end = item.time + options.fixed_duration;
+                let Some(lane) = lanes.take_fixed(mode, item.time, end) else {
+                    continue;
+                };
+                let position = if mode == DanmakuMode::Top {
+                    format!("\\an8\\pos({:.0},{:.0})", width / 2.0, lane as f64 * lane_height)
+                } else {
+                    format!("\\an2\\pos({:.0},{:.0})", width / 2.0, height - lane as f64 * lane_height)
+                };
+                (end, position)
+            }
+        };
+
+        let mut tags = position;
+        if font_size as u32 != options.font_size {
+            let _ = write!(tags, "\\fs{}", font_size);
+        }
+        let color = item.color & 0xFFFFFF;
+        if color != 0xFFFFFF {
+            // ASS 颜色为 BGR 顺序
+            let bgr = ((color & 0xFF) << 16) | (color & 0xFF00) | (color >> 16);
+            let _ = write!(tags, "\\c&H{:06X}&", bgr);
+            // 深色弹幕改用白色描边，避免看不清
+            if color == 0 {
+                tags.push_str("\\3c&HFFFFFF&");
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "Dialogue: 0,{},{},Danmaku,,0,0,0,,{{{}}}{}",
+            ass_time(item.time),
+            ass_time(end),
+            tags,
+            text
+        );
+    }
+    out
+}
+
+/// 格式化为 H:MM:SS.cc
+fn ass_time(secs: f64) -> String {
+    let centis = (secs.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+/// 去掉换行，并把 ASS 控制字符换成全角，避免被当作样式代码
+fn escape_text(text: &str) -> String {
+    text.trim()
+        .chars()
+        .map(|c| match c {
+            '\r' | '\n' => ' ',
+            '\\' => '＼',
+            '{' => '｛',
+            '}' => '｝',
+            c => c,
+        })
+        .collect()
+}
+
+/// 按半角字符半宽、其他字符全宽估算文字宽度
+fn estimate_width(text: &str, font_size: f64) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+        .sum::<f64>()
+        * font_size
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn danmaku(time: f64, mode: DanmakuMode, text: &str) -> Danmaku {
+        Danmaku { time, mode, size: 25, color: 0xFFFFFF, text: text.to_string() }
+    }
+
+    #[test]
+    fn test_to_ass_lanes() {
+        let options = AssOptions { height: 240, font_size: 50, density: 0.5, ..Default::default() };
+        // 240 * 0.5 / 60 = 2 条轨道
+        let items = vec![
+            danmaku(0.0, DanmakuMode::Scroll, "第一条"),
+            danmaku(0.1, DanmakuMode::Scroll, "第二条"),
+            danmaku(0.2, DanmakuMode::Scroll, "轨道已满被丢弃"),
+            danmaku(1.0, DanmakuMode::Top, "顶部{\\b1}"),
+            danmaku(2.0, DanmakuMode::Bottom, "底部"),
+            Danmaku { color: 0xFF0000, size: 36, ..danmaku(3.5, DanmakuMode::Scroll, "红色大字") },
+        ];
+        let ass = to_ass(&items, &options);
+        let events: Vec<&str> = ass.lines().filter(|l| l.starts_with("Dialogue:")).collect();
+
+        assert!(ass.contains("PlayResY: 240"));
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0], "Dialogue: 0,0:00:00.00,0:00:08.00,Danmaku,,0,0,0,,{\\move(1920,0,-150,0)}第一条");
+        assert!(events[1].contains("\\move(1920,60,-150,60)"));
+        assert_eq!(events[2], "Dialogue: 0,0:00:01.00,0:00:05.00,Danmaku,,0,0,0,,{\\an8\\pos(960,0)}顶部｛＼b1｝");
+        assert!(events[3].contains("\\an2\\pos(960,240)"));
+        // 第一条已完全进入屏幕，且不会追上
+        assert!(events[4].contains("\\move(1920,0,-288,0)\\fs72\\c&H0000FF&}红色大字"));
+    }
+
+    #[test]
+    fn test_options_clamped() {
+        let items = vec![danmaku(0.0, DanmakuMode::Scroll, "弹幕")];
+        let options = AssOptions { font_size: 0, scroll_duration: 0.0, ..Default::default() };
+        assert!(to_ass(&items, &options).contains("Style: Danmaku,Microsoft YaHei,8,"));
+
+        let options = AssOptions { width: u32::MAX, height: u32::MAX, font_size: 1, ..Default::default() }.clamped();
+        assert_eq!((options.width, options.height, options.font_size), (8192, 8192, 8));
+        assert_eq!(to_ass(&items, &options).matches("Dialogue:").count(), 1);
+    }
+
+    #[test]
+    fn test_ass_time() {
+        assert_eq!(ass_time(0.0), "0:00:00.00");
+        assert_eq!(ass_time(3725.456), "1:02:05.46");
+    }
+}
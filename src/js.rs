@@ -0,0 +1,318 @@
+//! 内联脚本沙箱：执行页面中的 `<script>`，读取其赋值的全局变量
+//!
+//! 页面数据常以 `window._ROUTER_DATA = {...}`、`var videoInfo = {...};` 的形式内嵌，
+//! 其中混有 `undefined`、`NaN`、`new Date(...)`、单引号字符串等 JSON 不支持的写法。
+//! 赋值右侧是纯字面量时直接转换（见 [`extract_js_literal`]），
+//! 否则执行脚本，再用 `JSON.stringify` 取出结果。
+//!
+//! 脚本在独立的子进程中由 boa 引擎执行（本程序以 [`WORKER_ENV`] 环境变量重新启动），
+//! 只提供最小的浏览器环境桩，没有网络和文件访问。执行受以下限制：
+//! - 脚本总大小不超过 [`MAX_SCRIPT_BYTES`]，输出不超过 [`MAX_OUTPUT_BYTES`]
+//! - 子进程的堆内存不超过 [`MAX_MEMORY_BYTES`]（Unix 下由 `RLIMIT_DATA` 保证），超出时子进程终止
+//! - 单个循环最多 [`LOOP_ITERATION_LIMIT`] 次，递归深度最多 [`RECURSION_LIMIT`] 层
+//! - 总时长不超过 [`EVAL_TIMEOUT`]，超时或调用方取消时子进程被杀死
+//! - 同时最多 [`MAX_CONCURRENT_EVALS`] 个子进程，其余调用排队等待
+
+use crate::utils::extract_js_literal;
+use anyhow::{anyhow, Result};
+use boa_engine::{Context, Source};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+/// 参与执行的脚本总大小上限
+pub const MAX_SCRIPT_BYTES: usize = 8 * 1024 * 1024;
+
+/// `JSON.stringify` 结果大小上限
+pub const MAX_OUTPUT_BYTES: usize = 32 * 1024 * 1024;
+
+/// 沙箱子进程的堆内存上限
+pub const MAX_MEMORY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 单个循环的迭代次数上限
+pub const LOOP_ITERATION_LIMIT: u64 = 1_000_000;
+
+/// 函数调用深度上限
+pub const RECURSION_LIMIT: usize = 512;
+
+/// 执行时长上限，也是排队等待的时长上限
+pub const EVAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 同时运行的沙箱子进程数上限
+pub const MAX_CONCURRENT_EVALS: usize = 4;
+
+/// 设置此环境变量时程序作为沙箱子进程运行，见 [`run_worker`]
+pub const WORKER_ENV: &str = "RUST_VIDEO_PARSER_JS_WORKER";
+
+/// 沙箱线程栈大小，解析深层嵌套的字面量时需要
+const SANDBOX_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// 页面脚本常用到的浏览器对象，只保证访问时不报错
+const PRELUDE: &str = r#"
+var window = globalThis, self = globalThis, global = globalThis;
+var noop = function () {};
+var document = {
+    cookie: '', referrer: '', title: '',
+    getElementById: function () { return null; },
+    querySelector: function () { return null; },
+    querySelectorAll: function () { return []; },
+    getElementsByTagName: function () { return []; },
+    createElement: function () { return { style: {}, setAttribute: noop, appendChild: noop }; },
+    addEventListener: noop,
+};
+var navigator = { userAgent: '', platform: '', language: 'zh-CN' };
+var location = { href: '', host: '', hostname: '', origin: '', pathname: '/', search: '', hash: '', protocol: 'https:' };
+var storage = { getItem: function () { return null; }, setItem: noop, removeItem: noop };
+var localStorage = storage, sessionStorage = storage;
+var console = { log: noop, info: noop, warn: noop, error: noop, debug: noop };
+var setTimeout = function () { return 0; }, setInterval = setTimeout, clearTimeout = noop, clearInterval = noop;
+var addEventListener = noop;
+"#;
+
+/// 发给沙箱子进程的任务
+#[derive(Serialize, Deserialize)]
+struct WorkerInput {
+    scripts: Vec<String>,
+    global: String,
+}
+
+/// 子进程输出的最后一行
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WorkerOutput {
+    Ok(Value),
+    Error(String),
+}
+
+/// 执行脚本后读取全局变量，如 `window._ROUTER_DATA`
+///
+/// `undefined` 转为 `null`，函数被丢弃，`Date` 转为 ISO 字符串。
+/// 脚本在赋值之后因缺少浏览器环境报错时，只要变量已赋值就不影响结果。
+pub async fn eval_global(script: &str, global: &str) -> Result<Value> {
+    if let Ok(value) = extract_js_literal(script, global) {
+        return Ok(value);
+    }
+    eval_scripts(vec![script.to_string()], global).await
+}
+
+/// 在页面的内联脚本中找到给 `global` 赋值的脚本并执行
+pub async fn eval_page_global(html: &str, global: &str) -> Result<Value> {
+    if let Ok(value) = extract_js_literal(html, global) {
+        return Ok(value);
+    }
+    let name = global.rsplit('.').next().unwrap_or(global);
+    let scripts: Vec<String> = inline_scripts(html)
+        .into_iter()
+        .filter(|script| script.contains(name))
+        .collect();
+    if scripts.is_empty() {
+        return Err(anyhow!("页面中没有给 {} 赋值的脚本", global));
+    }
+    eval_scripts(scripts, global).await
+}
+
+/// 页面中所有内联脚本的源码
+fn inline_scripts(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("script:not([src])").expect("valid selector");
+    document
+        .select(&selector)
+        .map(|element| element.text().collect::<String>())
+        .filter(|text| !text.trim().is_empty())
+        .collect()
+}
+
+fn eval_slots() -> &'static Semaphore {
+    static SLOTS: OnceLock<Semaphore> = OnceLock::new();
+    SLOTS.get_or_init(|| Semaphore::new(MAX_CONCURRENT_EVALS))
+}
+
+async fn eval_scripts(scripts: Vec<String>, global: &str) -> Result<Value> {
+    let total: usize = scripts.iter().map(|s| s.len()).sum();
+    if total > MAX_SCRIPT_BYTES {
+        return Err(anyhow!("脚本过大: {} bytes", total));
+    }
+
+    let _permit = tokio::time::timeout(EVAL_TIMEOUT, eval_slots().acquire())
+        .await
+        .map_err(|_| anyhow!("等待脚本沙箱超时"))??;
+
+    let input = serde_json::to_vec(&WorkerInput { scripts, global: global.to_string() })?;
+    let mut child = worker_command()?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("启动脚本沙箱失败: {}", e))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("无法写入脚本沙箱"))?;
+
+    // 超时或调用方取消时 child 被丢弃，kill_on_drop 保证子进程随之结束
+    let output = tokio::time::timeout(EVAL_TIMEOUT, async move {
+        stdin.write_all(&input).await?;
+        drop(stdin);
+        child.wait_with_output().await
+    })
+    .await
+    .map_err(|_| anyhow!("脚本执行超时"))??;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last_line = stdout.lines().rev().find(|line| !line.trim().is_empty());
+    match last_line.and_then(|line| serde_json::from_str::<WorkerOutput>(line).ok()) {
+        Some(WorkerOutput::Ok(value)) => Ok(value),
+        Some(WorkerOutput::Error(message)) => Err(anyhow!(message)),
+        None => Err(anyhow!("脚本执行异常终止（{}），可能超出内存上限", output.status)),
+    }
+}
+
+fn worker_command() -> Result<Command> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.env(WORKER_ENV, "1");
+    // 单元测试中当前程序是测试二进制，由 tests::sandbox_worker 充当入口
+    #[cfg(test)]
+    command.args(["--exact", "js::tests::sandbox_worker", "--nocapture", "--quiet", "--test-threads=1"]);
+    Ok(command)
+}
+
+/// 沙箱子进程入口：从标准输入读取任务，执行后把结果作为最后一行写到标准输出
+pub fn run_worker() -> ! {
+    limit_memory();
+
+    let mut input = String::new();
+    let output = match std::io::stdin().read_to_string(&mut input) {
+        Ok(_) => match serde_json::from_str::<WorkerInput>(&input) {
+            Ok(input) => run_in_sandbox_thread(input),
+            Err(e) => Err(anyhow!("沙箱任务格式错误: {}", e)),
+        },
+        Err(e) => Err(anyhow!("读取沙箱任务失败: {}", e)),
+    };
+    let output = match output {
+        Ok(value) => WorkerOutput::Ok(value),
+        Err(e) => WorkerOutput::Error(e.to_string()),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    let written = serde_json::to_string(&output)
+        .map_err(std::io::Error::from)
+        .and_then(|line| writeln!(stdout, "\n{}", line))
+        .and_then(|_| stdout.flush());
+    std::process::exit(if written.is_ok() { 0 } else { 1 })
+}
+
+/// 限制子进程的堆内存，超出时分配失败，进程终止
+#[cfg(unix)]
+fn limit_memory() {
+    let limit = libc::rlimit {
+        rlim_cur: MAX_MEMORY_BYTES as libc::rlim_t,
+        rlim_max: MAX_MEMORY_BYTES as libc::rlim_t,
+    };
+    // SAFETY: 只传入栈上有效的 rlimit 结构，不涉及其他内存
+    if unsafe { libc::setrlimit(libc::RLIMIT_DATA, &limit) } != 0 {
+        // 无法限制内存时拒绝执行
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_memory() {}
+
+/// 解析深层嵌套的字面量需要较大的栈，在单独的线程中执行
+fn run_in_sandbox_thread(input: WorkerInput) -> Result<Value> {
+    std::thread::Builder::new()
+        .name("js-sandbox".to_string())
+        .stack_size(SANDBOX_STACK_SIZE)
+        .spawn(move || run(&input.scripts, &input.global))?
+        .join()
+        .map_err(|_| anyhow!("脚本执行异常终止"))?
+}
+
+/// 依次执行脚本，变量赋值后立即返回
+fn run(scripts: &[String], global: &str) -> Result<Value> {
+    let mut context = Context::default();
+    let limits = context.runtime_limits_mut();
+    limits.set_loop_iteration_limit(LOOP_ITERATION_LIMIT);
+    limits.set_recursion_limit(RECURSION_LIMIT);
+
+    context
+        .eval(Source::from_bytes(PRELUDE))
+        .map_err(|e| anyhow!("初始化脚本环境失败: {}", e))?;
+
+    // 变量未声明时返回 undefined，stringify 本身的错误（如循环引用）照常抛出
+    let read = format!(
+        "(function () {{ var value; try {{ value = {}; }} catch (e) {{ return undefined; }} \
+         if (value === undefined) return undefined; \
+         return JSON.stringify(value, function (key, v) {{ return v === undefined ? null : v; }}); }})()",
+        global
+    );
+
+    let mut last_error = None;
+    for script in scripts {
+        if let Err(e) = context.eval(Source::from_bytes(script)) {
+            last_error = Some(e.to_string());
+        }
+        let output = context
+            .eval(Source::from_bytes(&read))
+            .map_err(|e| anyhow!("读取 {} 失败: {}", global, e))?;
+        let Some(text) = output.as_string() else {
+            continue;
+        };
+        let text = text.to_std_string_escaped();
+        if text.len() > MAX_OUTPUT_BYTES {
+            return Err(anyhow!("{} 过大: {} bytes", global, text.len()));
+        }
+        return Ok(serde_json::from_str(&text)?);
+    }
+
+    match last_error {
+        Some(e) => Err(anyhow!("执行脚本失败，{} 未赋值: {}", global, e)),
+        None => Err(anyhow!("脚本没有给 {} 赋值", global)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 沙箱子进程在测试二进制中的入口，普通测试运行时什么也不做
+    #[test]
+    fn sandbox_worker() {
+        if std::env::var_os(WORKER_ENV).is_some() {
+            run_worker();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_eval_page_global() {
+        let html = r#"<html><head><script src="/app.js"></script><script>var other = 1;</script>
+            <script>window.__INITIAL_STATE__ = {note: {id: '1', desc: "a</b>", cover: undefined, list: [undefined, NaN],
+                time: new Date(0), fn: function () {}}}; document.getElementById('app').innerHTML = '';</script>
+            </head></html>"#;
+        let value = eval_page_global(html, "window.__INITIAL_STATE__").await.unwrap();
+        assert_eq!(value.pointer("/note/id").and_then(|v| v.as_str()), Some("1"));
+        assert_eq!(value.pointer("/note/desc").and_then(|v| v.as_str()), Some("a</b>"));
+        assert_eq!(value.pointer("/note/cover"), Some(&Value::Null));
+        assert_eq!(value.pointer("/note/list"), Some(&serde_json::json!([null, null])));
+        assert_eq!(value.pointer("/note/time").and_then(|v| v.as_str()), Some("1970-01-01T00:00:00.000Z"));
+        assert!(value.pointer("/note/fn").is_none());
+
+        let value = eval_global("var $render_data = [{status: {id: 5}}][0] || {};", "$render_data").await.unwrap();
+        assert_eq!(value.pointer("/status/id").and_then(|v| v.as_i64()), Some(5));
+
+        assert!(eval_page_global(html, "window._ROUTER_DATA").await.is_err());
+        assert!(eval_global("throw new Error('x'); var data = [1][0];", "data").await.is_err());
+        assert!(eval_global("while (true) {} var data = [1][0];", "data").await.is_err());
+        // 超出内存上限时子进程终止，不影响当前进程
+        let err = eval_global("var s = 'x'; for (var i = 0; i < 40; i++) { s = s + s; } var data = [s.length][0];", "data")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("内存"), "{}", err);
+    }
+}
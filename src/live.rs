@@ -0,0 +1,140 @@
+//! 直播流转发的公共部分：平台所需的请求头与 HLS 播放列表改写
+//!
+//! 媒体代理和录制都从这里创建拉流客户端，保证带上各平台校验的 Referer。
+
+use crate::models::VideoSource;
+use crate::upstream::apply_proxy;
+use anyhow::{anyhow, Result};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, ORIGIN, REFERER};
+use reqwest::Client;
+use std::time::Duration;
+use url::Url;
+
+pub const LIVE_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36";
+
+/// 拉流时等待响应或下一块数据的最长时间，超过视为断流
+pub const STREAM_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 拉流时平台校验的来源页
+pub fn stream_referer(source: Option<VideoSource>) -> Option<&'static str> {
+    match source? {
+        VideoSource::BiliBili => Some("https://live.bilibili.com/"),
+        VideoSource::HuYa => Some("https://www.huya.com/"),
+        VideoSource::DouYin => Some("https://live.douyin.com/"),
+        _ => None,
+    }
+}
+
+/// 创建拉流客户端：只限制连接超时，流本身可以持续任意时长，
+/// 读取停滞由 [`with_read_timeout`] 和 [`read_timeout`] 按每次读取判断
+pub fn create_stream_client(source: Option<VideoSource>) -> Result<Client> {
+    let mut headers = HeaderMap::new();
+    if let Some(referer) = stream_referer(source) {
+        headers.insert(REFERER, HeaderValue::from_static(referer));
+        headers.insert(ORIGIN, HeaderValue::from_static(referer.trim_end_matches('/')));
+    }
+
+    let builder = Client::builder()
+        .user_agent(LIVE_USER_AGENT)
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(10));
+
+    apply_proxy(builder, source)?
+        .build()
+        .map_err(|e| anyhow!("创建HTTP客户端失败: {}", e))
+}
+
+/// 等待一次拉流操作，超过 [`STREAM_READ_TIMEOUT`] 时返回错误
+pub async fn with_read_timeout<T, E>(
+    future: impl std::future::Future<Output = std::result::Result<T, E>>,
+) -> Result<T>
+where
+    E: Into<anyhow::Error>,
+{
+    match tokio::time::timeout(STREAM_READ_TIMEOUT, future).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(anyhow!("直播流{}秒内没有响应", STREAM_READ_TIMEOUT.as_secs())),
+    }
+}
+
+/// 给数据流的每次读取加上超时，超时后产生一个错误并结束
+pub fn read_timeout<S, T, E>(stream: S) -> BoxStream<'static, Result<T>>
+where
+    S: Stream<Item = std::result::Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Into<anyhow::Error>,
+{
+    futures::stream::unfold(Some(Box::pin(stream)), |stream| async move {
+        let mut stream = stream?;
+        match with_read_timeout(async { stream.next().await.transpose() }).await {
+            Ok(Some(item)) => Some((Ok(item), Some(stream))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+    .boxed()
+}
+
+/// 是否为 HLS 播放列表地址
+pub fn is_hls_url(url: &str) -> bool {
+    Url::parse(url)
+        .map(|u| u.path().ends_with(".m3u8"))
+        .unwrap_or(false)
+}
+
+/// 把播放列表中的分片、子列表和密钥地址解析为绝对地址后交给 `map` 改写
+pub fn rewrite_hls_playlist(playlist: &str, base: &Url, map: impl Fn(&str) -> String) -> String {
+    let resolve = |uri: &str| {
+        base.join(uri)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| uri.to_string())
+    };
+
+    let mut out = String::with_capacity(playlist.len());
+    for line in playlist.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            out.push_str(line);
+        } else if trimmed.starts_with('#') {
+            out.push_str(&rewrite_uri_attribute(trimmed, |uri| map(&resolve(uri))));
+        } else {
+            out.push_str(&map(&resolve(trimmed)));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// 改写 `#EXT-X-KEY`、`#EXT-X-MAP` 等标签中的 `URI="..."` 属性
+fn rewrite_uri_attribute(tag: &str, map: impl Fn(&str) -> String) -> String {
+    const ATTR: &str = "URI=\"";
+    let Some(start) = tag.find(ATTR).map(|i| i + ATTR.len()) else {
+        return tag.to_string();
+    };
+    let Some(len) = tag[start..].find('"') else {
+        return tag.to_string();
+    };
+    format!("{}{}{}", &tag[..start], map(&tag[start..start + len]), &tag[start + len..])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_hls_playlist() {
+        let base = Url::parse("https://cdn.example.com/live/room/index.m3u8?token=1").unwrap();
+        let playlist = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n#EXTINF:2.0,\nseg-1.ts?t=2\n\n#EXTINF:2.0,\nhttps://other.example.com/seg-2.ts\n";
+        let out = rewrite_hls_playlist(playlist, &base, |u| format!("/p?u={}", u));
+
+        assert_eq!(
+            out,
+            "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"/p?u=https://cdn.example.com/live/room/key.bin\"\n#EXTINF:2.0,\n/p?u=https://cdn.example.com/live/room/seg-1.ts?t=2\n\n#EXTINF:2.0,\n/p?u=https://other.example.com/seg-2.ts\n"
+        );
+        assert!(is_hls_url(base.as_str()));
+        assert!(!is_hls_url("https://cdn.example.com/live/room.flv?wsSecret=1"));
+    }
+}
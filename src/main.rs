@@ -1,9 +1,20 @@
+mod capture;
+mod config;
+mod credentials;
+mod danmaku;
+mod js;
+mod live;
 mod models;
 mod parser;
+mod recorder;
+mod replay;
 mod server;
+mod subtitle;
+mod upstream;
 mod utils;
 
 use clap::{Parser as ClapParser, Subcommand};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(ClapParser)]
@@ -28,13 +39,116 @@ enum Commands {
         port: u16,
     },
     Parse {
+        #[arg(required_unless_present_any = ["from_file", "replay"])]
+        url: Option<String>,
+        /// 同时获取顶层评论，可指定最多条数（默认50，0 表示全部）
+        #[arg(long, num_args = 0..=1, default_missing_value = "50", conflicts_with = "from_file")]
+        comments: Option<usize>,
+        /// 从保存的页面或接口响应离线解析，需同时指定 --source
+        #[arg(long, requires = "source", conflicts_with = "url")]
+        from_file: Option<std::path::PathBuf>,
+        /// 离线解析的平台，如 douyin，或规则文件中的平台名
+        #[arg(long, requires = "from_file")]
+        source: Option<String>,
+        /// 离线解析时内容中没有ID的平台可传入视频ID
+        #[arg(long, requires = "from_file")]
+        id: Option<String>,
+        /// 录制解析过程中的上游请求，保存为测试夹具，如 fixtures/douyin/video.json
+        #[arg(long, conflicts_with = "from_file")]
+        record: Option<std::path::PathBuf>,
+        /// 回放夹具中录制的请求重新解析，不发起网络请求
+        #[arg(long, conflicts_with_all = ["url", "from_file", "record"])]
+        replay: Option<std::path::PathBuf>,
+    },
+    /// 逐项获取合集、UP主空间、收藏夹或创作者主页中的作品
+    #[command(alias = "user")]
+    List {
+        url: String,
+        /// 从指定游标继续（每项输出中都带有游标）
+        #[arg(long)]
+        cursor: Option<String>,
+        /// 最多获取的作品数，0 表示全部
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        /// 每行输出一个作品的JSON，便于归档
+        #[arg(long)]
+        json: bool,
+    },
+    /// 录制直播间（虎牙、抖音、哔哩哔哩），每行输出一个JSON状态
+    Record {
+        url: String,
+        /// 分段文件保存目录
+        #[arg(short, long, default_value = "recordings")]
+        output: std::path::PathBuf,
+        /// 直播流格式
+        #[arg(long, default_value = "flv", value_parser = ["flv", "hls"])]
+        format: String,
+        /// 清晰度名称（如 原画、蓝光），默认最高清晰度
+        #[arg(long)]
+        quality: Option<String>,
+        /// 单个分段的最大大小（MB），0 表示不按大小切分
+        #[arg(long, default_value = "0")]
+        segment_size: u64,
+        /// 单个分段的最长时长（秒），0 表示不按时长切分
+        #[arg(long, default_value = "0")]
+        segment_duration: u64,
+        /// 未开播时检查直播状态的间隔（秒）
+        #[arg(long, default_value = "30")]
+        poll_interval: u64,
+    },
+    /// 下载哔哩哔哩、AcFun 视频弹幕并转换为 ASS 字幕
+    Danmaku {
+        url: String,
+        /// 输出文件，默认为 <内容ID>.ass
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// 标准弹幕字号（8-200）
+        #[arg(long, default_value = "48", value_parser = clap::value_parser!(u32).range(8..=200))]
+        font_size: u32,
+        /// 滚动弹幕横穿屏幕的时长（秒）
+        #[arg(long, default_value = "8", value_parser = parse_positive_secs)]
+        duration: f64,
+        /// 弹幕可占用的屏幕高度比例（0-1）
+        #[arg(long, default_value = "0.75")]
+        density: f64,
+        /// 画布宽度，应与视频宽高比一致（最大 8192）
+        #[arg(long, default_value = "1920", value_parser = clap::value_parser!(u32).range(1..=8192))]
+        width: u32,
+        #[arg(long, default_value = "1080", value_parser = clap::value_parser!(u32).range(1..=8192))]
+        height: u32,
+    },
+    /// 下载哔哩哔哩视频字幕（CC 或 AI 字幕）
+    Subtitle {
         url: String,
+        /// 语言代码（如 zh-CN、ai-zh），默认优先人工字幕
+        #[arg(long)]
+        lang: Option<String>,
+        #[arg(long, default_value = "srt", value_parser = ["srt", "vtt"])]
+        format: String,
+        /// 输出文件，默认为 <内容ID>.<语言>.<格式>
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
     },
     Platforms,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    if std::env::var_os(js::WORKER_ENV).is_some() {
+        js::run_worker();
+    }
+
+    dotenvy::dotenv().ok();
+
+    let cli = Cli::parse();
+
+    // 录制时标准输出只留给JSON状态行，日志改写到标准错误
+    let log_writer = if matches!(cli.command, Some(Commands::Record { .. })) {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -47,18 +161,58 @@ async fn main() -> anyhow::Result<()> {
                 .with_thread_names(false)
                 .with_file(false)
                 .with_line_number(false)
+                .with_writer(log_writer)
                 .compact(),
         )
         .init();
 
-    let cli = Cli::parse();
+    parser::rule::init();
+    parser::plugin::init();
 
     match cli.command {
         Some(Commands::Serve { port }) => {
             server::start_server(port).await?;
         }
-        Some(Commands::Parse { url }) => {
-            parse_video(&url).await?;
+        Some(Commands::Parse { url, comments, from_file, source, id, record, replay }) => {
+            match (from_file, source, url, record, replay) {
+                (_, _, _, _, Some(path)) => replay_fixture(&path).await?,
+                (Some(path), Some(source), _, _, _) => parse_offline_file(&path, &source, id.as_deref()).await?,
+                (_, _, Some(url), Some(path), _) => record_fixture(&url, &path).await?,
+                (_, _, Some(url), None, _) => parse_video(&url, comments).await?,
+                _ => unreachable!("clap 已校验参数"),
+            }
+        }
+        Some(Commands::List { url, cursor, limit, json }) => {
+            list_items(&url, cursor.as_deref(), limit, json).await?;
+        }
+        Some(Commands::Record { url, output, format, quality, segment_size, segment_duration, poll_interval }) => {
+            let url = utils::extract_url_from_string(&url)?;
+            recorder::prepare_output_dir(&output).await?;
+            let options = recorder::RecordOptions {
+                output_dir: output,
+                format: if format == "hls" { models::StreamFormat::Hls } else { models::StreamFormat::Flv },
+                quality,
+                segment_bytes: segment_size * 1024 * 1024,
+                segment_duration: (segment_duration > 0).then(|| std::time::Duration::from_secs(segment_duration)),
+                poll_interval: std::time::Duration::from_secs(poll_interval.max(1)),
+            };
+            recorder::record(&url, options).await?;
+        }
+        Some(Commands::Danmaku { url, output, font_size, duration, density, width, height }) => {
+            let options = danmaku::AssOptions {
+                width,
+                height,
+                font_size,
+                scroll_duration: duration,
+                density,
+                ..Default::default()
+            }
+            .clamped();
+            download_danmaku(&url, output, &options).await?;
+        }
+        Some(Commands::Subtitle { url, lang, format, output }) => {
+            let format = subtitle::SubtitleFormat::from_str(&format).unwrap_or(subtitle::SubtitleFormat::Srt);
+            download_subtitle(&url, lang.as_deref(), format, output).await?;
         }
         Some(Commands::Platforms) => {
             list_platforms();
@@ -72,7 +226,7 @@ async fn main() -> anyhow::Result<()> {
 }
 
 /// 解析视频并打印结果
-async fn parse_video(url_text: &str) -> anyhow::Result<()> {
+async fn parse_video(url_text: &str, comments: Option<usize>) -> anyhow::Result<()> {
     use crate::utils::extract_url_from_string;
     use crate::parser::parse_video_share_url;
 
@@ -82,36 +236,286 @@ async fn parse_video(url_text: &str) -> anyhow::Result<()> {
     let url = extract_url_from_string(url_text)?;
     println!("📎 提取到URL: {}\n", url);
 
+    // 合集、音乐、主页等列表链接按顺序列出其中的作品
+    if crate::parser::is_listing_url(&url) {
+        println!("📚 这是列表链接，按顺序获取其中的作品\n");
+        return list_items(&url, None, 20, false).await;
+    }
+
     // 解析视频
     match parse_video_share_url(&url).await {
         Ok(info) => {
-            println!("✅ 解析成功!\n");
-            println!("📺 标题: {}", info.title);
-            println!("👤 作者: {} ({})", info.author.name, info.author.uid);
+            print_video_info(&info)?;
             
-            if let Some(video_url) = &info.video_url {
-                println!("🎬 视频地址: {}", video_url);
+            if let Some(limit) = comments {
+                print_comments(&info, limit).await?;
             }
-            
-            if let Some(cover_url) = &info.cover_url {
-                println!("🖼️  封面地址: {}", cover_url);
+        }
+        Err(e) => {
+            println!("❌ 解析失败: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// 打印解析结果，最后输出JSON
+fn print_video_info(info: &models::VideoParseInfo) -> anyhow::Result<()> {
+    println!("✅ 解析成功!\n");
+    println!("📺 标题: {}", info.title);
+    println!("👤 作者: {} ({})", info.author.name, info.author.uid);
+    if let Some(content_id) = &info.content_id {
+        println!("🆔 平台: {}  内容ID: {}", info.source, content_id);
+    }
+    if let Some(canonical_url) = &info.canonical_url {
+        println!("🔗 规范链接: {}", canonical_url);
+    }
+    
+    if let Some(video_url) = &info.video_url {
+        println!("🎬 视频地址: {}", video_url);
+    }
+    
+    if let Some(live) = &info.live {
+        println!("📡 直播状态: {:?}  房间号: {}", live.status, live.room_id);
+        if let Some(online) = live.online_count {
+            println!("👥 在线人数: {}", online);
+        }
+        for stream in &live.streams {
+            println!("  [{:?} {}] {}", stream.format, stream.quality, stream.url);
+        }
+    }
+    
+    if let Some(music_url) = &info.music_url {
+        println!("🎵 音乐地址: {}", music_url);
+    }
+    if let Some(music) = &info.music {
+        println!("🎶 背景音乐: {} - {}", music.title, music.author);
+    }
+    
+    if let Some(cover_url) = &info.cover_url {
+        println!("🖼️  封面地址: {}", cover_url);
+    }
+    
+    if !info.images.is_empty() {
+        println!("🎨 图集 ({} 张):", info.images.len());
+        for (i, img) in info.images.iter().enumerate() {
+            println!("  [{}] {}", i + 1, img.url);
+            if let Some(live_photo) = &img.live_photo_url {
+                println!("      LivePhoto: {}", live_photo);
             }
-            
-            if !info.images.is_empty() {
-                println!("🎨 图集 ({} 张):", info.images.len());
-                for (i, img) in info.images.iter().enumerate() {
-                    println!("  [{}] {}", i + 1, img.url);
-                    if let Some(live_photo) = &img.live_photo_url {
-                        println!("      LivePhoto: {}", live_photo);
-                    }
-                }
+        }
+    }
+
+    if !info.subtitles.is_empty() {
+        println!("💬 字幕 ({} 条):", info.subtitles.len());
+        for track in &info.subtitles {
+            let kind = if track.ai { "AI" } else { "CC" };
+            println!("  [{}] {} {} - {} 句", track.lang, track.lang_name, kind, track.cues.len());
+        }
+    }
+
+    println!("\n📋 JSON格式:");
+    println!("{}", serde_json::to_string_pretty(info)?);
+
+    Ok(())
+}
+
+/// 从保存的页面或接口响应中离线解析，不发起网络请求
+async fn parse_offline_file(path: &std::path::Path, source: &str, video_id: Option<&str>) -> anyhow::Result<()> {
+    println!("📄 正在离线解析 {} ({})...\n", path.display(), source);
+
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("读取 {} 失败: {}", path.display(), e))?;
+
+    match parser::parse_offline(source, &content, video_id).await {
+        Ok(info) => print_video_info(&info)?,
+        Err(e) => println!("❌ 解析失败: {}", e),
+    }
+
+    Ok(())
+}
+
+/// 解析并录制上游请求，保存为回放测试使用的夹具和解析结果快照
+async fn record_fixture(url_text: &str, path: &std::path::Path) -> anyhow::Result<()> {
+    let url = utils::extract_url_from_string(url_text)?;
+    println!("⏺️  正在录制 {} ...\n", url);
+
+    let cassette = std::sync::Arc::new(replay::Cassette::record());
+    let info = replay::scope(cassette.clone(), parser::parse_video_share_url(&url)).await?;
+    print_video_info(&info)?;
+
+    let fixture = replay::Fixture { url, synthetic: false, exchanges: cassette.exchanges() };
+    replay::save_fixture(path, &fixture, &info)?;
+    println!("\n💾 已保存 {} 个请求到 {}，快照 {}", fixture.exchanges.len(), path.display(), replay::golden_path(path).display());
+
+    Ok(())
+}
+
+/// 回放夹具并与快照比对
+async fn replay_fixture(path: &std::path::Path) -> anyhow::Result<()> {
+    let fixture = replay::load_fixture(path)?;
+    println!("⏯️  正在回放 {} ({} 个请求)...\n", fixture.url, fixture.exchanges.len());
+
+    let info = replay::replay_fixture(&fixture).await?;
+    print_video_info(&info)?;
+
+    match std::fs::read_to_string(replay::golden_path(path)) {
+        Ok(golden) if serde_json::from_str::<serde_json::Value>(&golden)? == serde_json::to_value(&info)? => {
+            println!("\n✅ 与快照一致");
+        }
+        Ok(_) => println!("\n❌ 与快照 {} 不一致", replay::golden_path(path).display()),
+        Err(_) => println!("\n⚠️ 没有快照文件"),
+    }
+
+    Ok(())
+}
+
+/// 逐页获取评论并打印，最后输出JSON便于导出
+async fn print_comments(info: &models::VideoParseInfo, limit: usize) -> anyhow::Result<()> {
+    let (Some(source), Some(content_id)) = (models::VideoSource::from_str(&info.source), info.content_id.as_deref()) else {
+        println!("\n❌ 无法获取评论: 缺少平台或内容ID");
+        return Ok(());
+    };
+    let limit = if limit == 0 { usize::MAX } else { limit };
+
+    println!("\n💬 评论:");
+    let mut comments = Vec::new();
+    let mut cursor: Option<String> = None;
+    while comments.len() < limit {
+        let page = match parser::fetch_comments(source, content_id, cursor.as_deref()).await {
+            Ok(page) => page,
+            Err(e) => {
+                println!("❌ 评论获取失败: {}", e);
+                break;
             }
+        };
+        if comments.is_empty() {
+            if let Some(total) = page.total {
+                println!("共 {} 条", total);
+            }
+        }
+        for comment in page.items.into_iter().take(limit - comments.len()) {
+            println!(
+                "  {} 👍{} 💬{}  {}: {}",
+                comment.publish_time.as_deref().unwrap_or("-"),
+                comment.like_count.unwrap_or(0),
+                comment.reply_count.unwrap_or(0),
+                comment.author.name,
+                comment.text
+            );
+            comments.push(comment);
+        }
+        match page.cursor {
+            Some(next) if page.has_more => cursor = Some(next),
+            _ => break,
+        }
+    }
 
-            println!("\n📋 JSON格式:");
-            println!("{}", serde_json::to_string_pretty(&info)?);
+    println!("\n📋 评论JSON:");
+    println!("{}", serde_json::to_string_pretty(&comments)?);
+    Ok(())
+}
+
+/// 大于 0 的秒数
+fn parse_positive_secs(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(secs),
+        _ => Err(format!("{} 不是大于 0 的秒数", s)),
+    }
+}
+
+/// 获取弹幕并写入 ASS 文件
+async fn download_danmaku(url_text: &str, output: Option<std::path::PathBuf>, options: &danmaku::AssOptions) -> anyhow::Result<()> {
+    let url = utils::extract_url_from_string(url_text)?;
+    println!("🔍 正在获取弹幕...\n");
+
+    let (_, content_id, items) = parser::fetch_danmaku_by_url(&url).await?;
+    let output = output.unwrap_or_else(|| format!("{}.ass", content_id).into());
+    tokio::fs::write(&output, danmaku::to_ass(&items, options)).await?;
+
+    println!("✅ 共 {} 条弹幕，已保存到 {}", items.len(), output.display());
+    Ok(())
+}
+
+/// 解析视频并把选中的字幕轨道写入文件
+async fn download_subtitle(
+    url_text: &str,
+    lang: Option<&str>,
+    format: subtitle::SubtitleFormat,
+    output: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    let url = utils::extract_url_from_string(url_text)?;
+    println!("🔍 正在获取字幕...\n");
+
+    let info = parser::parse_video_share_url(&url).await?;
+    if info.subtitles.is_empty() {
+        anyhow::bail!("该视频没有字幕");
+    }
+    let Some(track) = subtitle::select_track(&info.subtitles, lang) else {
+        let langs: Vec<&str> = info.subtitles.iter().map(|t| t.lang.as_str()).collect();
+        anyhow::bail!("没有语言为 {} 的字幕，可选: {}", lang.unwrap_or(""), langs.join(", "));
+    };
+
+    let content_id = info.content_id.as_deref().unwrap_or("subtitle");
+    let output = output.unwrap_or_else(|| format!("{}.{}.{}", content_id, track.lang, format.extension()).into());
+    tokio::fs::write(&output, subtitle::render(&track.cues, format)).await?;
+
+    println!("✅ {} 共 {} 句，已保存到 {}", track.lang_name, track.cues.len(), output.display());
+    Ok(())
+}
+
+/// 以流的形式打印列表项，中断后可用最后输出的游标继续
+async fn list_items(url_text: &str, cursor: Option<&str>, limit: usize, json: bool) -> anyhow::Result<()> {
+    use crate::parser::listing_stream;
+    use crate::utils::extract_url_from_string;
+    use futures::StreamExt;
+
+    let url = extract_url_from_string(url_text)?;
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    let mut stream = listing_stream(&url, cursor)?.take(limit);
+
+    let mut count = 0;
+    let mut last_cursor = cursor.map(str::to_string);
+    while let Some(item) = stream.next().await {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                eprintln!("❌ 获取失败: {}", e);
+                break;
+            }
+        };
+
+        count += 1;
+        if json {
+            println!("{}", serde_json::to_string(&item)?);
+        } else {
+            if let Some(profile) = &item.profile {
+                println!("👤 {} ({})", profile.author.name, profile.author.uid);
+                if !profile.signature.is_empty() {
+                    println!("   {}", profile.signature);
+                }
+                println!(
+                    "   作品 {}  粉丝 {}  获赞 {}\n",
+                    profile.aweme_count.unwrap_or_default(),
+                    profile.follower_count.unwrap_or_default(),
+                    profile.total_favorited.unwrap_or_default()
+                );
+            }
+            println!(
+                "[{}] {} {}",
+                count,
+                item.info.title,
+                item.info.canonical_url.as_deref().unwrap_or_default()
+            );
         }
-        Err(e) => {
-            println!("❌ 解析失败: {}", e);
+        last_cursor = item.cursor;
+    }
+
+    if !json {
+        match &last_cursor {
+            Some(cursor) => println!("\n⏭️  共 {} 项，继续获取: --cursor '{}'", count, cursor),
+            None => println!("\n✅ 共 {} 项，已到末尾", count),
         }
     }
 
@@ -14,30 +14,242 @@ pub struct ImgInfo {
     pub live_photo_url: Option<String>,
 }
 
+/// 内容元数据，上游未提供的字段留空
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ContentMetadata {
+    /// 时长（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// 发布时间（RFC3339）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub like_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub play_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collect_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hashtags: Vec<String>,
+}
+
+impl ContentMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 内容类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentType {
+    #[default]
+    Video,
+    /// 图集
+    Gallery,
+    Audio,
+    Live,
+    /// 视频与图片混合
+    Mixed,
+}
+
+/// 背景音乐信息
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MusicInfo {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+}
+
+impl MusicInfo {
+    /// 标题和作者都为空时返回 None
+    pub fn from_parts(title: Option<&str>, author: Option<&str>) -> Option<Self> {
+        let title = title.unwrap_or("").trim().to_string();
+        let author = author.unwrap_or("").trim().to_string();
+        (!title.is_empty() || !author.is_empty()).then_some(Self { title, author })
+    }
+}
+
+/// 一句字幕，时间单位为秒
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SubtitleCue {
+    pub from: f64,
+    pub to: f64,
+    pub content: String,
+}
+
+/// 字幕轨道
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SubtitleTrack {
+    /// 语言代码，如 `zh-CN`、`ai-zh`
+    pub lang: String,
+    /// 语言名称，如 `中文（中国）`
+    pub lang_name: String,
+    /// 是否为 AI 生成的字幕
+    pub ai: bool,
+    /// 平台原始字幕地址（JSON）
+    pub url: String,
+    #[serde(default)]
+    pub cues: Vec<SubtitleCue>,
+}
+
+/// 直播间状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveStatus {
+    Live,
+    #[default]
+    Offline,
+    /// 轮播/回放
+    Replay,
+}
+
+/// 直播流格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    Flv,
+    Hls,
+}
+
+/// 某一清晰度的直播流地址
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LiveStream {
+    /// 清晰度名称，如 `原画`、`超清`
+    pub quality: String,
+    pub format: StreamFormat,
+    pub url: String,
+}
+
+/// 直播间信息
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LiveInfo {
+    pub room_id: String,
+    pub status: LiveStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online_count: Option<u64>,
+    /// 按清晰度从高到低排列，未开播时为空
+    #[serde(default)]
+    pub streams: Vec<LiveStream>,
+}
+
+impl LiveInfo {
+    /// 指定格式中清晰度最高的流
+    pub fn best_stream(&self, format: StreamFormat) -> Option<&LiveStream> {
+        self.streams.iter().find(|s| s.format == format)
+    }
+}
+
 /// 视频解析信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoParseInfo {
+    /// 平台标识，如 `douyin`
+    #[serde(default)]
+    pub source: String,
+    /// 平台内的内容ID（aweme_id、BV号、笔记ID等）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(default)]
+    pub content_type: ContentType,
     pub author: Author,
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub video_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub music_url: Option<String>,
+    /// 背景音乐的标题和作者
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music: Option<MusicInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cover_url: Option<String>,
     #[serde(default)]
     pub images: Vec<ImgInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ContentMetadata>,
+    /// 直播间信息，仅 `content_type` 为 `live` 时存在
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live: Option<LiveInfo>,
+    /// 字幕轨道（含正文），目前仅哔哩哔哩
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitles: Vec<SubtitleTrack>,
 }
 
 impl VideoParseInfo {
     pub fn new() -> Self {
         Self {
+            source: String::new(),
+            content_id: None,
+            canonical_url: None,
+            content_type: ContentType::Video,
             author: Author::default(),
             title: String::new(),
             video_url: None,
             music_url: None,
+            music: None,
             cover_url: None,
             images: Vec::new(),
+            metadata: None,
+            live: None,
+            subtitles: Vec::new(),
+        }
+    }
+
+    /// 设置元数据，全部字段为空时不输出
+    pub fn set_metadata(&mut self, metadata: ContentMetadata) {
+        self.metadata = (!metadata.is_empty()).then_some(metadata);
+    }
+
+    /// 设置平台和内容ID，同时生成规范链接
+    pub fn set_content_id(&mut self, source: VideoSource, id: impl Into<String>) {
+        let id = id.into();
+        if id.is_empty() {
+            return;
+        }
+        self.source = source.as_str().to_string();
+        self.canonical_url = Some(source.canonical_url(&id));
+        self.content_id = Some(id);
+    }
+
+    /// 设置直播间信息，`video_url` 取清晰度最高的FLV流（没有时取HLS）
+    pub fn set_live(&mut self, source: VideoSource, live: LiveInfo) {
+        self.source = source.as_str().to_string();
+        self.content_type = ContentType::Live;
+        self.content_id = Some(live.room_id.clone());
+        self.canonical_url = source.live_room_url(&live.room_id);
+        self.video_url = live.best_stream(StreamFormat::Flv)
+            .or_else(|| live.best_stream(StreamFormat::Hls))
+            .map(|s| s.url.clone());
+        self.live = Some(live);
+    }
+
+    /// 补全平台标识，并在解析器未指定时根据媒体推断内容类型
+    pub fn finish(&mut self, source: VideoSource) {
+        if self.source.is_empty() {
+            self.source = source.as_str().to_string();
+        }
+        self.infer_content_type();
+    }
+
+    /// 解析器未指定内容类型时根据媒体推断
+    pub fn infer_content_type(&mut self) {
+        if self.content_type == ContentType::Video {
+            self.content_type = match (self.video_url.is_some(), self.images.is_empty()) {
+                (true, false) => ContentType::Mixed,
+                (false, false) => ContentType::Gallery,
+                (false, true) if self.music_url.is_some() => ContentType::Audio,
+                _ => ContentType::Video,
+            };
         }
     }
 }
@@ -48,6 +260,113 @@ impl Default for VideoParseInfo {
     }
 }
 
+/// 创作者主页信息
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UserProfile {
+    pub author: Author,
+    #[serde(default)]
+    pub signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub following_count: Option<u64>,
+    /// 作品数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aweme_count: Option<u64>,
+    /// 获赞总数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_favorited: Option<u64>,
+}
+
+/// 分页列表的一页
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ListingPage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<UserProfile>,
+    pub items: Vec<VideoParseInfo>,
+    /// 下一页游标，传回接口即可继续翻页
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// 列表流中的一项，`cursor` 为从该项之后继续获取的位置，为空表示已到末尾
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListingItem {
+    #[serde(flatten)]
+    pub info: VideoParseInfo,
+    pub cursor: Option<String>,
+    /// 列表为创作者主页时，第一项附带主页信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<UserProfile>,
+}
+
+/// 顶层评论
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Comment {
+    pub id: String,
+    pub author: Author,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub like_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_count: Option<u64>,
+    /// 发布时间（RFC3339）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_time: Option<String>,
+}
+
+/// 一页评论
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommentPage {
+    /// 评论总数（含回复，按平台口径）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub items: Vec<Comment>,
+    /// 下一页游标，传回接口即可继续翻页
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// 弹幕显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DanmakuMode {
+    /// 从右向左滚动
+    #[default]
+    Scroll,
+    /// 顶部居中停留
+    Top,
+    /// 底部居中停留
+    Bottom,
+}
+
+impl DanmakuMode {
+    /// B站与A站通用的模式编码：1-3 滚动，4 底部，5 顶部，6 逆向滚动；高级与代码弹幕返回 None
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            1..=3 | 6 => Some(Self::Scroll),
+            4 => Some(Self::Bottom),
+            5 => Some(Self::Top),
+            _ => None,
+        }
+    }
+}
+
+/// 一条弹幕
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Danmaku {
+    /// 出现时间（秒）
+    pub time: f64,
+    pub mode: DanmakuMode,
+    /// 平台字号，25 为标准大小
+    pub size: u32,
+    /// RGB 颜色，如 0xFFFFFF
+    pub color: u32,
+    pub text: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum VideoSource {
     DouYin,
@@ -75,6 +394,31 @@ pub enum VideoSource {
 }
 
 impl VideoSource {
+    pub const ALL: [VideoSource; 22] = [
+        Self::DouYin,
+        Self::KuaiShou,
+        Self::PiPiXia,
+        Self::HuoShan,
+        Self::WeiBo,
+        Self::WeiShi,
+        Self::LvZhou,
+        Self::ZuiYou,
+        Self::QuanMin,
+        Self::XiGua,
+        Self::LiShiPin,
+        Self::PiPiGaoXiao,
+        Self::HuYa,
+        Self::AcFun,
+        Self::DouPai,
+        Self::MeiPai,
+        Self::QuanMinKGe,
+        Self::SixRoom,
+        Self::XinPianChang,
+        Self::HaoKan,
+        Self::RedBook,
+        Self::BiliBili,
+    ];
+
     /// 从字符串解析视频来源
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
@@ -158,9 +502,49 @@ impl VideoSource {
         }
     }
     
+    /// 由内容ID生成可再次解析的规范链接
+    pub fn canonical_url(&self, id: &str) -> String {
+        match self {
+            Self::DouYin => format!("https://www.douyin.com/video/{}", id),
+            Self::KuaiShou => format!("https://www.kuaishou.com/short-video/{}", id),
+            Self::PiPiXia => format!("https://h5.pipix.com/item/{}", id),
+            Self::HuoShan => format!("https://share.huoshan.com/hotsoon/s/?item_id={}", id),
+            // 视频号形如 1034:xxxx，其余为微博 mid
+            Self::WeiBo if id.contains(':') => format!("https://weibo.com/tv/show/{}", id),
+            Self::WeiBo => format!("https://weibo.com/detail/{}", id),
+            Self::WeiShi => format!("https://isee.weishi.qq.com/ws/app-pages/share/index.html?id={}", id),
+            Self::LvZhou => format!("https://m.oasis.weibo.cn/v1/h5/share?sid={}", id),
+            Self::ZuiYou => format!("https://share.xiaochuankeji.cn/hybrid/share/post?pid={}", id),
+            Self::QuanMin => format!("https://xspshare.baidu.com/?vid={}", id),
+            Self::XiGua => format!("https://www.ixigua.com/{}", id),
+            Self::LiShiPin => format!("https://www.pearvideo.com/detail_{}", id),
+            Self::PiPiGaoXiao => format!("https://h5.pipigx.com/pp/post/{}", id),
+            Self::HuYa => format!("https://v.huya.com/play/{}.html", id),
+            Self::AcFun => format!("https://www.acfun.cn/v/{}", id),
+            Self::DouPai => format!("https://doupai.cc/share?id={}", id),
+            Self::MeiPai => format!("https://www.meipai.com/media/{}", id),
+            Self::QuanMinKGe => format!("https://kg.qq.com/node/play?s={}", id),
+            Self::SixRoom => format!("https://m.6.cn/v/{}", id),
+            Self::XinPianChang => format!("https://www.xinpianchang.com/a{}", id),
+            Self::HaoKan => format!("https://haokan.baidu.com/v?vid={}", id),
+            Self::RedBook => format!("https://www.xiaohongshu.com/explore/{}", id),
+            Self::BiliBili => format!("https://www.bilibili.com/video/{}", id),
+        }
+    }
+
+    /// 直播间链接，不支持直播的平台返回 None
+    pub fn live_room_url(&self, room_id: &str) -> Option<String> {
+        match self {
+            Self::HuYa => Some(format!("https://www.huya.com/{}", room_id)),
+            Self::DouYin => Some(format!("https://live.douyin.com/{}", room_id)),
+            Self::BiliBili => Some(format!("https://live.bilibili.com/{}", room_id)),
+            _ => None,
+        }
+    }
+
     pub fn share_url_domains(&self) -> Vec<&'static str> {
         match self {
-            Self::DouYin => vec!["v.douyin.com", "www.iesdouyin.com", "www.douyin.com"],
+            Self::DouYin => vec!["v.douyin.com", "www.iesdouyin.com", "www.douyin.com", "live.douyin.com"],
             Self::KuaiShou => vec!["v.kuaishou.com", "www.kuaishou.com"],
             Self::PiPiXia => vec!["h5.pipix.com"],
             Self::HuoShan => vec!["share.huoshan.com"],
@@ -169,10 +553,10 @@ impl VideoSource {
             Self::LvZhou => vec!["weibo.cn"],
             Self::ZuiYou => vec!["share.xiaochuankeji.cn"],
             Self::QuanMin => vec!["xspshare.baidu.com"],
-            Self::XiGua => vec!["v.ixigua.com"],
+            Self::XiGua => vec!["v.ixigua.com", "www.ixigua.com"],
             Self::LiShiPin => vec!["www.pearvideo.com"],
             Self::PiPiGaoXiao => vec!["h5.pipigx.com"],
-            Self::HuYa => vec!["v.huya.com"],
+            Self::HuYa => vec!["v.huya.com", "www.huya.com", "m.huya.com"],
             Self::AcFun => vec!["www.acfun.cn"],
             Self::DouPai => vec!["doupai.cc"],
             Self::MeiPai => vec!["meipai.com"],
@@ -184,6 +568,34 @@ impl VideoSource {
             Self::BiliBili => vec!["bilibili.com", "b23.tv"],
         }
     }
+
+    /// 平台账号 Cookie 的作用域名（含子域名），`COOKIE_<平台>` 中的 Cookie 只发往这些域名
+    pub fn cookie_domains(&self) -> &'static [&'static str] {
+        match self {
+            Self::DouYin => &["douyin.com", "iesdouyin.com", "snssdk.com"],
+            Self::KuaiShou => &["kuaishou.com", "chenzhongtech.com"],
+            Self::PiPiXia => &["pipix.com"],
+            Self::HuoShan => &["huoshan.com"],
+            Self::WeiBo => &["weibo.com", "weibo.cn"],
+            Self::WeiShi => &["weishi.qq.com"],
+            Self::LvZhou => &["weibo.cn"],
+            Self::ZuiYou => &["xiaochuankeji.cn"],
+            Self::QuanMin => &["xspshare.baidu.com", "hao222.com"],
+            Self::XiGua => &["ixigua.com"],
+            Self::LiShiPin => &["pearvideo.com"],
+            Self::PiPiGaoXiao => &["pipigx.com", "ippzone.com"],
+            Self::HuYa => &["huya.com"],
+            Self::AcFun => &["acfun.cn"],
+            Self::DouPai => &["doupai.cc"],
+            Self::MeiPai => &["meipai.com"],
+            Self::QuanMinKGe => &["kg.qq.com"],
+            Self::SixRoom => &["6.cn"],
+            Self::XinPianChang => &["xinpianchang.com"],
+            Self::HaoKan => &["haokan.baidu.com", "haokan.hao123.com"],
+            Self::RedBook => &["xiaohongshu.com", "xhslink.com"],
+            Self::BiliBili => &["bilibili.com", "b23.tv"],
+        }
+    }
 }
 
 /// HTTP响应结构
@@ -193,6 +605,9 @@ pub struct HttpResponse<T> {
     pub msg: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
+    /// 解析失败时保存的调试记录ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capture_id: Option<String>,
 }
 
 impl<T> HttpResponse<T> {
@@ -201,6 +616,7 @@ impl<T> HttpResponse<T> {
             code: 200,
             msg: "解析成功".to_string(),
             data: Some(data),
+            capture_id: None,
         }
     }
 
@@ -209,7 +625,33 @@ impl<T> HttpResponse<T> {
             code: 201,
             msg,
             data: None,
+            capture_id: None,
         }
     }
+
+    pub fn with_capture(mut self, capture_id: Option<String>) -> Self {
+        self.capture_id = capture_id;
+        self
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_url() {
+        // 规范链接必须能被重新识别为同一平台
+        for source in VideoSource::ALL {
+            let url = source.canonical_url("123");
+            assert!(
+                source.share_url_domains().iter().any(|d| url.contains(d)),
+                "{} 的规范链接 {} 不在分享域名中",
+                source.as_str(),
+                url
+            );
+        }
+        assert_eq!(VideoSource::LiShiPin.canonical_url("1796002"), "https://www.pearvideo.com/detail_1796002");
+    }
+}
@@ -1,7 +1,9 @@
-use crate::models::VideoParseInfo;
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
-use anyhow::Result;
+use crate::js;
+use crate::models::{ContentMetadata, Danmaku, DanmakuMode, VideoParseInfo, VideoSource};
+use crate::parser::{DanmakuParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration, json_str, json_timestamp, json_u32};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use regex::Regex;
 use serde_json::Value;
@@ -11,57 +13,203 @@ pub struct AcfunParser;
 #[async_trait]
 impl VideoParser for AcfunParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_http_client()?;
+        let html = Self::fetch_page(share_url).await?;
+        let mut info = Self::extract_page_info(&html).await;
         
-        let html = client
-            .get(share_url)
-            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 11_0 like Mac OS X) AppleWebKit/604.1.38")
-            .send()
-            .await?
-            .text()
-            .await?;
+        // 从链接路径中提取acid，如 /v/ac36935385
+        let acid = Regex::new(r"/v/(ac\d+)")?
+            .captures(share_url)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default();
+        info.set_content_id(VideoSource::AcFun, acid);
+        
+        Ok(info)
+    }
+    
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        // acid格式: ac36935385
+        let req_url = format!("https://www.acfun.cn/v/{}", video_id);
+        self.parse_share_url(&req_url).await
+    }
+}
+
+/// 弹幕列表每页数量
+const DANMAKU_PAGE_SIZE: u32 = 200;
+
+/// 弹幕最多翻页数，防止游标异常时无限请求
+const DANMAKU_MAX_PAGES: u32 = 500;
+
+/// 内容ID为acid，弹幕按视频分段ID（currentVideoId）获取
+#[async_trait]
+impl DanmakuParser for AcfunParser {
+    async fn fetch_danmaku(&self, content_id: &str) -> Result<Vec<Danmaku>> {
+        let html = Self::fetch_page(&format!("https://www.acfun.cn/v/{}", content_id)).await?;
+        let video_id = Self::extract_video_info(&html)
+            .await
+            .and_then(|json| json_str(json.pointer("/currentVideoId")))
+            .ok_or_else(|| anyhow!("未找到A站视频分段ID"))?;
         
+        let client = create_http_client(VideoSource::AcFun)?;
+        let mut items = Vec::new();
+        let mut pcursor = "1".to_string();
+        for _ in 0..DANMAKU_MAX_PAGES {
+            let json: Value = client
+                .post("https://www.acfun.cn/rest/pc-direct/new-danmaku/list")
+                .header("Referer", format!("https://www.acfun.cn/v/{}", content_id))
+                .form(&[
+                    ("resourceId", video_id.as_str()),
+                    ("resourceType", "9"),
+                    ("enableAdvanced", "true"),
+                    ("pcursor", pcursor.as_str()),
+                    ("count", &DANMAKU_PAGE_SIZE.to_string()),
+                    ("sortType", "1"),
+                    ("asc", "false"),
+                ])
+                .send_via(VideoSource::AcFun)
+                .await?
+                .json()
+                .await?;
+            
+            let (page, next) = Self::parse_danmaku_page(&json)?;
+            if page.is_empty() {
+                break;
+            }
+            items.extend(page);
+            match next {
+                Some(next) if next != pcursor => pcursor = next,
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// 内容为视频页 HTML，其中没有 acid，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for AcfunParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let mut info = Self::extract_page_info(content).await;
+        info.set_content_id(VideoSource::AcFun, video_id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
+impl AcfunParser {
+    /// 从视频页的 videoInfo 和 playInfo 中提取信息
+    async fn extract_page_info(html: &str) -> VideoParseInfo {
         let mut info = VideoParseInfo::new();
         
         // 提取videoInfo
-        let video_info_re = Regex::new(r"var videoInfo =\s(.*?);")?;
-        if let Some(caps) = video_info_re.captures(&html) {
-            if let Some(json_str) = caps.get(1) {
-                let json_str = json_str.as_str().trim();
-                if let Ok(json) = serde_json::from_str::<Value>(json_str) {
-                    info.title = json.pointer("/title")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    
-                    info.cover_url = json.pointer("/cover")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string());
-                }
-            }
+        if let Some(json) = Self::extract_video_info(html).await {
+            info.title = json.pointer("/title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            
+            info.cover_url = json.pointer("/cover")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            
+            info.set_metadata(ContentMetadata {
+                duration: json_duration(json.pointer("/currentVideoInfo/durationMillis"), true),
+                publish_time: json_timestamp(json.pointer("/createTimeMillis")),
+                like_count: json_count(json.pointer("/likeCount")),
+                comment_count: json_count(json.pointer("/commentCount")),
+                share_count: json_count(json.pointer("/shareCount")),
+                play_count: json_count(json.pointer("/viewCount")),
+                collect_count: json_count(json.pointer("/stowCount")),
+                hashtags: json.pointer("/tagList")
+                    .and_then(|v| v.as_array())
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.pointer("/name").and_then(|v| v.as_str()))
+                            .map(|s| s.to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                ..Default::default()
+            });
         }
         
         // 提取playInfo
-        let play_info_re = Regex::new(r"var playInfo =\s(.*?);")?;
-        if let Some(caps) = play_info_re.captures(&html) {
-            if let Some(json_str) = caps.get(1) {
-                let json_str = json_str.as_str().trim();
-                if let Ok(json) = serde_json::from_str::<Value>(json_str) {
-                    info.video_url = json.pointer("/streams/0/playUrls/0")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string());
-                    // 视频地址是m3u8格式
-                }
-            }
+        if let Ok(json) = js::eval_page_global(html, "playInfo").await {
+            // 视频地址是m3u8格式
+            info.video_url = json.pointer("/streams/0/playUrls/0")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
         }
         
-        Ok(info)
+        info
     }
     
-    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
-        // acid格式: ac36935385
-        let req_url = format!("https://www.acfun.cn/v/{}", video_id);
-        self.parse_share_url(&req_url).await
+    async fn fetch_page(url: &str) -> Result<String> {
+        let client = create_http_client(VideoSource::AcFun)?;
+        Ok(client
+            .get(url)
+            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 11_0 like Mac OS X) AppleWebKit/604.1.38")
+            .send_via(VideoSource::AcFun)
+            .await?
+            .text()
+            .await?)
+    }
+    
+    async fn extract_video_info(html: &str) -> Option<Value> {
+        js::eval_page_global(html, "videoInfo").await.ok()
+    }
+    
+    /// 返回本页弹幕与下一页游标，游标为 "no_more" 时结束
+    fn parse_danmaku_page(json: &Value) -> Result<(Vec<Danmaku>, Option<String>)> {
+        let result = json.pointer("/result").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if result != 0 {
+            let message = json.pointer("/error_msg").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(anyhow!("A站弹幕接口返回错误: {} (result: {})", message, result));
+        }
+        
+        let items = json.pointer("/danmakus")
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|d| {
+                        Some(Danmaku {
+                            time: d.pointer("/position")?.as_f64()? / 1000.0,
+                            mode: DanmakuMode::from_code(d.pointer("/mode").and_then(|v| v.as_u64()).unwrap_or(1))?,
+                            size: json_u32(d.pointer("/size")).unwrap_or(25),
+                            color: d.pointer("/color").and_then(|v| v.as_u64()).unwrap_or(0xFFFFFF) as u32,
+                            text: json_str(d.pointer("/body"))?,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let next = json_str(json.pointer("/pcursor")).filter(|c| c != "no_more");
+        Ok((items, next))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_danmaku_page() {
+        let json = serde_json::json!({
+            "result": 0,
+            "pcursor": "2",
+            "danmakus": [
+                {"position": 1500, "mode": 1, "size": 25, "color": 16777215, "body": "前排"},
+                {"position": 3000, "mode": 5, "size": 36, "color": 16711680, "body": "顶部"},
+                {"position": 4000, "mode": 7, "size": 25, "color": 0, "body": "高级弹幕"}
+            ]
+        });
+        let (items, next) = AcfunParser::parse_danmaku_page(&json).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].time, 1.5);
+        assert_eq!(items[1].mode, DanmakuMode::Top);
+        assert_eq!(items[1].color, 0xFF0000);
+        assert_eq!(next.as_deref(), Some("2"));
+
+        let (_, next) = AcfunParser::parse_danmaku_page(&serde_json::json!({"result": 0, "pcursor": "no_more", "danmakus": []})).unwrap();
+        assert!(next.is_none());
+    }
+}
@@ -1,12 +1,30 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::js;
+use crate::models::{Author, Comment, CommentPage, ContentMetadata, Danmaku, DanmakuMode, ListingPage, LiveInfo, LiveStatus, LiveStream, StreamFormat, SubtitleCue, SubtitleTrack, VideoParseInfo, VideoSource};
+use crate::parser::{CommentParser, DanmakuParser, ListingParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, create_no_redirect_client, json_count, json_duration, json_str, json_timestamp, timestamp_to_rfc3339};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::Deserialize;
+use serde_json::Value;
 
 pub struct BilibiliParser;
 
+/// 列表每页数量
+const LIST_PAGE_SIZE: u32 = 20;
+
+/// 弹幕分段时长（秒）
+const DANMAKU_SEGMENT_SECS: u64 = 360;
+
+/// 列表类链接
+#[derive(Debug, PartialEq)]
+enum ListingTarget {
+    /// UP主空间投稿，mid
+    Space(String),
+    /// 收藏夹，media_id
+    Favorite(String),
+}
+
 #[derive(Debug, Deserialize)]
 struct BiliViewResponse {
     code: i32,
@@ -16,12 +34,35 @@ struct BiliViewResponse {
 
 #[derive(Debug, Deserialize)]
 struct BiliViewData {
-    #[allow(dead_code)]
     bvid: String,
     title: String,
     pic: String,
     owner: BiliOwner,
     pages: Vec<BiliPage>,
+    #[serde(default)]
+    duration: u64,
+    #[serde(default)]
+    pubdate: i64,
+    dimension: Option<BiliDimension>,
+    stat: Option<BiliStat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BiliDimension {
+    width: u32,
+    height: u32,
+    /// 为1时宽高需要对调
+    #[serde(default)]
+    rotate: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct BiliStat {
+    view: u64,
+    like: u64,
+    reply: u64,
+    share: u64,
+    favorite: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,27 +97,20 @@ struct BiliDurl {
 #[async_trait]
 impl VideoParser for BilibiliParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        // 提取BVID
-        let bvid = self.extract_bvid(share_url).await?;
-        
-        // 获取视频信息
-        let view_url = format!("https://api.bilibili.com/x/web-interface/view?bvid={}", bvid);
-        let client = create_http_client()?;
-        
-        let view_resp: BiliViewResponse = client
-            .get(&view_url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .header("Referer", "https://www.bilibili.com/")
-            .send()
-            .await?
-            .json()
-            .await?;
-        
-        if view_resp.code != 0 {
-            return Err(anyhow!("B站API返回错误: {} (code: {})", view_resp.message, view_resp.code));
+        if let Some(room_id) = Self::extract_live_room_id(share_url) {
+            return self.parse_live_room(&room_id).await;
         }
         
-        let view_data = view_resp.data;
+        // 提取BVID
+        let bvid = self.extract_bvid(share_url).await?;
+        self.parse_video_id(&bvid).await
+    }
+    
+    /// 支持BV号以及 av170001 形式的AV号
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let view_data = Self::fetch_view(video_id).await?;
+        let bvid = view_data.bvid.clone();
         
         // 获取第一个分P的cid
         let cid = view_data.pages.first()
@@ -93,7 +127,7 @@ impl VideoParser for BilibiliParser {
             .get(&play_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
             .header("Referer", "https://www.bilibili.com/")
-            .send()
+            .send_via(VideoSource::BiliBili)
             .await?
             .json()
             .await?;
@@ -106,7 +140,153 @@ impl VideoParser for BilibiliParser {
             .ok_or_else(|| anyhow!("未找到视频播放地址"))?
             .url.clone();
         
-        // 构建返回结果
+        let mut info = Self::build_view_info(view_data);
+        info.video_url = Some(video_url);
+        
+        // 字幕获取失败不影响解析结果
+        match Self::fetch_subtitles(&bvid, cid).await {
+            Ok(subtitles) => info.subtitles = subtitles,
+            Err(e) => tracing::warn!("B站字幕获取失败: {}", e),
+        }
+        
+        Ok(info)
+    }
+}
+
+/// 内容可以是视频信息接口 `/x/web-interface/view` 的响应，也可以是视频页 HTML，
+/// 视频页的 `__playinfo__` 中有 mp4 地址时一并提取，离线时不获取字幕
+#[async_trait]
+impl OfflineParser for BilibiliParser {
+    async fn parse_content(&self, content: &str, _video_id: Option<&str>) -> Result<VideoParseInfo> {
+        if !content.trim_start().starts_with('<') {
+            let view_resp: BiliViewResponse = serde_json::from_str(content)?;
+            if view_resp.code != 0 {
+                return Err(anyhow!("B站API返回错误: {} (code: {})", view_resp.message, view_resp.code));
+            }
+            return Ok(Self::build_view_info(view_resp.data));
+        }
+        
+        let state = js::eval_page_global(content, "window.__INITIAL_STATE__").await?;
+        let view_data: BiliViewData = serde_json::from_value(
+            state.get("videoData").cloned().ok_or_else(|| anyhow!("页面中没有视频信息"))?,
+        )?;
+        let mut info = Self::build_view_info(view_data);
+        info.video_url = js::eval_page_global(content, "window.__playinfo__").await.ok()
+            .and_then(|play| json_str(play.pointer("/data/durl/0/url")));
+        Ok(info)
+    }
+}
+
+/// 游标为页码，从1开始
+#[async_trait]
+impl ListingParser for BilibiliParser {
+    async fn fetch_page(&self, url: &str, cursor: Option<&str>) -> Result<ListingPage> {
+        let target = Self::extract_listing_target(url)
+            .ok_or_else(|| anyhow!("不是有效的B站空间或收藏夹链接"))?;
+        let page_num: u32 = cursor.map(|c| c.parse()).transpose()
+            .map_err(|_| anyhow!("无效的页码游标"))?
+            .unwrap_or(1);
+        
+        let (req_url, referer) = match &target {
+            ListingTarget::Space(mid) => (
+                format!(
+                    "https://api.bilibili.com/x/series/recArchivesByKeywords?mid={}&keywords=&orderby=pubdate&pn={}&ps={}",
+                    mid, page_num, LIST_PAGE_SIZE
+                ),
+                format!("https://space.bilibili.com/{}/video", mid),
+            ),
+            ListingTarget::Favorite(media_id) => (
+                format!(
+                    "https://api.bilibili.com/x/v3/fav/resource/list?media_id={}&pn={}&ps={}&order=mtime&platform=web",
+                    media_id, page_num, LIST_PAGE_SIZE
+                ),
+                "https://www.bilibili.com/".to_string(),
+            ),
+        };
+        
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let json: Value = client
+            .get(&req_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", referer)
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .json()
+            .await?;
+        
+        let code = json.pointer("/code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let message = json.pointer("/message").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(anyhow!("B站API返回错误: {} (code: {})", message, code));
+        }
+        
+        let (items, has_more) = match target {
+            ListingTarget::Space(mid) => Self::parse_space_page(&json, &mid),
+            ListingTarget::Favorite(_) => Self::parse_favorite_page(&json),
+        };
+        
+        Ok(ListingPage {
+            profile: None,
+            items,
+            cursor: has_more.then(|| (page_num + 1).to_string()),
+            has_more,
+        })
+    }
+    
+    fn is_listing_url(&self, url: &str) -> bool {
+        Self::extract_listing_target(url).is_some()
+    }
+}
+
+/// 游标为页码，从1开始
+#[async_trait]
+impl CommentParser for BilibiliParser {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+        let aid = Self::parse_aid(content_id)
+            .or_else(|| Self::bvid_to_aid(content_id))
+            .ok_or_else(|| anyhow!("无效的B站视频ID: {}", content_id))?;
+        let page_num: u32 = cursor.and_then(|c| c.parse().ok()).unwrap_or(1);
+        
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let json: Value = client
+            .get(format!(
+                "https://api.bilibili.com/x/v2/reply?type=1&oid={}&pn={}&ps={}&sort=0",
+                aid, page_num, LIST_PAGE_SIZE
+            ))
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", format!("https://www.bilibili.com/video/{}", content_id))
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .json()
+            .await?;
+        
+        Self::parse_comment_page(&json, page_num)
+    }
+}
+
+/// 优先按6分钟分段获取 protobuf 弹幕（完整），失败时退回 XML 弹幕池（有条数上限）
+#[async_trait]
+impl DanmakuParser for BilibiliParser {
+    async fn fetch_danmaku(&self, content_id: &str) -> Result<Vec<Danmaku>> {
+        let view_data = Self::fetch_view(content_id).await?;
+        let cid = view_data.pages.first()
+            .ok_or_else(|| anyhow!("没有找到视频分P"))?
+            .cid;
+        let segments = view_data.duration.div_ceil(DANMAKU_SEGMENT_SECS).max(1);
+        
+        match Self::fetch_danmaku_segments(cid, segments).await {
+            Ok(items) => Ok(items),
+            Err(e) => {
+                tracing::warn!("B站分段弹幕获取失败，改用XML接口: {}", e);
+                Self::fetch_danmaku_xml(cid).await
+            }
+        }
+    }
+}
+
+impl BilibiliParser {
+    /// 由视频基本信息构建解析结果，不含播放地址和字幕
+    fn build_view_info(view_data: BiliViewData) -> VideoParseInfo {
         let mut info = VideoParseInfo::new();
         info.author = Author {
             uid: view_data.owner.mid.to_string(),
@@ -114,14 +294,57 @@ impl VideoParser for BilibiliParser {
             avatar: view_data.owner.face,
         };
         info.title = view_data.title;
-        info.video_url = Some(video_url);
         info.cover_url = Some(view_data.pic);
         
-        Ok(info)
+        let (width, height) = match &view_data.dimension {
+            Some(d) if d.rotate == 1 => (Some(d.height), Some(d.width)),
+            Some(d) => (Some(d.width), Some(d.height)),
+            None => (None, None),
+        };
+        info.set_metadata(ContentMetadata {
+            duration: (view_data.duration > 0).then_some(view_data.duration as f64),
+            width: width.filter(|w| *w > 0),
+            height: height.filter(|h| *h > 0),
+            publish_time: timestamp_to_rfc3339(view_data.pubdate),
+            like_count: view_data.stat.as_ref().map(|s| s.like),
+            comment_count: view_data.stat.as_ref().map(|s| s.reply),
+            share_count: view_data.stat.as_ref().map(|s| s.share),
+            play_count: view_data.stat.as_ref().map(|s| s.view),
+            collect_count: view_data.stat.as_ref().map(|s| s.favorite),
+            hashtags: Vec::new(),
+        });
+        
+        info.set_content_id(VideoSource::BiliBili, view_data.bvid);
+        
+        info
     }
-}
-
-impl BilibiliParser {
+    
+    /// 获取视频基本信息（标题、UP主、分P等）
+    async fn fetch_view(video_id: &str) -> Result<BiliViewData> {
+        let video_id = video_id.trim();
+        let view_url = match Self::parse_aid(video_id) {
+            Some(aid) => format!("https://api.bilibili.com/x/web-interface/view?aid={}", aid),
+            None if video_id.starts_with("BV") => format!("https://api.bilibili.com/x/web-interface/view?bvid={}", video_id),
+            None => return Err(anyhow!("无效的B站视频ID: {}", video_id)),
+        };
+        let client = create_http_client(VideoSource::BiliBili)?;
+        
+        let view_resp: BiliViewResponse = client
+            .get(&view_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", "https://www.bilibili.com/")
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .json()
+            .await?;
+        
+        if view_resp.code != 0 {
+            return Err(anyhow!("B站API返回错误: {} (code: {})", view_resp.message, view_resp.code));
+        }
+        
+        Ok(view_resp.data)
+    }
+    
     /// 提取BVID
     async fn extract_bvid(&self, url: &str) -> Result<String> {
         let parsed_url = url::Url::parse(url)?;
@@ -137,10 +360,10 @@ impl BilibiliParser {
                 let path = parsed_url.path();
                 let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
                 
-                if parts.len() >= 2 && parts[0] == "video" {
-                    if parts[1].starts_with("BV") {
-                        return Ok(parts[1].to_string());
-                    }
+                if parts.len() >= 2 && parts[0] == "video"
+                    && (parts[1].starts_with("BV") || Self::parse_aid(parts[1]).is_some())
+                {
+                    return Ok(parts[1].to_string());
                 }
             }
         }
@@ -148,12 +371,332 @@ impl BilibiliParser {
         Err(anyhow!("不是有效的B站视频链接"))
     }
     
+    /// 识别 space.bilibili.com/<mid>、space.bilibili.com/<mid>/favlist?fid=<media_id>
+    /// 和 www.bilibili.com/medialist/detail/ml<media_id>、www.bilibili.com/list/ml<media_id>
+    fn extract_listing_target(url: &str) -> Option<ListingTarget> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        let host = parsed_url.host_str()?;
+        let segments: Vec<&str> = parsed_url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        
+        if host == "space.bilibili.com" {
+            let mid = segments.first().filter(|mid| mid.chars().all(|c| c.is_ascii_digit()))?;
+            if segments.get(1) == Some(&"favlist") {
+                let fid = parsed_url.query_pairs().find(|(key, _)| key == "fid")?.1;
+                return Some(ListingTarget::Favorite(fid.to_string()));
+            }
+            return Some(ListingTarget::Space(mid.to_string()));
+        }
+        
+        let media_id = match segments.as_slice() {
+            ["medialist", "detail", id] | ["list", id] => id.strip_prefix("ml")?,
+            _ => return None,
+        };
+        media_id.chars().all(|c| c.is_ascii_digit()).then(|| ListingTarget::Favorite(media_id.to_string()))
+    }
+    
+    fn parse_space_page(json: &Value, mid: &str) -> (Vec<VideoParseInfo>, bool) {
+        let items = json.pointer("/data/archives")
+            .and_then(|v| v.as_array())
+            .map(|archives| {
+                archives.iter()
+                    .filter_map(|archive| {
+                        let mut info = Self::archive_summary(archive, "/pic")?;
+                        info.author.uid = mid.to_string();
+                        info.set_metadata(ContentMetadata {
+                            duration: json_duration(archive.pointer("/duration"), false),
+                            publish_time: json_timestamp(archive.pointer("/pubdate")),
+                            play_count: json_count(archive.pointer("/stat/view")),
+                            ..Default::default()
+                        });
+                        Some(info)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let num = json.pointer("/data/page/num").and_then(|v| v.as_u64()).unwrap_or(0);
+        let size = json.pointer("/data/page/size").and_then(|v| v.as_u64()).unwrap_or(0);
+        let total = json.pointer("/data/page/total").and_then(|v| v.as_u64()).unwrap_or(0);
+        
+        (items, num * size < total)
+    }
+    
+    fn parse_favorite_page(json: &Value) -> (Vec<VideoParseInfo>, bool) {
+        let items = json.pointer("/data/medias")
+            .and_then(|v| v.as_array())
+            .map(|medias| {
+                medias.iter()
+                    .filter_map(|media| {
+                        let mut info = Self::archive_summary(media, "/cover")?;
+                        info.author = Author {
+                            uid: json_count(media.pointer("/upper/mid")).map(|m| m.to_string()).unwrap_or_default(),
+                            name: media.pointer("/upper/name").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                            avatar: media.pointer("/upper/face").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                        };
+                        info.set_metadata(ContentMetadata {
+                            duration: json_duration(media.pointer("/duration"), false),
+                            publish_time: json_timestamp(media.pointer("/pubtime")),
+                            play_count: json_count(media.pointer("/cnt_info/play")),
+                            collect_count: json_count(media.pointer("/cnt_info/collect")),
+                            ..Default::default()
+                        });
+                        Some(info)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let has_more = json.pointer("/data/has_more").and_then(|v| v.as_bool()).unwrap_or(false);
+        
+        (items, has_more)
+    }
+    
+    /// 列表项摘要：标题、封面和BV号，不包含播放地址；失效视频没有BV号时跳过
+    fn archive_summary(data: &Value, cover_pointer: &str) -> Option<VideoParseInfo> {
+        let bvid = data.pointer("/bvid").and_then(|v| v.as_str()).filter(|s| !s.is_empty())?;
+        
+        let mut info = VideoParseInfo::new();
+        info.title = data.pointer("/title").and_then(|v| v.as_str()).unwrap_or("").to_string();
+        info.cover_url = data.pointer(cover_pointer)
+            .and_then(|v| v.as_str())
+            .map(|s| s.replace("http://", "https://"));
+        info.set_content_id(VideoSource::BiliBili, bvid);
+        
+        Some(info)
+    }
+    
+    /// 解析 av170001 形式的AV号
+    /// BV号转AV号
+    fn bvid_to_aid(bvid: &str) -> Option<u64> {
+        const TABLE: &[u8] = b"FcwAPNKTMug3GV5Lj7EJnHpWsx4tb8haYeviqBz6rkCy12mUSDQX9RdoZf";
+        const XOR_CODE: u64 = 23442827791579;
+        const MASK_CODE: u64 = 2251799813685247;
+        
+        let mut chars: Vec<u8> = bvid.bytes().collect();
+        if chars.len() != 12 || !bvid.starts_with("BV1") {
+            return None;
+        }
+        chars.swap(3, 9);
+        chars.swap(4, 7);
+        let mut value: u64 = 0;
+        for c in &chars[3..] {
+            let index = TABLE.iter().position(|t| t == c)? as u64;
+            value = value * TABLE.len() as u64 + index;
+        }
+        Some((value & MASK_CODE) ^ XOR_CODE)
+    }
+    
+    async fn fetch_danmaku_segments(cid: i64, segments: u64) -> Result<Vec<Danmaku>> {
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let mut items = Vec::new();
+        for index in 1..=segments {
+            let resp = client
+                .get(format!(
+                    "https://api.bilibili.com/x/v2/dm/web/seg.so?type=1&oid={}&segment_index={}",
+                    cid, index
+                ))
+                .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+                .header("Referer", "https://www.bilibili.com/")
+                .send_via(VideoSource::BiliBili)
+                .await?;
+            if !resp.status().is_success() {
+                return Err(anyhow!("弹幕分段请求失败: {}", resp.status()));
+            }
+            items.extend(Self::parse_danmaku_segment(&resp.bytes().await?)?);
+        }
+        Ok(items)
+    }
+    
+    async fn fetch_danmaku_xml(cid: i64) -> Result<Vec<Danmaku>> {
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let xml = client
+            .get(format!("https://comment.bilibili.com/{}.xml", cid))
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", "https://www.bilibili.com/")
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .text()
+            .await?;
+        Ok(Self::parse_danmaku_xml(&xml))
+    }
+    
+    /// 解析 XML 弹幕：`<d p="时间,模式,字号,颜色,...">内容</d>`
+    fn parse_danmaku_xml(xml: &str) -> Vec<Danmaku> {
+        let re = regex::Regex::new(r#"<d p="([^"]*)">([^<]*)</d>"#).expect("valid regex");
+        re.captures_iter(xml)
+            .filter_map(|caps| {
+                let attrs: Vec<&str> = caps[1].split(',').collect();
+                let mode = DanmakuMode::from_code(attrs.get(1)?.parse().ok()?)?;
+                Some(Danmaku {
+                    time: attrs.first()?.parse().ok()?,
+                    mode,
+                    size: attrs.get(2).and_then(|v| v.parse().ok()).unwrap_or(25),
+                    color: attrs.get(3).and_then(|v| v.parse().ok()).unwrap_or(0xFFFFFF),
+                    text: caps[2]
+                        .replace("&lt;", "<")
+                        .replace("&gt;", ">")
+                        .replace("&quot;", "\"")
+                        .replace("&apos;", "'")
+                        .replace("&amp;", "&"),
+                })
+            })
+            .collect()
+    }
+    
+    /// 解析 protobuf 分段弹幕（DmSegMobileReply），只取用到的字段：
+    /// elems=1 { progress=2 毫秒, mode=3, fontsize=4, color=5, content=7 }
+    fn parse_danmaku_segment(buf: &[u8]) -> Result<Vec<Danmaku>> {
+        let mut items = Vec::new();
+        for (field, value) in proto_fields(buf)? {
+            let (1, ProtoValue::Bytes(elem)) = (field, value) else {
+                continue;
+            };
+            let mut danmaku = Danmaku { size: 25, color: 0xFFFFFF, ..Default::default() };
+            // 模式为默认值时字段省略，按滚动弹幕处理
+            let mut mode = Some(DanmakuMode::Scroll);
+            for (field, value) in proto_fields(elem)? {
+                match (field, value) {
+                    (2, ProtoValue::Varint(v)) => danmaku.time = v as f64 / 1000.0,
+                    (3, ProtoValue::Varint(v)) => mode = DanmakuMode::from_code(v),
+                    (4, ProtoValue::Varint(v)) => danmaku.size = v as u32,
+                    (5, ProtoValue::Varint(v)) => danmaku.color = v as u32,
+                    (7, ProtoValue::Bytes(v)) => danmaku.text = String::from_utf8_lossy(v).into_owned(),
+                    _ => {}
+                }
+            }
+            let Some(mode) = mode else {
+                continue;
+            };
+            danmaku.mode = mode;
+            items.push(danmaku);
+        }
+        Ok(items)
+    }
+    
+    /// 获取全部字幕轨道及正文，AI字幕的语言代码以 `ai-` 开头
+    async fn fetch_subtitles(bvid: &str, cid: i64) -> Result<Vec<SubtitleTrack>> {
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let json: Value = client
+            .get(format!("https://api.bilibili.com/x/player/v2?bvid={}&cid={}", bvid, cid))
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", format!("https://www.bilibili.com/video/{}", bvid))
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .json()
+            .await?;
+        
+        let mut tracks = Self::parse_subtitle_tracks(&json)?;
+        for track in &mut tracks {
+            let body: Value = client
+                .get(&track.url)
+                .header("Referer", "https://www.bilibili.com/")
+                .send_via(VideoSource::BiliBili)
+                .await?
+                .json()
+                .await?;
+            track.cues = Self::parse_subtitle_body(&body);
+        }
+        Ok(tracks)
+    }
+    
+    fn parse_subtitle_tracks(json: &Value) -> Result<Vec<SubtitleTrack>> {
+        let code = json.pointer("/code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let message = json.pointer("/message").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(anyhow!("B站API返回错误: {} (code: {})", message, code));
+        }
+        
+        Ok(json.pointer("/data/subtitle/subtitles")
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|t| {
+                        let url = json_str(t.pointer("/subtitle_url"))?;
+                        let lang = json_str(t.pointer("/lan")).unwrap_or_default();
+                        Some(SubtitleTrack {
+                            ai: lang.starts_with("ai-") || t.pointer("/type").and_then(|v| v.as_i64()) == Some(1),
+                            lang_name: json_str(t.pointer("/lan_doc")).unwrap_or_else(|| lang.clone()),
+                            lang,
+                            // 地址通常省略协议
+                            url: if url.starts_with("//") { format!("https:{}", url) } else { url },
+                            cues: Vec::new(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+    
+    /// 字幕正文：`{"body": [{"from": 0.5, "to": 2.1, "content": "..."}]}`
+    fn parse_subtitle_body(json: &Value) -> Vec<SubtitleCue> {
+        json.pointer("/body")
+            .and_then(|v| v.as_array())
+            .map(|body| {
+                body.iter()
+                    .filter_map(|c| {
+                        Some(SubtitleCue {
+                            from: c.pointer("/from")?.as_f64()?,
+                            to: c.pointer("/to")?.as_f64()?,
+                            content: json_str(c.pointer("/content"))?,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    fn parse_comment_page(json: &Value, page_num: u32) -> Result<CommentPage> {
+        let code = json.pointer("/code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let message = json.pointer("/message").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(anyhow!("B站API返回错误: {} (code: {})", message, code));
+        }
+        
+        let items: Vec<Comment> = json.pointer("/data/replies")
+            .and_then(|v| v.as_array())
+            .map(|replies| {
+                replies.iter()
+                    .map(|r| Comment {
+                        id: json_str(r.pointer("/rpid_str"))
+                            .or_else(|| json_str(r.pointer("/rpid")))
+                            .unwrap_or_default(),
+                        author: Author {
+                            uid: json_str(r.pointer("/member/mid")).unwrap_or_default(),
+                            name: json_str(r.pointer("/member/uname")).unwrap_or_default(),
+                            avatar: json_str(r.pointer("/member/avatar")).unwrap_or_default(),
+                        },
+                        text: json_str(r.pointer("/content/message")).unwrap_or_default(),
+                        like_count: json_count(r.pointer("/like")),
+                        reply_count: json_count(r.pointer("/rcount")),
+                        publish_time: json_timestamp(r.pointer("/ctime")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // count 为顶层评论数，acount 含回复
+        let count = json_count(json.pointer("/data/page/count")).unwrap_or(0);
+        let size = json_count(json.pointer("/data/page/size")).unwrap_or(LIST_PAGE_SIZE as u64);
+        let has_more = !items.is_empty() && (page_num as u64) * size < count;
+        
+        Ok(CommentPage {
+            total: json_count(json.pointer("/data/page/acount")).or(Some(count)),
+            items,
+            cursor: has_more.then(|| (page_num + 1).to_string()),
+            has_more,
+        })
+    }
+    
+    fn parse_aid(video_id: &str) -> Option<u64> {
+        video_id.strip_prefix("av")
+            .or_else(|| video_id.strip_prefix("AV"))
+            .and_then(|aid| aid.parse().ok())
+    }
+    
     async fn resolve_short_url(&self, url: &str) -> Result<String> {
-        let client = reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .build()?;
+        let client = create_no_redirect_client(VideoSource::BiliBili)?;
         
-        let response = client.get(url).send().await?;
+        let response = client.get(url).send_via(VideoSource::BiliBili).await?;
         
         let location = response
             .headers()
@@ -163,5 +706,379 @@ impl BilibiliParser {
         
         Box::pin(self.extract_bvid(location)).await
     }
+    
+    /// 直播间 live.bilibili.com/<房间号>，房间号可以是短号
+    fn extract_live_room_id(url: &str) -> Option<String> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        if parsed_url.host_str() != Some("live.bilibili.com") {
+            return None;
+        }
+        let segments: Vec<&str> = parsed_url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        // 兼容 live.bilibili.com/h5/<房间号>
+        let room_id = match segments.as_slice() {
+            ["h5", id, ..] | [id, ..] => *id,
+            _ => return None,
+        };
+        room_id.chars().all(|c| c.is_ascii_digit()).then(|| room_id.to_string())
+    }
+    
+    async fn live_api_get(&self, url: &str) -> Result<Value> {
+        let client = create_http_client(VideoSource::BiliBili)?;
+        let json: Value = client
+            .get(url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", "https://live.bilibili.com/")
+            .send_via(VideoSource::BiliBili)
+            .await?
+            .json()
+            .await?;
+        
+        let code = json.pointer("/code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let message = json.pointer("/message")
+                .or_else(|| json.pointer("/msg"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            return Err(anyhow!("B站直播API返回错误: {} (code: {})", message, code));
+        }
+        Ok(json)
+    }
+    
+    async fn parse_live_room(&self, room_id: &str) -> Result<VideoParseInfo> {
+        let room = self.live_api_get(&format!(
+            "https://api.live.bilibili.com/room/v1/Room/get_info?room_id={}",
+            room_id
+        )).await?;
+        let data = room.pointer("/data")
+            .ok_or_else(|| anyhow!("无法获取直播间数据"))?;
+        
+        // 短号统一换成真实房间号
+        let real_room_id = json_str(data.pointer("/room_id")).unwrap_or_else(|| room_id.to_string());
+        let status = match data.pointer("/live_status").and_then(|v| v.as_i64()) {
+            Some(1) => LiveStatus::Live,
+            Some(2) => LiveStatus::Replay,
+            _ => LiveStatus::Offline,
+        };
+        
+        let mut info = VideoParseInfo::new();
+        info.title = json_str(data.pointer("/title")).unwrap_or_default();
+        info.cover_url = json_str(data.pointer("/user_cover"));
+        info.author.uid = json_str(data.pointer("/uid")).unwrap_or_default();
+        
+        if !info.author.uid.is_empty() {
+            let master = self.live_api_get(&format!(
+                "https://api.live.bilibili.com/live_user/v1/Master/info?uid={}",
+                info.author.uid
+            )).await;
+            if let Ok(master) = master {
+                info.author.name = json_str(master.pointer("/data/info/uname")).unwrap_or_default();
+                info.author.avatar = json_str(master.pointer("/data/info/face")).unwrap_or_default();
+            }
+        }
+        
+        let streams = if status == LiveStatus::Live {
+            self.fetch_live_streams(&real_room_id).await?
+        } else {
+            Vec::new()
+        };
+        
+        info.set_live(VideoSource::BiliBili, LiveInfo {
+            room_id: real_room_id,
+            status,
+            online_count: json_count(data.pointer("/online")),
+            streams,
+        });
+        
+        Ok(info)
+    }
+    
+    /// 接口每次只返回一个清晰度的地址，先取最高清晰度，再逐个请求其余清晰度
+    async fn fetch_live_streams(&self, room_id: &str) -> Result<Vec<LiveStream>> {
+        let play_info_url = |qn: i64| format!(
+            "https://api.live.bilibili.com/xlive/web-room/v2/index/getRoomPlayInfo?room_id={}&protocol=0,1&format=0,1,2&codec=0&qn={}&platform=web&ptype=8",
+            room_id, qn
+        );
+        
+        let first = self.live_api_get(&play_info_url(10000)).await?;
+        let mut streams = Self::parse_play_info(&first);
+        let current_qn = first.pointer("/data/playurl_info/playurl/stream/0/format/0/codec/0/current_qn")
+            .and_then(|v| v.as_i64());
+        let accept_qn: Vec<i64> = first.pointer("/data/playurl_info/playurl/stream/0/format/0/codec/0/accept_qn")
+            .and_then(|v| v.as_array())
+            .map(|list| list.iter().filter_map(|v| v.as_i64()).collect())
+            .unwrap_or_default();
+        
+        for qn in accept_qn.into_iter().filter(|qn| Some(*qn) != current_qn) {
+            match self.live_api_get(&play_info_url(qn)).await {
+                Ok(json) => streams.extend(Self::parse_play_info(&json)),
+                Err(e) => tracing::warn!("获取B站直播清晰度 {} 失败: {}", qn, e),
+            }
+        }
+        
+        Ok(streams)
+    }
+    
+    /// 解析 getRoomPlayInfo 中当前清晰度的FLV和HLS地址（只取H.264编码）
+    fn parse_play_info(json: &Value) -> Vec<LiveStream> {
+        let playurl = json.pointer("/data/playurl_info/playurl").unwrap_or(&Value::Null);
+        let quality_name = |qn: i64| {
+            playurl.pointer("/g_qn_desc")
+                .and_then(|v| v.as_array())
+                .and_then(|list| list.iter().find(|d| d.pointer("/qn").and_then(|v| v.as_i64()) == Some(qn)))
+                .and_then(|d| json_str(d.pointer("/desc")))
+                .unwrap_or_else(|| qn.to_string())
+        };
+        
+        let mut streams = Vec::new();
+        for stream in playurl.pointer("/stream").and_then(|v| v.as_array()).into_iter().flatten() {
+            let format = match stream.pointer("/protocol_name").and_then(|v| v.as_str()) {
+                Some("http_stream") => StreamFormat::Flv,
+                Some("http_hls") => StreamFormat::Hls,
+                _ => continue,
+            };
+            // HLS 同时有 ts 和 fmp4 两种封装，取第一种即可
+            let Some(stream_format) = stream.pointer("/format/0") else {
+                continue;
+            };
+            for codec in stream_format.pointer("/codec").and_then(|v| v.as_array()).into_iter().flatten() {
+                if codec.pointer("/codec_name").and_then(|v| v.as_str()) != Some("avc") {
+                    continue;
+                }
+                let (Some(base_url), Some(host)) = (
+                    codec.pointer("/base_url").and_then(|v| v.as_str()),
+                    codec.pointer("/url_info/0/host").and_then(|v| v.as_str()),
+                ) else {
+                    continue;
+                };
+                let extra = codec.pointer("/url_info/0/extra").and_then(|v| v.as_str()).unwrap_or("");
+                streams.push(LiveStream {
+                    quality: quality_name(codec.pointer("/current_qn").and_then(|v| v.as_i64()).unwrap_or(0)),
+                    format,
+                    url: format!("{}{}{}", host, base_url, extra),
+                });
+            }
+        }
+        streams
+    }
+}
+
+
+/// protobuf 字段值，定长字段不需要，只保留占位
+enum ProtoValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos).ok_or_else(|| anyhow!("protobuf数据不完整"))?;
+        *pos += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("protobuf varint过长"))
 }
 
+/// 按顺序读出一条消息的所有字段
+fn proto_fields(buf: &[u8]) -> Result<Vec<(u64, ProtoValue<'_>)>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let value = match key & 7 {
+            0 => ProtoValue::Varint(read_varint(buf, &mut pos)?),
+            1 | 5 => {
+                pos += if key & 7 == 1 { 8 } else { 4 };
+                ProtoValue::Fixed
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let bytes = pos
+                    .checked_add(len)
+                    .and_then(|end| buf.get(pos..end))
+                    .ok_or_else(|| anyhow!("protobuf数据不完整"))?;
+                pos += len;
+                ProtoValue::Bytes(bytes)
+            }
+            wire => return Err(anyhow!("不支持的protobuf类型: {}", wire)),
+        };
+        fields.push((key >> 3, value));
+    }
+    if pos > buf.len() {
+        return Err(anyhow!("protobuf数据不完整"));
+    }
+    Ok(fields)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subtitles() {
+        let json = serde_json::json!({
+            "code": 0,
+            "data": {"subtitle": {"subtitles": [
+                {"lan": "zh-CN", "lan_doc": "中文（中国）", "type": 0, "subtitle_url": "//aisubtitle.hdslb.com/bfs/subtitle/1.json"},
+                {"lan": "ai-zh", "lan_doc": "中文（自动生成）", "type": 1, "subtitle_url": "https://aisubtitle.hdslb.com/bfs/ai_subtitle/2.json"},
+                {"lan": "en-US", "lan_doc": "English", "subtitle_url": ""}
+            ]}}
+        });
+        let tracks = BilibiliParser::parse_subtitle_tracks(&json).unwrap();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].url, "https://aisubtitle.hdslb.com/bfs/subtitle/1.json");
+        assert_eq!(tracks[0].lang_name, "中文（中国）");
+        assert!(!tracks[0].ai);
+        assert!(tracks[1].ai);
+
+        let body = serde_json::json!({"body": [
+            {"from": 0.5, "to": 2.1, "location": 2, "content": "大家好"},
+            {"from": 2.1, "to": 4.0, "location": 2, "content": ""}
+        ]});
+        assert_eq!(
+            BilibiliParser::parse_subtitle_body(&body),
+            vec![SubtitleCue { from: 0.5, to: 2.1, content: "大家好".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_parse_danmaku() {
+        // elems: {progress=1500, mode=5, fontsize=25, color=0xFF0000, midHash="ab", content="hi"}
+        //        {mode=7 高级弹幕}, {progress=2000, content="ok"} 省略模式字段
+        let elem1: &[u8] = &[0x10, 0xDC, 0x0B, 0x18, 0x05, 0x20, 0x19, 0x28, 0x80, 0x80, 0xFC, 0x07, 0x32, 0x02, b'a', b'b', 0x3A, 0x02, b'h', b'i'];
+        let elem2: &[u8] = &[0x18, 0x07, 0x3A, 0x01, b'x'];
+        let elem3: &[u8] = &[0x10, 0xD0, 0x0F, 0x3A, 0x02, b'o', b'k'];
+        let mut buf = Vec::new();
+        for elem in [elem1, elem2, elem3] {
+            buf.extend([0x0A, elem.len() as u8]);
+            buf.extend(elem);
+        }
+        let items = BilibiliParser::parse_danmaku_segment(&buf).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].time, 1.5);
+        assert_eq!(items[0].mode, DanmakuMode::Top);
+        assert_eq!(items[0].color, 0xFF0000);
+        assert_eq!(items[0].text, "hi");
+        assert_eq!(items[1].mode, DanmakuMode::Scroll);
+        assert_eq!(items[1].time, 2.0);
+        assert!(BilibiliParser::parse_danmaku_segment(&[0x0A, 0x05, 0x10]).is_err());
+        // 长度字段为 u64::MAX，不能因加法溢出而 panic
+        let oversized = [0x0A, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0x10];
+        assert!(BilibiliParser::parse_danmaku_segment(&oversized).is_err());
+
+        let xml = r#"<i><chatid>1</chatid><d p="12.5,4,36,16777215,1700000000,0,abc,1">A &amp; B</d><d p="3,8,25,0,0,0,x,2">code</d></i>"#;
+        let items = BilibiliParser::parse_danmaku_xml(xml);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].mode, DanmakuMode::Bottom);
+        assert_eq!(items[0].size, 36);
+        assert_eq!(items[0].text, "A & B");
+    }
+
+    #[test]
+    fn test_parse_comment_page() {
+        assert_eq!(BilibiliParser::bvid_to_aid("BV17x411w7KC"), Some(170001));
+        assert_eq!(BilibiliParser::bvid_to_aid("BV1L9Uoa9EUx"), Some(111298867365120));
+        assert_eq!(BilibiliParser::bvid_to_aid("av170001"), None);
+
+        let json = serde_json::json!({
+            "code": 0,
+            "data": {
+                "page": {"num": 1, "size": 20, "count": 45, "acount": 120},
+                "replies": [{
+                    "rpid": 1, "rpid_str": "1", "ctime": 1700000000, "like": 9, "rcount": 2,
+                    "member": {"mid": "2", "uname": "观众", "avatar": "https://i0.hdslb.com/a.jpg"},
+                    "content": {"message": "前排"}
+                }]
+            }
+        });
+        let page = BilibiliParser::parse_comment_page(&json, 2).unwrap();
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("3"));
+        assert_eq!(page.total, Some(120));
+        assert_eq!(page.items[0].author.name, "观众");
+        assert_eq!(page.items[0].like_count, Some(9));
+
+        let last = BilibiliParser::parse_comment_page(&json, 3).unwrap();
+        assert!(!last.has_more);
+    }
+
+    #[test]
+    fn test_parse_live_play_info() {
+        assert_eq!(BilibiliParser::extract_live_room_id("https://live.bilibili.com/6?spm_id_from=333").as_deref(), Some("6"));
+        assert_eq!(BilibiliParser::extract_live_room_id("https://live.bilibili.com/h5/21452505").as_deref(), Some("21452505"));
+        assert_eq!(BilibiliParser::extract_live_room_id("https://www.bilibili.com/video/BV1xx411c7mD"), None);
+
+        let json = serde_json::json!({
+            "code": 0,
+            "data": {"playurl_info": {"playurl": {
+                "g_qn_desc": [{"qn": 10000, "desc": "原画"}, {"qn": 400, "desc": "蓝光"}],
+                "stream": [
+                    {"protocol_name": "http_stream", "format": [{"format_name": "flv", "codec": [
+                        {"codec_name": "avc", "current_qn": 10000, "accept_qn": [10000, 400], "base_url": "/live-bvc/1/live_1.flv?", "url_info": [{"host": "https://d1--cn-gotcha.bilivideo.com", "extra": "expires=1"}]}
+                    ]}]},
+                    {"protocol_name": "http_hls", "format": [{"format_name": "ts", "codec": [
+                        {"codec_name": "hevc", "current_qn": 10000, "base_url": "/hevc.m3u8?", "url_info": [{"host": "https://h", "extra": ""}]},
+                        {"codec_name": "avc", "current_qn": 10000, "base_url": "/live-bvc/1/index.m3u8?", "url_info": [{"host": "https://d1--cn-gotcha.bilivideo.com", "extra": "expires=1"}]}
+                    ]}]}
+                ]
+            }}}
+        });
+        let streams = BilibiliParser::parse_play_info(&json);
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].quality, "原画");
+        assert_eq!(streams[0].format, StreamFormat::Flv);
+        assert_eq!(streams[0].url, "https://d1--cn-gotcha.bilivideo.com/live-bvc/1/live_1.flv?expires=1");
+        assert_eq!(streams[1].format, StreamFormat::Hls);
+    }
+
+    #[test]
+    fn test_listing_pages() {
+        assert_eq!(
+            BilibiliParser::extract_listing_target("https://space.bilibili.com/2/video"),
+            Some(ListingTarget::Space("2".to_string()))
+        );
+        assert_eq!(
+            BilibiliParser::extract_listing_target("https://space.bilibili.com/2/favlist?fid=100&ftype=create"),
+            Some(ListingTarget::Favorite("100".to_string()))
+        );
+        assert_eq!(
+            BilibiliParser::extract_listing_target("https://www.bilibili.com/medialist/detail/ml100"),
+            Some(ListingTarget::Favorite("100".to_string()))
+        );
+        assert_eq!(BilibiliParser::extract_listing_target("https://www.bilibili.com/video/BV1xx411c7mD"), None);
+
+        let space = serde_json::json!({
+            "code": 0,
+            "data": {
+                "archives": [{"bvid": "BV1xx411c7mD", "title": "投稿", "pic": "http://i0.hdslb.com/a.jpg", "duration": 120, "pubdate": 1700000000, "stat": {"view": 42}}],
+                "page": {"num": 1, "size": 20, "total": 21}
+            }
+        });
+        let (items, has_more) = BilibiliParser::parse_space_page(&space, "2");
+        assert!(has_more);
+        assert_eq!(items[0].author.uid, "2");
+        assert_eq!(items[0].cover_url.as_deref(), Some("https://i0.hdslb.com/a.jpg"));
+        assert_eq!(items[0].canonical_url.as_deref(), Some("https://www.bilibili.com/video/BV1xx411c7mD"));
+
+        let favorite = serde_json::json!({
+            "code": 0,
+            "data": {
+                "medias": [
+                    {"bvid": "BV1xx411c7mD", "title": "收藏", "cover": "https://i0.hdslb.com/b.jpg", "upper": {"mid": 2, "name": "UP"}, "cnt_info": {"play": 7}},
+                    {"bvid": "", "title": "已失效视频"}
+                ],
+                "has_more": false
+            }
+        });
+        let (items, has_more) = BilibiliParser::parse_favorite_page(&favorite);
+        assert!(!has_more);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].author.name, "UP");
+        assert_eq!(items[0].metadata.as_ref().and_then(|m| m.play_count), Some(7));
+    }
+}
@@ -1,71 +0,0 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
-use anyhow::{anyhow, Result};
-use async_trait::async_trait;
-use serde_json::Value;
-
-pub struct DoupaiParser;
-
-#[async_trait]
-impl VideoParser for DoupaiParser {
-    async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let parsed_url = url::Url::parse(share_url)?;
-        
-        let video_id = parsed_url.query_pairs()
-            .find(|(key, _)| key == "id")
-            .map(|(_, value)| value.to_string())
-            .ok_or_else(|| anyhow!("无法从分享链接中解析视频ID"))?;
-        
-        self.parse_video_id(&video_id).await
-    }
-    
-    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
-        let req_url = format!("https://v2.doupai.cc/topic/{}.json", video_id);
-        
-        let client = create_http_client()?;
-        let response = client
-            .get(&req_url)
-            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
-            .await?;
-        
-        let json: Value = response.json().await?;
-        
-        let data = json.pointer("/data")
-            .ok_or_else(|| anyhow!("无法获取视频数据"))?;
-        
-        let mut info = VideoParseInfo::new();
-        
-        info.title = data.pointer("/name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        
-        info.video_url = data.pointer("/videoUrl")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        info.cover_url = data.pointer("/imageUrl")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        info.author = Author {
-            uid: data.pointer("/userId/id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string(),
-            name: data.pointer("/userId/name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string(),
-            avatar: data.pointer("/userId/avatar")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string(),
-        };
-        
-        Ok(info)
-    }
-}
-
@@ -1,6 +1,8 @@
-use crate::models::{Author, ImgInfo, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::{create_http_client, create_no_redirect_client, extract_json_from_html, generate_numeric_id, generate_random_string};
+use crate::js;
+use crate::models::{Author, Comment, CommentPage, ContentMetadata, ImgInfo, ListingPage, LiveInfo, LiveStatus, LiveStream, MusicInfo, StreamFormat, UserProfile, VideoParseInfo, VideoSource};
+use crate::parser::{douyin_sign, CommentParser, ListingParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, create_no_redirect_client, generate_numeric_id, generate_random_string, json_count, json_duration, json_str, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use scraper::{Html, Selector};
@@ -9,6 +11,41 @@ use tracing;
 
 pub struct DouyinParser;
 
+/// 签名接口使用的 User-Agent，必须与签名时一致
+const WEB_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36";
+
+/// 网页版接口的公共参数
+const WEB_API_PARAMS: &str = "device_platform=webapp&aid=6383&channel=channel_pc_web&pc_client_type=1&version_code=170400&version_name=17.4.0&cookie_enabled=true&platform=PC&downlink=10";
+
+/// 主页作品、合集每页数量
+const USER_POSTS_PAGE_SIZE: u32 = 18;
+
+/// 评论接口每页数量
+const COMMENT_PAGE_SIZE: u32 = 20;
+
+/// 直播间接口的公共参数
+const LIVE_API_PARAMS: &str = "aid=6383&app_name=douyin_web&live_id=1&device_platform=web&language=zh-CN&enter_from=web_live&cookie_enabled=true&browser_language=zh-CN&browser_platform=Win32&browser_name=Chrome&browser_version=129.0.0.0";
+
+/// 直播流清晰度，从高到低
+const LIVE_QUALITIES: [(&str, &str); 5] = [
+    ("ORIGION", "原画"),
+    ("FULL_HD1", "蓝光"),
+    ("HD1", "超清"),
+    ("SD1", "高清"),
+    ("SD2", "标清"),
+];
+
+/// 列表类链接
+#[derive(Debug, PartialEq)]
+enum ListingTarget {
+    /// 用户主页，sec_uid
+    User(String),
+    /// 合集，mix_id
+    Mix(String),
+    /// 使用某个音乐的作品，music_id
+    Music(String),
+}
+
 #[async_trait]
 impl VideoParser for DouyinParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
@@ -16,6 +53,13 @@ impl VideoParser for DouyinParser {
         
         match url.host_str() {
             Some("v.douyin.com") => self.parse_app_share_url(share_url).await,
+            Some("live.douyin.com") => {
+                let web_rid = url.path_segments()
+                    .and_then(|mut segments| segments.find(|s| !s.is_empty()))
+                    .filter(|id| id.chars().all(|c| c.is_ascii_digit()))
+                    .ok_or_else(|| anyhow!("无法从直播间链接中解析房间号"))?;
+                self.parse_live_room(web_rid).await
+            }
             Some("www.iesdouyin.com") | Some("www.douyin.com") => {
                 self.parse_pc_share_url(share_url).await
             }
@@ -26,11 +70,11 @@ impl VideoParser for DouyinParser {
     async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
         let req_url = format!("https://www.iesdouyin.com/share/video/{}", video_id);
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::DouYin)?;
         let response = client
             .get(&req_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::DouYin)
             .await?;
         
         let html = response.text().await?;
@@ -44,25 +88,91 @@ impl VideoParser for DouyinParser {
                 Ok(data) => (data, true),
                 Err(_) => {
                     // 图集API失败，降级到普通视频API
-                    (self.parse_video_data_from_html(&html, video_id)?, false)
+                    (self.parse_video_data_from_html(&html, video_id).await?, false)
                 }
             }
         } else {
-            (self.parse_video_data_from_html(&html, video_id)?, false)
+            (self.parse_video_data_from_html(&html, video_id).await?, false)
         };
         
-        self.extract_video_info(&data, is_note_final).await
+        let mut info = self.extract_video_info(&data, is_note_final).await?;
+        info.set_content_id(VideoSource::DouYin, video_id);
+        Ok(info)
+    }
+}
+
+#[async_trait]
+impl ListingParser for DouyinParser {
+    async fn fetch_page(&self, url: &str, cursor: Option<&str>) -> Result<ListingPage> {
+        match self.resolve_listing_target(url).await? {
+            ListingTarget::User(sec_uid) => {
+                let mut page = self.fetch_user_posts(&sec_uid, cursor.unwrap_or("0")).await?;
+                
+                // 首页附带主页信息
+                if cursor.is_none() {
+                    match self.fetch_user_profile(&sec_uid).await {
+                        Ok(profile) => page.profile = Some(profile),
+                        Err(e) => tracing::warn!("获取抖音用户信息失败: {}", e),
+                    }
+                }
+                
+                Ok(page)
+            }
+            ListingTarget::Mix(mix_id) => self.fetch_mix_page(&mix_id, cursor.unwrap_or("0")).await,
+            ListingTarget::Music(music_id) => self.fetch_music_page(&music_id, cursor.unwrap_or("0")).await,
+        }
+    }
+    
+    fn is_listing_url(&self, url: &str) -> bool {
+        Self::extract_listing_target(url).is_some()
+    }
+}
+
+/// 内容可以是分享页 HTML，也可以是图集、作品详情接口的响应，
+/// 离线时视频地址不做重定向
+#[async_trait]
+impl OfflineParser for DouyinParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let video_id = video_id.unwrap_or_default();
+        let mut info = if content.trim_start().starts_with('<') {
+            let data = self.parse_video_data_from_html(content, video_id).await?;
+            self.build_video_info(&data, false)?
+        } else {
+            let json: Value = serde_json::from_str(content)?;
+            let data = json.pointer("/aweme_details/0")
+                .or_else(|| json.get("aweme_detail"))
+                .or_else(|| json.pointer("/item_list/0"))
+                .unwrap_or(&json);
+            let is_note = data.pointer("/images").is_some_and(|v| v.is_array());
+            self.build_video_info(data, is_note)?
+        };
+        info.set_content_id(VideoSource::DouYin, video_id);
+        Ok(info)
+    }
+}
+
+/// 游标为接口返回的 cursor（偏移量）
+#[async_trait]
+impl CommentParser for DouyinParser {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+        let json = self.web_api_get(
+            "/aweme/v1/web/comment/list/",
+            &format!("aweme_id={}&cursor={}&count={}&item_type=0", content_id, cursor.unwrap_or("0"), COMMENT_PAGE_SIZE),
+            &format!("https://www.douyin.com/video/{}", content_id),
+        ).await?;
+        
+        Ok(Self::parse_comment_page(&json))
     }
 }
 
 impl DouyinParser {
     async fn parse_app_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_no_redirect_client()?;
+        let client = create_no_redirect_client(VideoSource::DouYin)?;
         
         let response = client
             .get(share_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::DouYin)
             .await?;
         
         // 获取重定向地址
@@ -72,6 +182,10 @@ impl DouyinParser {
             .ok_or_else(|| anyhow!("未找到重定向地址"))?
             .to_str()?;
         
+        if Self::extract_listing_target(location).is_some() {
+            return Err(anyhow!("这是主页、合集或音乐链接，请使用列表接口 /api/list"));
+        }
+        
         let video_id = self.extract_video_id_from_path(location)?;
         
         if location.contains("ixigua.com") {
@@ -82,6 +196,9 @@ impl DouyinParser {
     }
     
     async fn parse_pc_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
+        if Self::extract_listing_target(share_url).is_some() {
+            return Err(anyhow!("这是主页、合集或音乐链接，请使用列表接口 /api/list"));
+        }
         let video_id = self.extract_video_id_from_path(share_url)?;
         self.parse_video_id(&video_id).await
     }
@@ -121,18 +238,21 @@ impl DouyinParser {
     
     async fn parse_note_data(&self, video_id: &str) -> Result<Value> {
         let web_id = format!("75{}", generate_numeric_id(15));
-        let a_bogus = generate_random_string(64);
         
+        let query = format!(
+            "reflow_source=reflow_page&web_id={}&device_id={}&aweme_ids=%5B{}%5D&request_source=200",
+            web_id, web_id, video_id
+        );
         let url = format!(
-            "https://www.iesdouyin.com/web/api/v2/aweme/slidesinfo/?reflow_source=reflow_page&web_id={}&device_id={}&aweme_ids=%5B{}%5D&request_source=200&a_bogus={}",
-            web_id, web_id, video_id, a_bogus
+            "https://www.iesdouyin.com/web/api/v2/aweme/slidesinfo/?{}",
+            douyin_sign::sign_query_legacy(&query, WEB_USER_AGENT)
         );
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::DouYin)?;
         let response = client
             .get(&url)
-            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .header("User-Agent", WEB_USER_AGENT)
+            .send_via(VideoSource::DouYin)
             .await?;
         
         let json: Value = response.json().await?;
@@ -143,11 +263,8 @@ impl DouyinParser {
             .ok_or_else(|| anyhow!("获取图集数据失败"))
     }
     
-    fn parse_video_data_from_html(&self, html: &str, video_id: &str) -> Result<Value> {
-        let pattern = r"window\._ROUTER_DATA\s*=\s*(.*?)</script>";
-        let json_str = extract_json_from_html(html, pattern)?;
-        
-        let json: Value = serde_json::from_str(&json_str)?;
+    async fn parse_video_data_from_html(&self, html: &str, video_id: &str) -> Result<Value> {
+        let json = js::eval_page_global(html, "window._ROUTER_DATA").await?;
         
         // 调试：打印JSON结构
         tracing::debug!("JSON keys: {:?}", json.as_object().map(|o| o.keys().collect::<Vec<_>>()));
@@ -187,18 +304,22 @@ impl DouyinParser {
             }
         }
         
-        if let Err(e) = std::fs::write("debug_douyin.html", html) {
-            tracing::warn!("无法保存调试HTML: {}", e);
-        }
-        if let Err(e) = std::fs::write("debug_douyin.json", &json_str) {
-            tracing::warn!("无法保存调试JSON: {}", e);
-        }
-        
-        Err(anyhow!("无法解析视频数据，已保存调试文件到 debug_douyin.html 和 debug_douyin.json"))
+        Err(anyhow!("无法解析视频数据：页面中没有 videoInfoRes"))
     }
     
     /// 从JSON数据中提取视频信息
     async fn extract_video_info(&self, data: &Value, is_note: bool) -> Result<VideoParseInfo> {
+        let mut info = self.build_video_info(data, is_note)?;
+        
+        if let Some(video_url) = info.video_url.take() {
+            info.video_url = Some(self.get_redirect_url(&video_url).await.unwrap_or(video_url));
+        }
+        
+        Ok(info)
+    }
+    
+    /// 从aweme数据构建解析结果，视频地址不做重定向
+    fn build_video_info(&self, data: &Value, is_note: bool) -> Result<VideoParseInfo> {
         let mut info = VideoParseInfo::new();
         
         // 提取作者信息
@@ -246,8 +367,7 @@ impl DouyinParser {
             if let Some(video_url) = data.pointer("/video/play_addr/url_list/0")
                 .and_then(|v| v.as_str())
             {
-                let video_url = video_url.replace("playwm", "play");
-                info.video_url = Some(self.get_redirect_url(&video_url).await.unwrap_or(video_url));
+                info.video_url = Some(video_url.replace("playwm", "play"));
             }
         }
         
@@ -255,13 +375,57 @@ impl DouyinParser {
             info.video_url = None;
         }
         
+        // 背景音乐
+        info.music_url = data.pointer("/music/play_url/url_list/0")
+            .or_else(|| data.pointer("/music/play_url/uri"))
+            .and_then(|v| v.as_str())
+            .filter(|s| s.starts_with("http"))
+            .map(|s| s.to_string());
+        info.music = MusicInfo::from_parts(
+            data.pointer("/music/title").and_then(|v| v.as_str()),
+            data.pointer("/music/author").and_then(|v| v.as_str()),
+        );
+        
         if info.video_url.is_none() && info.images.is_empty() {
             return Err(anyhow!("没有找到视频或图集内容"));
         }
         
+        info.set_metadata(Self::extract_metadata(data));
+        
+        if let Some(aweme_id) = data.pointer("/aweme_id").and_then(|v| v.as_str()) {
+            info.set_content_id(VideoSource::DouYin, aweme_id);
+        }
+        info.finish(VideoSource::DouYin);
+        
         Ok(info)
     }
     
+    /// 提取时长、尺寸、发布时间、互动数据和话题
+    fn extract_metadata(data: &Value) -> ContentMetadata {
+        let mut hashtags: Vec<String> = Vec::new();
+        for extra in data.pointer("/text_extra").and_then(|v| v.as_array()).into_iter().flatten() {
+            if let Some(tag) = extra.pointer("/hashtag_name").and_then(|v| v.as_str()) {
+                if !tag.is_empty() && !hashtags.iter().any(|t| t == tag) {
+                    hashtags.push(tag.to_string());
+                }
+            }
+        }
+        
+        ContentMetadata {
+            duration: json_duration(data.pointer("/video/duration"), true)
+                .or_else(|| json_duration(data.pointer("/duration"), true)),
+            width: json_u32(data.pointer("/video/width")),
+            height: json_u32(data.pointer("/video/height")),
+            publish_time: json_timestamp(data.pointer("/create_time")),
+            like_count: json_count(data.pointer("/statistics/digg_count")),
+            comment_count: json_count(data.pointer("/statistics/comment_count")),
+            share_count: json_count(data.pointer("/statistics/share_count")),
+            play_count: json_count(data.pointer("/statistics/play_count")),
+            collect_count: json_count(data.pointer("/statistics/collect_count")),
+            hashtags,
+        }
+    }
+    
     fn get_non_webp_url(&self, url_list: Option<&Value>) -> Option<String> {
         let array = url_list?.as_array()?;
         
@@ -278,14 +442,329 @@ impl DouyinParser {
             .map(|s| s.to_string())
     }
     
+    /// 解析列表类链接，短链先跟随一次重定向
+    async fn resolve_listing_target(&self, url: &str) -> Result<ListingTarget> {
+        if let Some(target) = Self::extract_listing_target(url) {
+            return Ok(target);
+        }
+        
+        let client = create_no_redirect_client(VideoSource::DouYin)?;
+        let response = client
+            .get(url)
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .send_via(VideoSource::DouYin)
+            .await?;
+        
+        response
+            .headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .and_then(Self::extract_listing_target)
+            .ok_or_else(|| anyhow!("不是有效的抖音主页或合集链接"))
+    }
+    
+    /// 识别主页（/user/<sec_uid>、/share/user/<sec_uid>）、合集（/collection/<mix_id>、/mix/detail/<mix_id>）
+    /// 和音乐（/music/<music_id>、/share/music/<music_id>）链接
+    fn extract_listing_target(url: &str) -> Option<ListingTarget> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        let segments: Vec<&str> = parsed_url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        
+        match segments.as_slice() {
+            ["user", sec_uid, ..] | ["share", "user", sec_uid, ..] => {
+                // douyin.com/user/self 是当前登录用户，无法解析
+                (sec_uid.starts_with("MS4wLjABAAAA") || sec_uid.len() > 20)
+                    .then(|| ListingTarget::User(sec_uid.to_string()))
+            }
+            ["collection", mix_id, ..] | ["mix", "detail", mix_id, ..] | ["share", "mix", "detail", mix_id, ..] => {
+                mix_id.chars().all(|c| c.is_ascii_digit()).then(|| ListingTarget::Mix(mix_id.to_string()))
+            }
+            ["music", music_id, ..] | ["share", "music", music_id, ..] => {
+                music_id.chars().all(|c| c.is_ascii_digit()).then(|| ListingTarget::Music(music_id.to_string()))
+            }
+            _ => None,
+        }
+    }
+    
+    /// 请求网页版接口，Cookie 来自账号配置（COOKIE_DOUYIN / COOKIES_DIR）
+    async fn web_api_get(&self, path: &str, query: &str, referer: &str) -> Result<Value> {
+        let query = format!(
+            "{}&{}&msToken={}",
+            WEB_API_PARAMS,
+            query,
+            generate_random_string(107)
+        );
+        let url = format!(
+            "https://www.douyin.com{}?{}",
+            path,
+            douyin_sign::sign_query(&query, WEB_USER_AGENT)
+        );
+        
+        let client = create_http_client(VideoSource::DouYin)?;
+        let text = client
+            .get(&url)
+            .header("User-Agent", WEB_USER_AGENT)
+            .header("Referer", referer)
+            .send_via(VideoSource::DouYin)
+            .await?
+            .text()
+            .await?;
+        
+        // 风控时接口返回空内容
+        if text.trim().is_empty() {
+            return Err(anyhow!("抖音接口返回空数据，可能需要配置Cookie"));
+        }
+        
+        let json: Value = serde_json::from_str(&text)?;
+        let status_code = json.pointer("/status_code").and_then(|v| v.as_i64()).unwrap_or(0);
+        if status_code != 0 {
+            let msg = json.pointer("/status_msg").and_then(|v| v.as_str()).unwrap_or("");
+            return Err(anyhow!("抖音接口返回错误: {} (status_code: {})", msg, status_code));
+        }
+        
+        Ok(json)
+    }
+    
+    async fn fetch_user_profile(&self, sec_uid: &str) -> Result<UserProfile> {
+        let json = self.web_api_get(
+            "/aweme/v1/web/user/profile/other/",
+            &format!("sec_user_id={}", sec_uid),
+            &format!("https://www.douyin.com/user/{}", sec_uid),
+        ).await?;
+        
+        let user = json.pointer("/user")
+            .ok_or_else(|| anyhow!("无法获取用户信息"))?;
+        
+        Ok(Self::parse_user_profile(user))
+    }
+    
+    async fn fetch_mix_page(&self, mix_id: &str, cursor: &str) -> Result<ListingPage> {
+        let json = self.web_api_get(
+            "/aweme/v1/web/mix/aweme/",
+            &format!("mix_id={}&cursor={}&count={}", mix_id, cursor, USER_POSTS_PAGE_SIZE),
+            &format!("https://www.douyin.com/collection/{}", mix_id),
+        ).await?;
+        
+        Ok(self.parse_posts_page(&json))
+    }
+    
+    async fn fetch_music_page(&self, music_id: &str, cursor: &str) -> Result<ListingPage> {
+        let json = self.web_api_get(
+            "/aweme/v1/web/music/aweme/",
+            &format!("music_id={}&cursor={}&count={}", music_id, cursor, USER_POSTS_PAGE_SIZE),
+            &format!("https://www.douyin.com/music/{}", music_id),
+        ).await?;
+        
+        Ok(self.parse_posts_page(&json))
+    }
+    
+    async fn fetch_user_posts(&self, sec_uid: &str, max_cursor: &str) -> Result<ListingPage> {
+        let json = self.web_api_get(
+            "/aweme/v1/web/aweme/post/",
+            &format!("sec_user_id={}&max_cursor={}&count={}&locate_query=false&show_live_replay_strategy=1", sec_uid, max_cursor, USER_POSTS_PAGE_SIZE),
+            &format!("https://www.douyin.com/user/{}", sec_uid),
+        ).await?;
+        
+        Ok(self.parse_posts_page(&json))
+    }
+    
+    fn parse_user_profile(user: &Value) -> UserProfile {
+        UserProfile {
+            author: Author {
+                uid: user.pointer("/sec_uid")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                name: user.pointer("/nickname")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                avatar: user.pointer("/avatar_larger/url_list/0")
+                    .or_else(|| user.pointer("/avatar_thumb/url_list/0"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            },
+            signature: user.pointer("/signature")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            follower_count: json_count(user.pointer("/follower_count")),
+            following_count: json_count(user.pointer("/following_count")),
+            aweme_count: json_count(user.pointer("/aweme_count")),
+            total_favorited: json_count(user.pointer("/total_favorited")),
+        }
+    }
+    
+    /// 解析作品列表接口返回的一页数据，跳过无法识别的作品
+    fn parse_posts_page(&self, json: &Value) -> ListingPage {
+        let items = json.pointer("/aweme_list")
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|aweme| {
+                        let is_note = aweme.pointer("/images").is_some_and(|v| v.is_array());
+                        self.build_video_info(aweme, is_note)
+                            .map_err(|e| tracing::debug!("跳过作品: {}", e))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let has_more = json.pointer("/has_more")
+            .and_then(|v| v.as_i64().or_else(|| v.as_bool().map(i64::from)))
+            .unwrap_or(0) != 0;
+        
+        // 主页接口返回 max_cursor，合集接口返回 cursor
+        let cursor = json.pointer("/max_cursor")
+            .or_else(|| json.pointer("/cursor"))
+            .and_then(|v| v.as_i64().map(|n| n.to_string()).or_else(|| v.as_str().map(str::to_string)))
+            .filter(|_| has_more);
+        
+        ListingPage {
+            profile: None,
+            items,
+            cursor,
+            has_more,
+        }
+    }
+    
+    fn parse_comment_page(json: &Value) -> CommentPage {
+        let items = json.pointer("/comments")
+            .and_then(|v| v.as_array())
+            .map(|comments| {
+                comments.iter()
+                    .map(|c| Comment {
+                        id: json_str(c.pointer("/cid")).unwrap_or_default(),
+                        author: Author {
+                            uid: json_str(c.pointer("/user/sec_uid"))
+                                .or_else(|| json_str(c.pointer("/user/uid")))
+                                .unwrap_or_default(),
+                            name: json_str(c.pointer("/user/nickname")).unwrap_or_default(),
+                            avatar: json_str(c.pointer("/user/avatar_thumb/url_list/0")).unwrap_or_default(),
+                        },
+                        text: json_str(c.pointer("/text")).unwrap_or_default(),
+                        like_count: json_count(c.pointer("/digg_count")),
+                        reply_count: json_count(c.pointer("/reply_comment_total")),
+                        publish_time: json_timestamp(c.pointer("/create_time")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let has_more = json.pointer("/has_more")
+            .and_then(|v| v.as_i64().or_else(|| v.as_bool().map(i64::from)))
+            .unwrap_or(0) != 0;
+        
+        CommentPage {
+            total: json_count(json.pointer("/total")),
+            items,
+            cursor: json_str(json.pointer("/cursor")).filter(|_| has_more),
+            has_more,
+        }
+    }
+    
+    /// 直播间接口需要 ttwid Cookie，先访问直播首页获取
+    async fn fetch_live_ttwid(&self) -> Result<String> {
+        let client = create_no_redirect_client(VideoSource::DouYin)?;
+        let response = client
+            .get("https://live.douyin.com/")
+            .header("User-Agent", WEB_USER_AGENT)
+            .send_via(VideoSource::DouYin)
+            .await?;
+        
+        response.headers()
+            .get_all("set-cookie")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find_map(|cookie| cookie.split(';').next().filter(|c| c.starts_with("ttwid=")))
+            .map(|c| c.to_string())
+            .ok_or_else(|| anyhow!("无法获取抖音直播的ttwid"))
+    }
+    
+    /// 解析直播间 live.douyin.com/<web_rid>
+    async fn parse_live_room(&self, web_rid: &str) -> Result<VideoParseInfo> {
+        let ttwid = self.fetch_live_ttwid().await?;
+        let query = format!("{}&web_rid={}", LIVE_API_PARAMS, web_rid);
+        let url = format!(
+            "https://live.douyin.com/webcast/room/web/enter/?{}",
+            douyin_sign::sign_query(&query, WEB_USER_AGENT)
+        );
+        
+        let client = create_http_client(VideoSource::DouYin)?;
+        let json: Value = client
+            .get(&url)
+            .header("User-Agent", WEB_USER_AGENT)
+            .header("Referer", format!("https://live.douyin.com/{}", web_rid))
+            .header("Cookie", ttwid)
+            .send_via(VideoSource::DouYin)
+            .await?
+            .json()
+            .await?;
+        
+        Self::parse_live_data(&json, web_rid)
+    }
+    
+    fn parse_live_data(json: &Value, web_rid: &str) -> Result<VideoParseInfo> {
+        let status_code = json.pointer("/status_code").and_then(|v| v.as_i64()).unwrap_or(0);
+        if status_code != 0 {
+            let msg = json_str(json.pointer("/data/prompts")).unwrap_or_default();
+            return Err(anyhow!("抖音直播接口返回错误: {} (status_code: {})", msg, status_code));
+        }
+        let room = json.pointer("/data/data/0")
+            .ok_or_else(|| anyhow!("直播间不存在: {}", web_rid))?;
+        let user = json.pointer("/data/user").unwrap_or(&Value::Null);
+        
+        // status 2 为直播中，4 为已下播
+        let status = match room.pointer("/status").and_then(|v| v.as_i64()) {
+            Some(2) => LiveStatus::Live,
+            _ => LiveStatus::Offline,
+        };
+        
+        let mut info = VideoParseInfo::new();
+        info.title = json_str(room.pointer("/title")).unwrap_or_default();
+        info.cover_url = json_str(room.pointer("/cover/url_list/0"));
+        info.author = Author {
+            uid: json_str(user.pointer("/sec_uid"))
+                .or_else(|| json_str(user.pointer("/id_str")))
+                .unwrap_or_default(),
+            name: json_str(user.pointer("/nickname")).unwrap_or_default(),
+            avatar: json_str(user.pointer("/avatar_thumb/url_list/0")).unwrap_or_default(),
+        };
+        
+        let mut streams = Vec::new();
+        if status == LiveStatus::Live {
+            for (format, map) in [
+                (StreamFormat::Flv, "/stream_url/flv_pull_url"),
+                (StreamFormat::Hls, "/stream_url/hls_pull_url_map"),
+            ] {
+                for (key, quality) in LIVE_QUALITIES {
+                    if let Some(url) = json_str(room.pointer(&format!("{}/{}", map, key))) {
+                        streams.push(LiveStream { quality: quality.to_string(), format, url });
+                    }
+                }
+            }
+        }
+        
+        info.set_live(VideoSource::DouYin, LiveInfo {
+            room_id: web_rid.to_string(),
+            status,
+            online_count: json_count(room.pointer("/room_view_stats/display_value"))
+                .or_else(|| json_count(room.pointer("/user_count_str"))),
+            streams,
+        });
+        
+        Ok(info)
+    }
+    
     /// 获取302重定向后的URL
     async fn get_redirect_url(&self, url: &str) -> Result<String> {
-        let client = create_no_redirect_client()?;
+        let client = create_no_redirect_client(VideoSource::DouYin)?;
         
         let response = client
             .get(url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::DouYin)
             .await?;
         
         if let Some(location) = response.headers().get("location") {
@@ -296,3 +775,157 @@ impl DouyinParser {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comment_page() {
+        let json = serde_json::json!({
+            "status_code": 0,
+            "cursor": 20,
+            "has_more": 1,
+            "total": 345,
+            "comments": [{
+                "cid": "7300000000000000100",
+                "text": "好看",
+                "create_time": 1700000000,
+                "digg_count": 12,
+                "reply_comment_total": 3,
+                "user": {"sec_uid": "MS4wLjABAAAA", "nickname": "观众", "avatar_thumb": {"url_list": ["https://p3.douyinpic.com/a.jpeg"]}}
+            }]
+        });
+        let page = DouyinParser::parse_comment_page(&json);
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("20"));
+        assert_eq!(page.total, Some(345));
+        assert_eq!(page.items[0].text, "好看");
+        assert_eq!(page.items[0].reply_count, Some(3));
+        assert_eq!(page.items[0].publish_time.as_deref(), Some("2023-11-14T22:13:20Z"));
+    }
+
+    #[test]
+    fn test_parse_live_data() {
+        let json = serde_json::json!({
+            "status_code": 0,
+            "data": {
+                "data": [{
+                    "status": 2,
+                    "title": "直播标题",
+                    "cover": {"url_list": ["https://p3-webcast.douyinpic.com/cover.jpg"]},
+                    "user_count_str": "1.2万",
+                    "stream_url": {
+                        "flv_pull_url": {
+                            "SD1": "https://pull-flv.douyincdn.com/stage/stream-1_sd.flv",
+                            "FULL_HD1": "https://pull-flv.douyincdn.com/stage/stream-1_or4.flv"
+                        },
+                        "hls_pull_url_map": {"HD1": "https://pull-hls.douyincdn.com/stage/stream-1_hd/index.m3u8"}
+                    }
+                }],
+                "user": {"sec_uid": "MS4wLjABAAAA", "nickname": "主播", "avatar_thumb": {"url_list": ["https://p3.douyinpic.com/a.jpeg"]}}
+            }
+        });
+        let info = DouyinParser::parse_live_data(&json, "123456").unwrap();
+        let live = info.live.as_ref().unwrap();
+
+        assert_eq!(info.content_type, crate::models::ContentType::Live);
+        assert_eq!(info.canonical_url.as_deref(), Some("https://live.douyin.com/123456"));
+        assert_eq!(info.author.name, "主播");
+        assert_eq!(live.online_count, Some(12000));
+        assert_eq!(live.streams.len(), 3);
+        assert_eq!(live.streams[0].quality, "蓝光");
+        assert_eq!(info.video_url.as_deref(), Some("https://pull-flv.douyincdn.com/stage/stream-1_or4.flv"));
+        assert_eq!(live.best_stream(StreamFormat::Hls).map(|s| s.quality.as_str()), Some("超清"));
+
+        let offline = serde_json::json!({"status_code": 0, "data": {"data": [{"status": 4, "title": "下播"}], "user": {}}});
+        let info = DouyinParser::parse_live_data(&offline, "123456").unwrap();
+        assert_eq!(info.live.map(|l| l.status), Some(LiveStatus::Offline));
+        assert_eq!(info.video_url, None);
+    }
+
+    #[test]
+    fn test_extract_listing_target() {
+        let sec_uid = "MS4wLjABAAAAabcdefghijklmnopqrstuvwxyz";
+        assert_eq!(
+            DouyinParser::extract_listing_target(&format!("https://www.douyin.com/user/{}?from_tab_name=main", sec_uid)),
+            Some(ListingTarget::User(sec_uid.to_string()))
+        );
+        assert_eq!(
+            DouyinParser::extract_listing_target(&format!("https://www.iesdouyin.com/share/user/{}", sec_uid)),
+            Some(ListingTarget::User(sec_uid.to_string()))
+        );
+        assert_eq!(
+            DouyinParser::extract_listing_target("https://www.douyin.com/collection/7200000000000000000"),
+            Some(ListingTarget::Mix("7200000000000000000".to_string()))
+        );
+        assert_eq!(
+            DouyinParser::extract_listing_target("https://www.iesdouyin.com/share/mix/detail/7200000000000000000/"),
+            Some(ListingTarget::Mix("7200000000000000000".to_string()))
+        );
+        assert_eq!(
+            DouyinParser::extract_listing_target("https://www.douyin.com/music/7100000000000000000"),
+            Some(ListingTarget::Music("7100000000000000000".to_string()))
+        );
+        assert_eq!(DouyinParser::extract_listing_target("https://www.douyin.com/user/self"), None);
+        assert_eq!(DouyinParser::extract_listing_target("https://www.douyin.com/video/7300000000000000000"), None);
+    }
+
+    #[test]
+    fn test_parse_posts_page() {
+        let json = serde_json::json!({
+            "status_code": 0,
+            "max_cursor": 1700000000000i64,
+            "has_more": 1,
+            "aweme_list": [
+                {
+                    "aweme_id": "7300000000000000001",
+                    "desc": "视频 #话题",
+                    "create_time": 1700000000,
+                    "author": {"sec_uid": "MS4wLjABAAAA", "nickname": "作者"},
+                    "video": {
+                        "play_addr": {"url_list": ["https://aweme.snssdk.com/aweme/v1/playwm/?video_id=v1"]},
+                        "cover": {"url_list": ["https://p3.douyinpic.com/cover.jpeg"]},
+                        "duration": 15000
+                    },
+                    "music": {"title": "原声", "author": "作者", "play_url": {"uri": "https://sf3-cdn-tos.douyinstatic.com/obj/ies-music/1.mp3", "url_list": ["https://sf3-cdn-tos.douyinstatic.com/obj/ies-music/1.mp3"]}},
+                    "statistics": {"digg_count": 10}
+                },
+                {
+                    "aweme_id": "7300000000000000002",
+                    "desc": "图集",
+                    "images": [{"url_list": ["https://p3.douyinpic.com/a.webp", "https://p3.douyinpic.com/a.jpeg"]}],
+                    "video": {"cover": {"url_list": []}}
+                },
+                {"aweme_id": "7300000000000000003"}
+            ]
+        });
+
+        let page = DouyinParser.parse_posts_page(&json);
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("1700000000000"));
+        assert_eq!(page.items.len(), 2);
+
+        let video = &page.items[0];
+        assert_eq!(video.content_id.as_deref(), Some("7300000000000000001"));
+        assert_eq!(video.video_url.as_deref(), Some("https://aweme.snssdk.com/aweme/v1/play/?video_id=v1"));
+        assert_eq!(video.metadata.as_ref().and_then(|m| m.duration), Some(15.0));
+        assert_eq!(video.music_url.as_deref(), Some("https://sf3-cdn-tos.douyinstatic.com/obj/ies-music/1.mp3"));
+        assert_eq!(video.music.as_ref().map(|m| m.title.as_str()), Some("原声"));
+
+        let gallery = &page.items[1];
+        assert_eq!(gallery.content_type, crate::models::ContentType::Gallery);
+        assert_eq!(gallery.images[0].url, "https://p3.douyinpic.com/a.jpeg");
+
+        let profile = DouyinParser::parse_user_profile(&serde_json::json!({
+            "sec_uid": "MS4wLjABAAAA",
+            "nickname": "作者",
+            "signature": "简介",
+            "follower_count": 1200,
+            "aweme_count": 3
+        }));
+        assert_eq!(profile.author.name, "作者");
+        assert_eq!(profile.follower_count, Some(1200));
+        assert_eq!(profile.total_favorited, None);
+    }
+}
@@ -0,0 +1,324 @@
+//! 抖音网页接口签名（X-Bogus / a_bogus）
+//!
+//! 两种签名都由查询串、请求体（或请求方法）、User-Agent 和时间戳计算得出，
+//! 请求时使用的 User-Agent 必须与签名时一致。
+
+use md5::{Digest, Md5};
+use rand::Rng;
+
+/// X-Bogus 输出字母表
+const X_BOGUS_ALPHABET: &[u8; 64] = b"Dkdpgh4ZKsQB80/Mfvw36XI1R25-WUAlEi7NLboqYTOPuzmFjJnryx9HVGcaStCe";
+/// a_bogus 中 User-Agent 摘要使用的字母表
+const UA_ALPHABET: &[u8; 64] = b"ckdp1h4ZKsUB80/Mfvw36XIgR25+WQAlEi7NLboqYTOPuzmFjJnryx9HVGDaStCe";
+/// a_bogus 输出字母表
+const A_BOGUS_ALPHABET: &[u8; 64] = b"Dkdpgh2ZmsQB80/MfvV36XI1R45-WUAlEixNLwoqYTOPuzKFjJnry79HbGcaStCe";
+
+/// 模拟的浏览器窗口与屏幕参数，需与 Windows 版 User-Agent 保持一致
+const BROWSER_FINGERPRINT: &str = "1536|742|1536|864|0|0|0|0|1536|864|1536|864|1536|742|24|24|Win32";
+
+/// X-Bogus 中固定的画布指纹
+const CANVAS_FINGERPRINT: u32 = 536919696;
+
+/// 计算 X-Bogus
+///
+/// `timestamp` 为秒级 Unix 时间戳。`iesdouyin.com` 的 `web/api/v2` 旧接口仍校验 X-Bogus。
+pub fn x_bogus(query: &str, body: &str, user_agent: &str, timestamp: u32) -> String {
+    let ua_hash = md5(base64_standard(&rc4(&[0, 1, 12], user_agent.as_bytes())).as_bytes());
+    let body_hash = md5(&md5(body.as_bytes()));
+    let query_hash = md5(&md5(query.as_bytes()));
+
+    let mut values = vec![
+        64, 0, 1, 12,
+        query_hash[14], query_hash[15],
+        body_hash[14], body_hash[15],
+        ua_hash[14], ua_hash[15],
+    ];
+    values.extend_from_slice(&timestamp.to_be_bytes());
+    values.extend_from_slice(&CANVAS_FINGERPRINT.to_be_bytes());
+    values.push(values.iter().fold(0, |acc, v| acc ^ v));
+
+    // 先取偶数位再取奇数位，随后按固定顺序交错
+    let merged: Vec<u8> = values.iter().step_by(2)
+        .chain(values.iter().skip(1).step_by(2))
+        .copied()
+        .collect();
+    const ORDER: [usize; 19] = [0, 10, 1, 11, 2, 12, 3, 13, 4, 14, 5, 15, 6, 16, 7, 17, 8, 18, 9];
+    let shuffled: Vec<u8> = ORDER.iter().map(|&i| merged[i]).collect();
+
+    let mut garbled = vec![2, 255];
+    garbled.extend(rc4(&[255], &shuffled));
+
+    encode(&garbled, X_BOGUS_ALPHABET)
+}
+
+/// a_bogus 中随时间和随机数变化的部分，固定后签名结果可复现
+#[derive(Debug, Clone, Copy)]
+pub struct ABogusSeed {
+    /// 开始计算时间（毫秒）
+    pub start_ms: u64,
+    /// 结束计算时间（毫秒），通常比开始晚几毫秒
+    pub end_ms: u64,
+    pub randoms: [f64; 3],
+}
+
+impl ABogusSeed {
+    pub fn now() -> Self {
+        let start_ms = chrono::Utc::now().timestamp_millis() as u64;
+        let mut rng = rand::thread_rng();
+        Self {
+            start_ms,
+            end_ms: start_ms + rng.gen_range(4..=8),
+            randoms: [
+                rng.gen::<f64>() * 10000.0,
+                rng.gen::<f64>() * 10000.0,
+                rng.gen::<f64>() * 10000.0,
+            ],
+        }
+    }
+}
+
+/// 计算 a_bogus
+///
+/// 网页版接口的签名只覆盖查询串和请求方法，请求体不参与计算。
+pub fn a_bogus(query: &str, method: &str, user_agent: &str, seed: ABogusSeed) -> String {
+    let mut prefix = Vec::with_capacity(12);
+    prefix.extend(random_list(seed.randoms[0], 1, 2, 5, 45 & 170));
+    prefix.extend(random_list(seed.randoms[1], 1, 0, 0, 0));
+    prefix.extend(random_list(seed.randoms[2], 1, 0, 5, 0));
+
+    let ua_code = sm3(encode(&rc4(&[0, 1, 14], user_agent.as_bytes()), UA_ALPHABET).as_bytes());
+    let query_code = sm3(&sm3(format!("{}cus", query).as_bytes()));
+    let method_code = sm3(&sm3(format!("{}cus", method).as_bytes()));
+
+    let start = seed.start_ms;
+    let end = seed.end_ms;
+    let byte = |value: u64, shift: u32| ((value >> shift) & 255) as u8;
+
+    let mut payload = vec![
+        44, byte(end, 24), 0, 0, 0, 0, 24, query_code[21], method_code[21], 0,
+        ua_code[23], byte(end, 16), 0, 0, 0, 1, 0, 239, query_code[22], method_code[22],
+        ua_code[24], byte(end, 8), 0, 0, 0, 0, byte(end, 0), 0, 0, 14,
+        byte(start, 24), byte(start, 16), 0, byte(start, 8), byte(start, 0), 3, byte(end, 32), 1, byte(start, 32), 1,
+        BROWSER_FINGERPRINT.len() as u8, 0, 0, 0,
+    ];
+    let checksum = payload.iter().fold(0, |acc, v| acc ^ v);
+    payload.extend_from_slice(BROWSER_FINGERPRINT.as_bytes());
+    payload.push(checksum);
+
+    prefix.extend(rc4(b"y", &payload));
+    encode(&prefix, A_BOGUS_ALPHABET)
+}
+
+/// 为查询串追加 a_bogus 参数
+pub fn sign_query(query: &str, user_agent: &str) -> String {
+    let signature = a_bogus(query, "GET", user_agent, ABogusSeed::now());
+    format!(
+        "{}&a_bogus={}",
+        query,
+        url::form_urlencoded::byte_serialize(signature.as_bytes()).collect::<String>()
+    )
+}
+
+/// 为旧接口的查询串同时追加 X-Bogus 和 a_bogus，两者都按原查询串计算
+pub fn sign_query_legacy(query: &str, user_agent: &str) -> String {
+    let signature = x_bogus(query, "", user_agent, chrono::Utc::now().timestamp() as u32);
+    let signed = sign_query(query, user_agent);
+    let a_bogus = &signed[query.len()..];
+    format!("{}&X-Bogus={}{}", query, signature, a_bogus)
+}
+
+fn random_list(random: f64, d: u8, e: u8, f: u8, g: u8) -> [u8; 4] {
+    let value = random as u64;
+    let low = (value & 255) as u8;
+    let high = ((value >> 8) & 255) as u8;
+    [low & 170 | d, low & 85 | e, high & 170 | f, high & 85 | g]
+}
+
+/// 三字节一组的 Base64 式编码，末尾按需补 `=`
+fn encode(data: &[u8], alphabet: &[u8; 64]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        let chars = chunk.len() + 1;
+        for shift in [18, 12, 6, 0].into_iter().take(chars) {
+            out.push(alphabet[((n >> shift) & 63) as usize] as char);
+        }
+    }
+
+    while !out.len().is_multiple_of(4) {
+        out.push('=');
+    }
+    out
+}
+
+fn base64_standard(data: &[u8]) -> String {
+    encode(data, b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/")
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+
+    let mut i: u8 = 0;
+    let mut j: u8 = 0;
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            byte ^ s[s[i as usize].wrapping_add(s[j as usize]) as usize]
+        })
+        .collect()
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data).into()
+}
+
+/// SM3 摘要（GB/T 32905-2016）
+fn sm3(data: &[u8]) -> [u8; 32] {
+    const IV: [u32; 8] = [
+        0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600,
+        0xa96f30bc, 0x163138aa, 0xe38dee4d, 0xb0fb0e4e,
+    ];
+
+    let mut message = data.to_vec();
+    let bit_len = (data.len() as u64) * 8;
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&bit_len.to_be_bytes());
+
+    let p0 = |x: u32| x ^ x.rotate_left(9) ^ x.rotate_left(17);
+    let p1 = |x: u32| x ^ x.rotate_left(15) ^ x.rotate_left(23);
+
+    let mut v = IV;
+    for block in message.chunks(64) {
+        let mut w = [0u32; 68];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..68 {
+            w[i] = p1(w[i - 16] ^ w[i - 9] ^ w[i - 3].rotate_left(15)) ^ w[i - 13].rotate_left(7) ^ w[i - 6];
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = v;
+        for j in 0..64 {
+            let t: u32 = if j < 16 { 0x79cc4519 } else { 0x7a879d8a };
+            let ss1 = a.rotate_left(12)
+                .wrapping_add(e)
+                .wrapping_add(t.rotate_left((j % 32) as u32))
+                .rotate_left(7);
+            let ss2 = ss1 ^ a.rotate_left(12);
+            let (ff, gg) = if j < 16 {
+                (a ^ b ^ c, e ^ f ^ g)
+            } else {
+                ((a & b) | (a & c) | (b & c), (e & f) | (!e & g))
+            };
+            let tt1 = ff.wrapping_add(d).wrapping_add(ss2).wrapping_add(w[j] ^ w[j + 4]);
+            let tt2 = gg.wrapping_add(h).wrapping_add(ss1).wrapping_add(w[j]);
+            d = c;
+            c = b.rotate_left(9);
+            b = a;
+            a = tt1;
+            h = g;
+            g = f.rotate_left(19);
+            f = e;
+            e = p0(tt2);
+        }
+
+        for (state, value) in v.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state ^= value;
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_mut(4).zip(v) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36";
+    const QUERY: &str = "device_platform=webapp&aid=6383&channel=channel_pc_web&sec_user_id=MS4wLjABAAAA&max_cursor=0&count=18";
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// 基础算法使用公开的标准测试向量：SM3 取自 GB/T 32905-2016 附录 A，MD5 取自 RFC 1321，
+    /// RC4 为常用的已知向量
+    #[test]
+    fn test_primitives() {
+        assert_eq!(hex(&sm3(b"abc")), "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0");
+        assert_eq!(
+            hex(&sm3("abcd".repeat(16).as_bytes())),
+            "debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+        );
+        assert_eq!(hex(&rc4(b"Key", b"Plaintext")), "bbf316e8d940af0ad3");
+        assert_eq!(hex(&rc4(b"Secret", b"Attack at dawn")), "45a01f645fc35b383552544b9bf5");
+        assert_eq!(hex(&md5(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(base64_standard(b"hello!"), "aGVsbG8h");
+        assert_eq!(base64_standard(b"hello"), "aGVsbG8=");
+    }
+
+    // 以下两个测试只是自洽性检查：期望值是固定时间戳和随机数下的输出快照，没有与抖音网页实际请求
+    // 中抓取的签名核对，只能发现改动导致的输出变化，不能证明签名会被抖音接受
+
+    #[test]
+    fn test_x_bogus_self_consistency() {
+        assert_eq!(x_bogus(QUERY, "", UA, 1700000000), "DFSzswVY2KbANH-ltmWx-e9WX7j-");
+        assert_eq!(
+            x_bogus("aweme_id=7300000000000000000&aid=6383", "", UA, 1710000000),
+            "DFSzswVYwJTANH-ltbipae9WX7rm"
+        );
+    }
+
+    #[test]
+    fn test_a_bogus_self_consistency() {
+        let seed = ABogusSeed {
+            start_ms: 1700000000000,
+            end_ms: 1700000000006,
+            randoms: [1234.5, 5678.9, 4321.0],
+        };
+        assert_eq!(
+            a_bogus(QUERY, "GET", UA, seed),
+            "E7mhBdugDiViff6f56KLfY3q6ULVYBII0SVkMD2fLaDsqL39HMY29exoIBGvXY8jwG/-Ieujy4hbT3ohrQ2y0Hwf9W0L/25ksDSkKl5Q5xSSs1X9eghgJ04qmkt5SMx2RvB-rOXmqhZHKRbp09oHmhK4bIOwu3GMmf=="
+        );
+
+        let seed = ABogusSeed {
+            start_ms: 1710000000123,
+            end_ms: 1710000000128,
+            randoms: [9999.0, 42.0, 777.7],
+        };
+        assert_eq!(
+            a_bogus("aweme_ids=%5B7300000000000000000%5D&request_source=200", "GET", UA, seed),
+            "djWqBVuDDDDsDfWk55ALfY3q6f8VYBId0SVkMD2fsaDsKL39HMOD9exozQTvWFEjLT/AIeujy4hbT3ohrQ2y0Hwf9W0L/25ksDSkKl5Q5xSSs1X9eghgJ04qmkt5SMx2RvB-rOXmqhZHKRbp09oHmhK4bIOwu3GMuE=="
+        );
+    }
+
+    #[test]
+    fn test_sign_query() {
+        let signed = sign_query(QUERY, UA);
+        let signature = signed.strip_prefix(&format!("{}&a_bogus=", QUERY)).unwrap();
+        assert!(!signature.contains('/') && !signature.contains('='));
+
+        let legacy = sign_query_legacy(QUERY, UA);
+        let rest = legacy.strip_prefix(&format!("{}&X-Bogus=", QUERY)).unwrap();
+        let (x_bogus, a_bogus) = rest.split_once("&a_bogus=").unwrap();
+        assert_eq!(x_bogus.len(), 28);
+        assert!(!a_bogus.is_empty());
+    }
+}
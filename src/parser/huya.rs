@@ -1,6 +1,7 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::models::{Author, ContentMetadata, LiveInfo, LiveStatus, LiveStream, StreamFormat, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration, json_str, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use regex::Regex;
@@ -11,6 +12,10 @@ pub struct HuyaParser;
 #[async_trait]
 impl VideoParser for HuyaParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
+        if let Some(room) = Self::extract_live_room(share_url) {
+            return self.parse_live_room(&room).await;
+        }
+        
         let re = Regex::new(r"/(\d+)\.html")?;
         
         let video_id = re.captures(share_url)
@@ -24,16 +29,36 @@ impl VideoParser for HuyaParser {
     async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
         let req_url = format!("https://liveapi.huya.com/moment/getMomentContent?videoId={}", video_id);
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::HuYa)?;
         let response = client
             .get(&req_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
             .header("Referer", "https://v.huya.com/")
-            .send()
+            .send_via(VideoSource::HuYa)
             .await?;
         
         let json: Value = response.json().await?;
         
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::HuYa, video_id);
+        
+        Ok(info)
+    }
+}
+
+/// 内容为 getMomentContent 接口的响应，其中没有视频ID，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for HuyaParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::HuYa, video_id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
+impl HuyaParser {
+    fn extract_video_info(json: &Value) -> Result<VideoParseInfo> {
         let video_data = json.pointer("/data/moment/videoInfo")
             .ok_or_else(|| anyhow!("无法获取视频数据"))?;
         
@@ -67,7 +92,208 @@ impl VideoParser for HuyaParser {
                 .to_string(),
         };
         
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(video_data.pointer("/videoDuration"), false),
+            width: json_u32(video_data.pointer("/definitions/0/width")),
+            height: json_u32(video_data.pointer("/definitions/0/height")),
+            publish_time: json_timestamp(json.pointer("/data/moment/cTime")),
+            like_count: json_count(json.pointer("/data/moment/favorCount")),
+            comment_count: json_count(json.pointer("/data/moment/commentCount")),
+            play_count: json_count(video_data.pointer("/videoPlayNum")),
+            ..Default::default()
+        });
+        
+        Ok(info)
+    }
+    
+    /// 直播间链接 www.huya.com/<房间号或别名>、m.huya.com/<房间号>
+    fn extract_live_room(url: &str) -> Option<String> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        if !matches!(parsed_url.host_str(), Some("www.huya.com") | Some("m.huya.com")) {
+            return None;
+        }
+        let room = parsed_url.path_segments()?.find(|s| !s.is_empty())?;
+        room.chars().all(|c| c.is_ascii_alphanumeric() || c == '_').then(|| room.to_string())
+    }
+    
+    async fn parse_live_room(&self, room: &str) -> Result<VideoParseInfo> {
+        let client = create_http_client(VideoSource::HuYa)?;
+        
+        // 别名房间（如 huya.com/lpl）需要先从页面中取到数字房间号
+        let room_id = if room.chars().all(|c| c.is_ascii_digit()) {
+            room.to_string()
+        } else {
+            let html = client
+                .get(format!("https://www.huya.com/{}", room))
+                .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+                .send_via(VideoSource::HuYa)
+                .await?
+                .text()
+                .await?;
+            Regex::new(r#""lProfileRoom"\s*:\s*"?(\d+)"#)?
+                .captures(&html)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().to_string())
+                .ok_or_else(|| anyhow!("无法获取虎牙房间号: {}", room))?
+        };
+        
+        let json: Value = client
+            .get(format!("https://mp.huya.com/cache.php?m=Live&do=profileRoom&roomid={}", room_id))
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .header("Referer", "https://m.huya.com/")
+            .send_via(VideoSource::HuYa)
+            .await?
+            .json()
+            .await?;
+        
+        Self::parse_live_data(&json, &room_id)
+    }
+    
+    fn parse_live_data(json: &Value, room_id: &str) -> Result<VideoParseInfo> {
+        if json.pointer("/status").and_then(|v| v.as_i64()) != Some(200) {
+            return Err(anyhow!(
+                "虎牙直播接口返回错误: {}",
+                json.pointer("/message").and_then(|v| v.as_str()).unwrap_or("")
+            ));
+        }
+        let data = json.pointer("/data")
+            .ok_or_else(|| anyhow!("无法获取直播间数据"))?;
+        let live_data = data.pointer("/liveData").unwrap_or(&Value::Null);
+        let profile = data.pointer("/profileInfo").unwrap_or(&Value::Null);
+        
+        let status = match data.pointer("/liveStatus").and_then(|v| v.as_str()) {
+            Some("ON") => LiveStatus::Live,
+            Some("REPLAY") => LiveStatus::Replay,
+            _ => LiveStatus::Offline,
+        };
+        
+        let mut info = VideoParseInfo::new();
+        info.title = json_str(live_data.pointer("/introduction"))
+            .or_else(|| json_str(live_data.pointer("/roomName")))
+            .unwrap_or_default();
+        info.cover_url = json_str(live_data.pointer("/screenshot"));
+        info.author = Author {
+            uid: json_str(profile.pointer("/uid")).unwrap_or_default(),
+            name: json_str(profile.pointer("/nick"))
+                .or_else(|| json_str(live_data.pointer("/nick")))
+                .unwrap_or_default(),
+            avatar: json_str(profile.pointer("/avatar180"))
+                .or_else(|| json_str(live_data.pointer("/avatar180")))
+                .unwrap_or_default(),
+        };
+        
+        let streams = if status == LiveStatus::Offline {
+            Vec::new()
+        } else {
+            Self::live_streams(data.pointer("/stream").unwrap_or(&Value::Null))
+        };
+        
+        info.set_live(VideoSource::HuYa, LiveInfo {
+            room_id: room_id.to_string(),
+            status,
+            online_count: json_count(live_data.pointer("/userCount")),
+            streams,
+        });
+        
         Ok(info)
     }
+    
+    /// 取第一条线路，按码率生成各清晰度的FLV和HLS地址；码率0为原画
+    fn live_streams(stream: &Value) -> Vec<LiveStream> {
+        let Some(line) = stream.pointer("/baseSteamInfoList/0") else {
+            return Vec::new();
+        };
+        let stream_name = line.pointer("/sStreamName").and_then(|v| v.as_str()).unwrap_or("");
+        
+        let mut streams = Vec::new();
+        for (format, key, rates) in [
+            (StreamFormat::Flv, "Flv", "/flv/rateArray"),
+            (StreamFormat::Hls, "Hls", "/hls/rateArray"),
+        ] {
+            let (Some(base), Some(suffix)) = (
+                line.pointer(&format!("/s{}Url", key)).and_then(|v| v.as_str()).filter(|s| !s.is_empty()),
+                line.pointer(&format!("/s{}UrlSuffix", key)).and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            let anti_code = line.pointer(&format!("/s{}AntiCode", key))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .replace("&amp;", "&");
+            let base_url = format!("{}/{}.{}?{}", base, stream_name, suffix, anti_code);
+            
+            let mut rates: Vec<(String, i64)> = stream.pointer(rates)
+                .and_then(|v| v.as_array())
+                .map(|list| {
+                    list.iter()
+                        .map(|r| (
+                            json_str(r.pointer("/sDisplayName")).unwrap_or_default(),
+                            r.pointer("/iBitRate").and_then(|v| v.as_i64()).unwrap_or(0),
+                        ))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if rates.is_empty() {
+                rates.push(("原画".to_string(), 0));
+            }
+            rates.sort_by_key(|(_, bitrate)| if *bitrate == 0 { i64::MIN } else { -bitrate });
+            
+            for (quality, bitrate) in rates {
+                let url = if bitrate == 0 {
+                    base_url.clone()
+                } else {
+                    format!("{}&ratio={}", base_url, bitrate)
+                };
+                streams.push(LiveStream { quality, format, url });
+            }
+        }
+        streams
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_live_data() {
+        assert_eq!(HuyaParser::extract_live_room("https://www.huya.com/lpl").as_deref(), Some("lpl"));
+        assert_eq!(HuyaParser::extract_live_room("https://m.huya.com/660000?from=share").as_deref(), Some("660000"));
+        assert_eq!(HuyaParser::extract_live_room("https://v.huya.com/play/123.html"), None);
+
+        let json = serde_json::json!({
+            "status": 200,
+            "data": {
+                "liveStatus": "ON",
+                "liveData": {"introduction": "直播标题", "screenshot": "https://a.msstatic.com/1.jpg", "userCount": 1234},
+                "profileInfo": {"uid": 1001, "nick": "主播", "avatar180": "https://a.msstatic.com/a.jpg"},
+                "stream": {
+                    "baseSteamInfoList": [{
+                        "sStreamName": "1001-abc",
+                        "sFlvUrl": "https://al.flv.huya.com/src",
+                        "sFlvUrlSuffix": "flv",
+                        "sFlvAntiCode": "wsSecret=x&amp;wsTime=y",
+                        "sHlsUrl": "https://al.hls.huya.com/src",
+                        "sHlsUrlSuffix": "m3u8",
+                        "sHlsAntiCode": "wsSecret=x"
+                    }],
+                    "flv": {"rateArray": [{"sDisplayName": "高清", "iBitRate": 2000}, {"sDisplayName": "原画", "iBitRate": 0}]}
+                }
+            }
+        });
+        let info = HuyaParser::parse_live_data(&json, "660000").unwrap();
+        let live = info.live.as_ref().unwrap();
+
+        assert_eq!(info.content_type, crate::models::ContentType::Live);
+        assert_eq!(info.canonical_url.as_deref(), Some("https://www.huya.com/660000"));
+        assert_eq!(info.author.uid, "1001");
+        assert_eq!(live.status, LiveStatus::Live);
+        assert_eq!(live.online_count, Some(1234));
+        assert_eq!(live.streams.len(), 3);
+        assert_eq!(live.streams[0].quality, "原画");
+        assert_eq!(info.video_url.as_deref(), Some("https://al.flv.huya.com/src/1001-abc.flv?wsSecret=x&wsTime=y"));
+        assert_eq!(live.streams[1].url, "https://al.flv.huya.com/src/1001-abc.flv?wsSecret=x&wsTime=y&ratio=2000");
+        assert_eq!(live.streams[2].format, StreamFormat::Hls);
+    }
+}
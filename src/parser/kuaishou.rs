@@ -1,42 +1,188 @@
-use crate::models::{Author, ImgInfo, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::{create_http_client, extract_json_from_html};
+use crate::js;
+use crate::models::{Author, Comment, CommentPage, ContentMetadata, ImgInfo, ListingPage, MusicInfo, VideoParseInfo, VideoSource};
+use crate::parser::{CommentParser, ListingParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, extract_hashtags, json_count, json_duration, json_str, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use serde_json::Value;
+use serde_json::{json, Value};
 
 pub struct KuaishouParser;
 
+/// 主页作品列表的 GraphQL 查询
+const PROFILE_PHOTO_QUERY: &str = "fragment photoContent on PhotoEntity { id duration caption likeCount viewCount realLikeCount coverUrl photoUrl timestamp } fragment feedContent on Feed { type author { id name headerUrl } photo { ...photoContent } } query visionProfilePhotoList($pcursor: String, $userId: String, $page: String) { visionProfilePhotoList(pcursor: $pcursor, userId: $userId, page: $page) { result llsid pcursor feeds { ...feedContent } } }";
+
+/// 作品评论的 GraphQL 查询
+const COMMENT_LIST_QUERY: &str = "query commentListQuery($photoId: String, $pcursor: String) { visionCommentList(photoId: $photoId, pcursor: $pcursor) { commentCount pcursor rootComments { commentId authorId authorName content headurl timestamp likedCount realLikedCount subCommentCount } } }";
+
 #[async_trait]
 impl VideoParser for KuaishouParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_http_client()?;
+        // PC端作品链接直接按作品ID解析
+        if let Some(photo_id) = Self::extract_pc_photo_id(share_url) {
+            return self.parse_video_id(&photo_id).await;
+        }
+        
+        let client = create_http_client(VideoSource::KuaiShou)?;
         
         let response = client
             .get(share_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
             .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8")
-            .send()
+            .send_via(VideoSource::KuaiShou)
             .await?;
         
         let final_url = response.url().to_string();
         
         let final_url = final_url.replace("/fw/long-video/", "/fw/photo/");
         
+        self.parse_photo_page(&final_url).await
+    }
+    
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        let video_id = video_id.trim();
+        if video_id.is_empty() {
+            return Err(anyhow!("作品ID不能为空"));
+        }
+        self.parse_photo_page(&format!("https://v.m.chenzhongtech.com/fw/photo/{}", video_id)).await
+    }
+}
+
+/// 内容为移动端作品页 /fw/photo/<id> 的 HTML
+#[async_trait]
+impl OfflineParser for KuaishouParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        self.extract_photo_page(content, video_id).await
+    }
+}
+
+/// 游标为接口返回的 pcursor，最后一页为 `no_more`
+#[async_trait]
+impl ListingParser for KuaishouParser {
+    async fn fetch_page(&self, url: &str, cursor: Option<&str>) -> Result<ListingPage> {
+        let user_id = self.resolve_profile_id(url).await?;
+        
+        let body = json!({
+            "operationName": "visionProfilePhotoList",
+            "variables": {
+                "userId": user_id,
+                "pcursor": cursor.unwrap_or(""),
+                "page": "profile",
+            },
+            "query": PROFILE_PHOTO_QUERY,
+        });
+        
+        let client = create_http_client(VideoSource::KuaiShou)?;
+        let json: Value = client
+            .post("https://www.kuaishou.com/graphql")
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", format!("https://www.kuaishou.com/profile/{}", user_id))
+            .json(&body)
+            .send_via(VideoSource::KuaiShou)
+            .await?
+            .json()
+            .await?;
+        
+        let data = json.pointer("/data/visionProfilePhotoList")
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| anyhow!("获取快手主页作品失败，可能需要配置Cookie"))?;
+        
+        Ok(Self::parse_profile_page(data))
+    }
+    
+    fn is_listing_url(&self, url: &str) -> bool {
+        Self::extract_profile_id(url).is_some()
+    }
+}
+
+/// 游标为接口返回的 pcursor，最后一页为 `no_more`
+#[async_trait]
+impl CommentParser for KuaishouParser {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+        let body = json!({
+            "operationName": "commentListQuery",
+            "variables": {
+                "photoId": content_id,
+                "pcursor": cursor.unwrap_or(""),
+            },
+            "query": COMMENT_LIST_QUERY,
+        });
+        
+        let client = create_http_client(VideoSource::KuaiShou)?;
+        let json: Value = client
+            .post("https://www.kuaishou.com/graphql")
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header("Referer", format!("https://www.kuaishou.com/short-video/{}", content_id))
+            .json(&body)
+            .send_via(VideoSource::KuaiShou)
+            .await?
+            .json()
+            .await?;
+        
+        let data = json.pointer("/data/visionCommentList")
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| anyhow!("获取快手评论失败，可能需要配置Cookie"))?;
+        
+        Ok(Self::parse_comment_page(data))
+    }
+}
+
+impl KuaishouParser {
+    fn parse_comment_page(data: &Value) -> CommentPage {
+        let items = data.pointer("/rootComments")
+            .and_then(|v| v.as_array())
+            .map(|comments| {
+                comments.iter()
+                    .map(|c| Comment {
+                        id: json_str(c.pointer("/commentId")).unwrap_or_default(),
+                        author: Author {
+                            uid: json_str(c.pointer("/authorId")).unwrap_or_default(),
+                            name: json_str(c.pointer("/authorName")).unwrap_or_default(),
+                            avatar: json_str(c.pointer("/headurl")).unwrap_or_default(),
+                        },
+                        text: json_str(c.pointer("/content")).unwrap_or_default(),
+                        like_count: json_count(c.pointer("/realLikedCount"))
+                            .or_else(|| json_count(c.pointer("/likedCount"))),
+                        reply_count: json_count(c.pointer("/subCommentCount")),
+                        publish_time: json_timestamp(c.pointer("/timestamp")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let cursor = json_str(data.pointer("/pcursor")).filter(|c| c != "no_more");
+        
+        CommentPage {
+            total: json_count(data.pointer("/commentCount")),
+            items,
+            has_more: cursor.is_some(),
+            cursor,
+        }
+    }
+    
+    /// 解析移动端作品页 /fw/photo/<id>
+    async fn parse_photo_page(&self, final_url: &str) -> Result<VideoParseInfo> {
+        let client = create_http_client(VideoSource::KuaiShou)?;
+        
         // 获取页面内容
         let html = client
-            .get(&final_url)
+            .get(final_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
             .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8")
-            .send()
+            .send_via(VideoSource::KuaiShou)
             .await?
             .text()
             .await?;
         
-        let pattern = r"window\.INIT_STATE\s*=\s*(.*?)</script>";
-        let json_str = extract_json_from_html(&html, pattern)?;
-        
-        let json: Value = serde_json::from_str(&json_str)?;
+        // 作品ID优先取数据中的photoId，其次取落地页路径 /fw/photo/<id>
+        let path_id = url::Url::parse(final_url).ok()
+            .and_then(|url| url.path_segments()?.next_back().map(|s| s.to_string()));
+        self.extract_photo_page(&html, path_id.as_deref()).await
+    }
+    
+    /// 从作品页的 `INIT_STATE` 中提取作品信息
+    async fn extract_photo_page(&self, html: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json = js::eval_page_global(html, "window.INIT_STATE").await?;
         
         let data = self.find_video_data(&json)?;
         
@@ -48,11 +194,106 @@ impl VideoParser for KuaishouParser {
             return Err(anyhow!("获取作品信息失败: result={}", result_code));
         }
         
-        self.extract_video_info(&data)
+        let mut info = self.extract_video_info(&data)?;
+        
+        let photo_id = data.pointer("/photo/photoId")
+            .and_then(|v| v.as_str())
+            .or(video_id)
+            .unwrap_or_default();
+        info.set_content_id(VideoSource::KuaiShou, photo_id);
+        
+        Ok(info)
     }
-}
-
-impl KuaishouParser {
+    
+    /// 获取主页用户ID，短链跟随重定向后再识别
+    async fn resolve_profile_id(&self, url: &str) -> Result<String> {
+        if let Some(user_id) = Self::extract_profile_id(url) {
+            return Ok(user_id);
+        }
+        
+        let client = create_http_client(VideoSource::KuaiShou)?;
+        let response = client
+            .get(url)
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .send_via(VideoSource::KuaiShou)
+            .await?;
+        
+        Self::extract_profile_id(response.url().as_str())
+            .ok_or_else(|| anyhow!("不是有效的快手主页链接"))
+    }
+    
+    /// 支持 www.kuaishou.com/profile/<id> 和移动端 /fw/user/<id>
+    fn extract_profile_id(url: &str) -> Option<String> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        let segments: Vec<&str> = parsed_url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        
+        match segments.as_slice() {
+            ["profile", id, ..] | ["fw", "user", id, ..] => Some(id.to_string()),
+            _ => None,
+        }
+    }
+    
+    fn parse_profile_page(data: &Value) -> ListingPage {
+        let items = data.pointer("/feeds")
+            .and_then(|v| v.as_array())
+            .map(|feeds| {
+                feeds.iter()
+                    .filter_map(|feed| {
+                        let photo = feed.pointer("/photo")?;
+                        let photo_id = photo.pointer("/id").and_then(|v| v.as_str())?;
+                        
+                        let mut info = VideoParseInfo::new();
+                        info.author = Author {
+                            uid: feed.pointer("/author/id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                            name: feed.pointer("/author/name").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                            avatar: feed.pointer("/author/headerUrl").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                        };
+                        info.title = photo.pointer("/caption").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                        info.video_url = photo.pointer("/photoUrl").and_then(|v| v.as_str()).map(|s| s.to_string());
+                        info.cover_url = photo.pointer("/coverUrl").and_then(|v| v.as_str()).map(|s| s.to_string());
+                        info.set_metadata(ContentMetadata {
+                            duration: json_duration(photo.pointer("/duration"), true),
+                            publish_time: json_timestamp(photo.pointer("/timestamp")),
+                            like_count: json_count(photo.pointer("/realLikeCount"))
+                                .or_else(|| json_count(photo.pointer("/likeCount"))),
+                            play_count: json_count(photo.pointer("/viewCount")),
+                            hashtags: extract_hashtags(&info.title),
+                            ..Default::default()
+                        });
+                        info.set_content_id(VideoSource::KuaiShou, photo_id);
+                        
+                        Some(info)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let cursor = data.pointer("/pcursor")
+            .and_then(|v| v.as_str())
+            .filter(|c| !c.is_empty() && *c != "no_more")
+            .map(|c| c.to_string());
+        
+        ListingPage {
+            profile: None,
+            items,
+            has_more: cursor.is_some(),
+            cursor,
+        }
+    }
+    
+    /// 从 www.kuaishou.com/short-video/<id> 链接中提取作品ID
+    fn extract_pc_photo_id(url: &str) -> Option<String> {
+        let parsed_url = url::Url::parse(url).ok()?;
+        if parsed_url.host_str() != Some("www.kuaishou.com") {
+            return None;
+        }
+        let mut segments = parsed_url.path_segments()?;
+        match (segments.next(), segments.next()) {
+            (Some("short-video"), Some(id)) if !id.is_empty() => Some(id.to_string()),
+            _ => None,
+        }
+    }
+    
     fn find_video_data(&self, json: &Value) -> Result<Value> {
         if let Some(obj) = json.as_object() {
             for (_, value) in obj {
@@ -111,9 +352,120 @@ impl KuaishouParser {
                     }
                 }
             }
+            
+            // 图集的背景音乐与图片在同一个CDN上
+            info.music_url = photo.pointer("/ext_params/atlas/music")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|path| format!("https://{}/{}", cdn, path.trim_start_matches('/')));
+        }
+        
+        // 视频作品的配乐在 music 或 soundTrack 字段中
+        let track = photo.pointer("/music").or_else(|| photo.pointer("/soundTrack"));
+        if let Some(track) = track {
+            if info.music_url.is_none() {
+                info.music_url = track.pointer("/audioUrls/0/url")
+                    .and_then(|v| v.as_str())
+                    .filter(|s| s.starts_with("http"))
+                    .map(|s| s.to_string());
+            }
+            info.music = MusicInfo::from_parts(
+                track.pointer("/name").and_then(|v| v.as_str()),
+                track.pointer("/artist").and_then(|v| v.as_str()),
+            );
         }
         
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(photo.pointer("/duration"), true),
+            width: json_u32(photo.pointer("/width")),
+            height: json_u32(photo.pointer("/height")),
+            publish_time: json_timestamp(photo.pointer("/timestamp")),
+            like_count: json_count(photo.pointer("/likeCount")),
+            comment_count: json_count(photo.pointer("/commentCount")),
+            share_count: json_count(photo.pointer("/shareCount")),
+            play_count: json_count(photo.pointer("/viewCount")),
+            collect_count: json_count(photo.pointer("/collectCount")),
+            hashtags: extract_hashtags(&info.title),
+        });
+        
         Ok(info)
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile_page() {
+        assert_eq!(
+            KuaishouParser::extract_profile_id("https://www.kuaishou.com/profile/3xabc").as_deref(),
+            Some("3xabc")
+        );
+        assert_eq!(KuaishouParser::extract_profile_id("https://www.kuaishou.com/short-video/3xdef"), None);
+
+        let data = serde_json::json!({
+            "result": 1,
+            "pcursor": "1.7E12",
+            "feeds": [
+                {
+                    "author": {"id": "3xabc", "name": "作者"},
+                    "photo": {"id": "3xdef", "caption": "作品 #话题", "photoUrl": "https://v.kwaicdn.com/a.mp4", "duration": 12000, "viewCount": 99}
+                },
+                {"author": {"id": "3xabc"}, "photo": null}
+            ]
+        });
+        let page = KuaishouParser::parse_profile_page(&data);
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("1.7E12"));
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].content_id.as_deref(), Some("3xdef"));
+        assert_eq!(page.items[0].metadata.as_ref().and_then(|m| m.duration), Some(12.0));
+
+        let last = KuaishouParser::parse_profile_page(&serde_json::json!({"pcursor": "no_more", "feeds": []}));
+        assert!(!last.has_more);
+        assert_eq!(last.cursor, None);
+    }
+
+    #[test]
+    fn test_extract_atlas_music() {
+        let data = serde_json::json!({
+            "photo": {
+                "caption": "图集",
+                "ext_params": {"atlas": {
+                    "cdn": ["p2.a.yximgs.com"],
+                    "list": ["/ufile/atlas/1.jpg"],
+                    "music": "/ufile/atlas/1.m4a"
+                }},
+                "soundTrack": {"name": "配乐", "artist": "歌手"}
+            }
+        });
+        let info = KuaishouParser.extract_video_info(&data).unwrap();
+        assert_eq!(info.music_url.as_deref(), Some("https://p2.a.yximgs.com/ufile/atlas/1.m4a"));
+        assert_eq!(info.music.as_ref().map(|m| m.author.as_str()), Some("歌手"));
+        assert_eq!(info.images.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_comment_page() {
+        let data = serde_json::json!({
+            "commentCount": 88,
+            "pcursor": "1700000000000",
+            "rootComments": [{
+                "commentId": "900001", "authorId": "3xuser", "authorName": "老铁", "content": "666",
+                "headurl": "https://p2.a.yximgs.com/a.jpg", "timestamp": 1700000000000u64,
+                "likedCount": "1.2w", "realLikedCount": 12034, "subCommentCount": 5
+            }]
+        });
+        let page = KuaishouParser::parse_comment_page(&data);
+        assert!(page.has_more);
+        assert_eq!(page.total, Some(88));
+        assert_eq!(page.items[0].like_count, Some(12034));
+        assert_eq!(page.items[0].publish_time.as_deref(), Some("2023-11-14T22:13:20Z"));
+
+        let last = KuaishouParser::parse_comment_page(&serde_json::json!({"pcursor": "no_more", "rootComments": []}));
+        assert!(!last.has_more);
+        assert_eq!(last.cursor, None);
+    }
+}
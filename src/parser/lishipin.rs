@@ -1,5 +1,6 @@
-use crate::models::VideoParseInfo;
-use crate::parser::VideoParser;
+use crate::models::{VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
 use crate::utils::create_http_client;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
@@ -34,16 +35,38 @@ impl VideoParser for LishipinParser {
             video_id, timestamp
         );
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::LiShiPin)?;
         let response = client
             .get(&req_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; WOW64) AppleWebKit/537.36")
             .header("Referer", format!("https://www.pearvideo.com/detail_{}", video_id))
-            .send()
+            .send_via(VideoSource::LiShiPin)
             .await?;
         
         let json: Value = response.json().await?;
         
+        let mut info = Self::extract_video_info(&json, video_id)?;
+        info.set_content_id(VideoSource::LiShiPin, video_id);
+        
+        Ok(info)
+    }
+}
+
+/// 内容为 videoStatus.jsp 的响应，其中没有视频ID，需要传入
+#[async_trait]
+impl OfflineParser for LishipinParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let video_id = video_id.ok_or_else(|| anyhow!("梨视频的接口响应中没有视频ID，请指定视频ID"))?;
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json, video_id)?;
+        info.set_content_id(VideoSource::LiShiPin, video_id);
+        Ok(info)
+    }
+}
+
+impl LishipinParser {
+    /// 接口返回的地址中以时间戳代替了 `cont-<视频ID>`，需要替换回来
+    fn extract_video_info(json: &Value, video_id: &str) -> Result<VideoParseInfo> {
         let video_info = json.pointer("/videoInfo")
             .ok_or_else(|| anyhow!("无法获取视频数据"))?;
         
@@ -1,5 +1,6 @@
-use crate::models::VideoParseInfo;
-use crate::parser::VideoParser;
+use crate::models::{VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
 use crate::utils::create_http_client;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -11,17 +12,25 @@ pub struct LvzhouParser;
 #[async_trait]
 impl VideoParser for LvzhouParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::LvZhou)?;
         
         let html = client
             .get(share_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::LvZhou)
             .await?
             .text()
             .await?;
         
-        self.parse_html(&html)
+        let mut info = self.parse_html(&html)?;
+        
+        if let Some(sid) = url::Url::parse(share_url).ok()
+            .and_then(|u| u.query_pairs().find(|(key, _)| key == "sid").map(|(_, v)| v.to_string()))
+        {
+            info.set_content_id(VideoSource::LvZhou, sid);
+        }
+        
+        Ok(info)
     }
     
     async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
@@ -30,6 +39,16 @@ impl VideoParser for LvzhouParser {
     }
 }
 
+/// 内容为分享页 HTML，其中没有 sid，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for LvzhouParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let mut info = self.parse_html(content)?;
+        info.set_content_id(VideoSource::LvZhou, video_id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
 impl LvzhouParser {
     fn parse_html(&self, html: &str) -> Result<VideoParseInfo> {
         let document = Html::parse_document(html);
@@ -3,26 +3,25 @@ pub mod kuaishou;
 pub mod bilibili;
 pub mod redbook;
 pub mod xigua;
-pub mod huoshan;
 pub mod pipixia;
 pub mod weibo;
-pub mod weishi;
 pub mod zuiyou;
 pub mod lvzhou;
 pub mod quanmin;
 pub mod lishipin;
-pub mod haokan;
 pub mod huya;
 pub mod pipigaoxiao;
 pub mod acfun;
-pub mod doupai;
-pub mod quanminkge;
-pub mod sixroom;
 pub mod xinpianchang;
+pub mod douyin_sign;
+pub mod rule;
+pub mod plugin;
 
-use crate::models::{VideoParseInfo, VideoSource};
+use crate::models::{CommentPage, Danmaku, ListingItem, ListingPage, VideoParseInfo, VideoSource};
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::Arc;
 
 #[async_trait]
 pub trait VideoParser: Send + Sync {
@@ -33,18 +32,238 @@ pub trait VideoParser: Send + Sync {
     }
 }
 
+/// 分页列表解析器（创作者主页、合集、收藏夹等）
+///
+/// 游标由平台决定（页码、max_cursor、pcursor 等），`None` 表示从第一页开始。
+#[async_trait]
+pub trait ListingParser: Send + Sync {
+    /// 获取一页内容，返回的 `cursor` 用于请求下一页
+    async fn fetch_page(&self, url: &str, cursor: Option<&str>) -> Result<ListingPage>;
+
+    /// 无需请求即可确定是列表链接（短链无法判断时返回 false）
+    fn is_listing_url(&self, _url: &str) -> bool {
+        false
+    }
+}
+
+/// 评论解析器，按内容ID分页获取顶层评论
+///
+/// 内容ID与解析结果中的 `content_id` 一致，游标由平台决定，`None` 表示第一页。
+#[async_trait]
+pub trait CommentParser: Send + Sync {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage>;
+}
+
+/// 弹幕解析器，按内容ID获取视频（第一个分P）的全部弹幕
+#[async_trait]
+pub trait DanmakuParser: Send + Sync {
+    async fn fetch_danmaku(&self, content_id: &str) -> Result<Vec<Danmaku>>;
+}
+
+/// 离线解析器，只执行解析的提取部分，不发起任何请求
+///
+/// `content` 为保存下来的页面或接口响应，即在线解析时请求到的内容。内容中没有内容ID的平台
+/// 需要通过 `video_id` 传入。需要再次请求才能得到的字段（如B站接口响应中的播放地址、
+/// 抖音视频地址的重定向）留空或保持原样。
+#[async_trait]
+pub trait OfflineParser: Send + Sync {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo>;
+}
+
+/// 判断链接是否为合集、主页等列表链接
+pub fn is_listing_url(url: &str) -> bool {
+    identify_video_source(url)
+        .and_then(get_listing_parser)
+        .map(|parser| parser.is_listing_url(url))
+        .unwrap_or(false)
+}
+
 /// 根据URL自动识别平台并解析
 pub async fn parse_video_share_url(share_url: &str) -> Result<VideoParseInfo> {
-    let source = identify_video_source(share_url)?;
+    let source = match identify_video_source(share_url) {
+        Ok(source) => source,
+        // 插件和规则文件新增的平台
+        Err(e) => {
+            if let Some(plugin) = plugin::find_by_url(share_url) {
+                return plugin::PluginParser::new(plugin).parse_share_url(share_url).await;
+            }
+            match rule::find_by_url(share_url) {
+                Some(rule) => return rule::RuleParser::new(rule).parse_share_url(share_url).await,
+                None => return Err(e),
+            }
+        }
+    };
 
     let parser = get_parser(source)?;
 
-    parser.parse_share_url(share_url).await
+    let mut info = parser.parse_share_url(share_url).await?;
+    info.finish(source);
+    Ok(info)
+}
+
+/// 离线解析保存下来的页面或接口响应，`source` 为平台标识，也可以是规则文件新增的平台
+pub async fn parse_offline(source: &str, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+    let Some(builtin) = VideoSource::from_str(source) else {
+        let rule = rule::find_by_name(source)
+            .ok_or_else(|| anyhow::anyhow!("未知的平台: {}", source))?;
+        return rule::RuleParser::new(rule).parse_content(content, video_id).await;
+    };
+    let parser = get_offline_parser(builtin)?;
+    let mut info = parser.parse_content(content, video_id).await?;
+    info.finish(builtin);
+    Ok(info)
 }
 
 pub async fn parse_video_id(source: VideoSource, video_id: &str) -> Result<VideoParseInfo> {
     let parser = get_parser(source)?;
-    parser.parse_video_id(video_id).await
+    let mut info = parser.parse_video_id(video_id).await?;
+    info.finish(source);
+    Ok(info)
+}
+
+/// 以流的形式逐项获取列表，自动翻页
+///
+/// 每一项都带有恢复游标，传回 `cursor` 即可从该项之后继续。
+/// 页内位置编码为 `<页游标>#<跳过数>`，因此即使在一页中间停止也不会重复或遗漏。
+pub fn listing_stream(url: &str, cursor: Option<&str>) -> Result<BoxStream<'static, Result<ListingItem>>> {
+    let source = identify_video_source(url)?;
+    let parser: Arc<dyn ListingParser> = Arc::from(get_listing_parser(source)?);
+    let url = url.to_string();
+    let start = split_cursor(cursor);
+
+    let pages = stream::unfold(Some(start), move |state| {
+        let parser = parser.clone();
+        let url = url.clone();
+        async move {
+            let (page_cursor, skip) = state?;
+            let page = match parser.fetch_page(&url, page_cursor.as_deref()).await {
+                Ok(page) => page,
+                Err(e) => return Some((vec![Err(e)], None)),
+            };
+
+            // 游标没有前进时停止，避免死循环
+            let next_cursor = page.cursor.filter(|c| page.has_more && Some(c) != page_cursor.as_ref());
+            let mut profile = page.profile.filter(|_| skip == 0);
+            let total = page.items.len();
+            let items = page.items
+                .into_iter()
+                .enumerate()
+                .skip(skip)
+                .map(|(index, mut info)| {
+                    info.finish(source);
+                    let cursor = if index + 1 == total {
+                        next_cursor.clone()
+                    } else {
+                        Some(join_cursor(page_cursor.as_deref(), index + 1))
+                    };
+                    Ok(ListingItem { info, cursor, profile: profile.take() })
+                })
+                .collect::<Vec<_>>();
+
+            let next_state = next_cursor.map(|c| (Some(c), 0));
+            Some((items, next_state))
+        }
+    });
+
+    Ok(pages.flat_map(stream::iter).boxed())
+}
+
+/// 获取最多 `limit` 项，返回的游标指向最后一项之后；中途出错时返回已获取的部分
+pub async fn collect_listing(url: &str, cursor: Option<&str>, limit: usize) -> Result<ListingPage> {
+    let mut stream = listing_stream(url, cursor)?.take(limit);
+    let mut page = ListingPage {
+        cursor: cursor.map(str::to_string),
+        has_more: true,
+        ..Default::default()
+    };
+
+    while let Some(item) = stream.next().await {
+        match item {
+            Ok(item) => {
+                page.cursor = item.cursor;
+                page.profile = page.profile.or(item.profile);
+                page.items.push(item.info);
+            }
+            Err(e) if page.items.is_empty() => return Err(e),
+            Err(e) => {
+                tracing::warn!("列表获取中断: {}", e);
+                break;
+            }
+        }
+    }
+
+    page.has_more = page.cursor.is_some() && !page.items.is_empty();
+    Ok(page)
+}
+
+/// 拆分恢复游标为（页游标, 页内跳过数）
+fn split_cursor(cursor: Option<&str>) -> (Option<String>, usize) {
+    let Some(cursor) = cursor.filter(|c| !c.is_empty()) else {
+        return (None, 0);
+    };
+
+    match cursor.rsplit_once('#') {
+        Some((page_cursor, skip)) if skip.parse::<usize>().is_ok() => (
+            (!page_cursor.is_empty()).then(|| page_cursor.to_string()),
+            skip.parse().unwrap_or(0),
+        ),
+        _ => (Some(cursor.to_string()), 0),
+    }
+}
+
+fn join_cursor(page_cursor: Option<&str>, skip: usize) -> String {
+    format!("{}#{}", page_cursor.unwrap_or(""), skip)
+}
+
+/// 获取一页评论
+pub async fn fetch_comments(source: VideoSource, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+    let parser = get_comment_parser(source)?;
+    let mut page = parser.fetch_comments(content_id.trim(), cursor.filter(|c| !c.is_empty())).await?;
+    // 游标没有前进时视为最后一页
+    if page.cursor.is_none() || page.cursor.as_deref() == cursor {
+        page.has_more = false;
+        page.cursor = None;
+    }
+    Ok(page)
+}
+
+/// 由分享链接获取一页评论，先解析出内容ID
+pub async fn fetch_comments_by_url(url: &str, cursor: Option<&str>) -> Result<CommentPage> {
+    let source = identify_video_source(url)?;
+    // 提前检查，避免为不支持的平台白白解析一次
+    get_comment_parser(source)?;
+    let info = parse_video_share_url(url).await?;
+    let content_id = info.content_id
+        .ok_or_else(|| anyhow::anyhow!("无法获取内容ID"))?;
+    fetch_comments(source, &content_id, cursor).await
+}
+
+/// 获取视频全部弹幕，按出现时间排序
+pub async fn fetch_danmaku(source: VideoSource, content_id: &str) -> Result<Vec<Danmaku>> {
+    let parser = get_danmaku_parser(source)?;
+    let mut items = parser.fetch_danmaku(content_id.trim()).await?;
+    items.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(items)
+}
+
+/// 由分享链接获取弹幕，返回平台、内容ID与弹幕
+pub async fn fetch_danmaku_by_url(url: &str) -> Result<(VideoSource, String, Vec<Danmaku>)> {
+    let source = identify_video_source(url)?;
+    get_danmaku_parser(source)?;
+    let info = parse_video_share_url(url).await?;
+    let content_id = info.content_id
+        .ok_or_else(|| anyhow::anyhow!("无法获取内容ID"))?;
+    let items = fetch_danmaku(source, &content_id).await?;
+    Ok((source, content_id, items))
+}
+
+/// 由平台和内容ID还原分享链接，用于把存储的ID重新转换为可访问、可解析的地址
+pub fn canonical_share_url(source: VideoSource, video_id: &str) -> Result<String> {
+    let video_id = video_id.trim();
+    if video_id.is_empty() {
+        return Err(anyhow::anyhow!("视频ID不能为空"));
+    }
+    Ok(source.canonical_url(video_id))
 }
 
 fn identify_video_source(url: &str) -> Result<VideoSource> {
@@ -85,32 +304,86 @@ fn identify_video_source(url: &str) -> Result<VideoSource> {
 }
 
 fn get_parser(source: VideoSource) -> Result<Box<dyn VideoParser>> {
+    // 插件和规则文件优先，便于不重新编译就修正解析逻辑
+    if let Some(plugin) = plugin::find(source) {
+        return Ok(Box::new(plugin::PluginParser::new(plugin)));
+    }
+    if let Some(rule) = rule::find(source) {
+        return Ok(Box::new(rule::RuleParser::new(rule)));
+    }
     match source {
         VideoSource::DouYin => Ok(Box::new(douyin::DouyinParser)),
         VideoSource::KuaiShou => Ok(Box::new(kuaishou::KuaishouParser)),
         VideoSource::BiliBili => Ok(Box::new(bilibili::BilibiliParser)),
         VideoSource::RedBook => Ok(Box::new(redbook::RedbookParser)),
         VideoSource::XiGua => Ok(Box::new(xigua::XiguaParser)),
-        VideoSource::HuoShan => Ok(Box::new(huoshan::HuoshanParser)),
         VideoSource::PiPiXia => Ok(Box::new(pipixia::PipixiaParser)),
         VideoSource::WeiBo => Ok(Box::new(weibo::WeiboParser)),
-        VideoSource::WeiShi => Ok(Box::new(weishi::WeishiParser)),
         VideoSource::ZuiYou => Ok(Box::new(zuiyou::ZuiyouParser)),
         VideoSource::LvZhou => Ok(Box::new(lvzhou::LvzhouParser)),
         VideoSource::QuanMin => Ok(Box::new(quanmin::QuanminParser)),
         VideoSource::LiShiPin => Ok(Box::new(lishipin::LishipinParser)),
-        VideoSource::HaoKan => Ok(Box::new(haokan::HaokanParser)),
         VideoSource::HuYa => Ok(Box::new(huya::HuyaParser)),
         VideoSource::PiPiGaoXiao => Ok(Box::new(pipigaoxiao::PipigaoxiaoParser)),
         VideoSource::AcFun => Ok(Box::new(acfun::AcfunParser)),
-        VideoSource::DouPai => Ok(Box::new(doupai::DoupaiParser)),
-        VideoSource::QuanMinKGe => Ok(Box::new(quanminkge::QuanminkgeParser)),
-        VideoSource::SixRoom => Ok(Box::new(sixroom::SixroomParser)),
         VideoSource::XinPianChang => Ok(Box::new(xinpianchang::XinpianchangParser)),
         _ => Err(anyhow::anyhow!("平台 {} 暂不支持", source.display_name())),
     }
 }
 
+/// 插件只能在线解析，插件替换的平台离线时仍按规则或内置解析器提取
+fn get_offline_parser(source: VideoSource) -> Result<Box<dyn OfflineParser>> {
+    if let Some(rule) = rule::find(source) {
+        return Ok(Box::new(rule::RuleParser::new(rule)));
+    }
+    match source {
+        VideoSource::DouYin => Ok(Box::new(douyin::DouyinParser)),
+        VideoSource::KuaiShou => Ok(Box::new(kuaishou::KuaishouParser)),
+        VideoSource::BiliBili => Ok(Box::new(bilibili::BilibiliParser)),
+        VideoSource::RedBook => Ok(Box::new(redbook::RedbookParser)),
+        VideoSource::XiGua => Ok(Box::new(xigua::XiguaParser)),
+        VideoSource::PiPiXia => Ok(Box::new(pipixia::PipixiaParser)),
+        VideoSource::WeiBo => Ok(Box::new(weibo::WeiboParser)),
+        VideoSource::ZuiYou => Ok(Box::new(zuiyou::ZuiyouParser)),
+        VideoSource::LvZhou => Ok(Box::new(lvzhou::LvzhouParser)),
+        VideoSource::QuanMin => Ok(Box::new(quanmin::QuanminParser)),
+        VideoSource::LiShiPin => Ok(Box::new(lishipin::LishipinParser)),
+        VideoSource::HuYa => Ok(Box::new(huya::HuyaParser)),
+        VideoSource::PiPiGaoXiao => Ok(Box::new(pipigaoxiao::PipigaoxiaoParser)),
+        VideoSource::AcFun => Ok(Box::new(acfun::AcfunParser)),
+        VideoSource::XinPianChang => Ok(Box::new(xinpianchang::XinpianchangParser)),
+        _ => Err(anyhow::anyhow!("平台 {} 暂不支持离线解析", source.display_name())),
+    }
+}
+
+fn get_listing_parser(source: VideoSource) -> Result<Box<dyn ListingParser>> {
+    match source {
+        VideoSource::DouYin => Ok(Box::new(douyin::DouyinParser)),
+        VideoSource::BiliBili => Ok(Box::new(bilibili::BilibiliParser)),
+        VideoSource::KuaiShou => Ok(Box::new(kuaishou::KuaishouParser)),
+        _ => Err(anyhow::anyhow!("平台 {} 暂不支持列表解析", source.display_name())),
+    }
+}
+
+fn get_comment_parser(source: VideoSource) -> Result<Box<dyn CommentParser>> {
+    match source {
+        VideoSource::DouYin => Ok(Box::new(douyin::DouyinParser)),
+        VideoSource::BiliBili => Ok(Box::new(bilibili::BilibiliParser)),
+        VideoSource::WeiBo => Ok(Box::new(weibo::WeiboParser)),
+        VideoSource::KuaiShou => Ok(Box::new(kuaishou::KuaishouParser)),
+        VideoSource::PiPiXia => Ok(Box::new(pipixia::PipixiaParser)),
+        _ => Err(anyhow::anyhow!("平台 {} 暂不支持获取评论", source.display_name())),
+    }
+}
+
+fn get_danmaku_parser(source: VideoSource) -> Result<Box<dyn DanmakuParser>> {
+    match source {
+        VideoSource::BiliBili => Ok(Box::new(bilibili::BilibiliParser)),
+        VideoSource::AcFun => Ok(Box::new(acfun::AcfunParser)),
+        _ => Err(anyhow::anyhow!("平台 {} 暂不支持获取弹幕", source.display_name())),
+    }
+}
+
 pub fn get_supported_platforms() -> Vec<(VideoSource, &'static str, Vec<&'static str>)> {
     vec![
         (VideoSource::DouYin, "抖音", VideoSource::DouYin.share_url_domains()),
@@ -137,3 +410,130 @@ pub fn get_supported_platforms() -> Vec<(VideoSource, &'static str, Vec<&'static
     ]
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_share_url_round_trip() {
+        for source in VideoSource::ALL {
+            if get_parser(source).is_err() {
+                continue;
+            }
+            let url = canonical_share_url(source, "12345").unwrap();
+            assert_eq!(identify_video_source(&url).unwrap(), source, "{}", url);
+        }
+
+        assert_eq!(
+            canonical_share_url(VideoSource::BiliBili, "BV1xx411c7mD").unwrap(),
+            "https://www.bilibili.com/video/BV1xx411c7mD"
+        );
+        assert!(canonical_share_url(VideoSource::DouYin, " ").is_err());
+    }
+
+    #[test]
+    fn test_listing_cursor_round_trip() {
+        assert_eq!(split_cursor(None), (None, 0));
+        assert_eq!(split_cursor(Some("")), (None, 0));
+        assert_eq!(split_cursor(Some("1700000000000")), (Some("1700000000000".to_string()), 0));
+        assert_eq!(split_cursor(Some(&join_cursor(Some("2"), 5))), (Some("2".to_string()), 5));
+        assert_eq!(split_cursor(Some(&join_cursor(None, 3))), (None, 3));
+        // 平台游标自身含 # 但后缀不是数字时原样保留
+        assert_eq!(split_cursor(Some("abc#def")), (Some("abc#def".to_string()), 0));
+    }
+
+    #[tokio::test]
+    async fn test_parse_offline() {
+        let view = r#"{"code":0,"message":"0","data":{"bvid":"BV1xx411c7mD","title":"标题","pic":"http://i0.hdslb.com/a.jpg",
+            "owner":{"mid":2,"name":"UP","face":"http://i0.hdslb.com/face.jpg"},"pages":[{"cid":1}],"duration":60,
+            "pubdate":1700000000,"dimension":{"width":1920,"height":1080,"rotate":0},
+            "stat":{"view":10,"like":5,"reply":1,"share":0,"favorite":2}}}"#;
+        let info = parse_offline("bilibili", view, None).await.unwrap();
+        assert_eq!(info.source, "bilibili");
+        assert_eq!(info.content_id.as_deref(), Some("BV1xx411c7mD"));
+        assert_eq!(info.author.name, "UP");
+        assert!(info.video_url.is_none());
+
+        let html = r#"<html><script>window.__INITIAL_STATE__ = {"note": {"currentNoteId": "abc",
+            "noteDetailMap": {"abc": {"note": {"type": "normal", "title": "笔记", "desc": "",
+            "user": {"userId": "u1", "nickname": "作者", "avatar": ""},
+            "imageList": [{"urlDefault": "http://sns-webpic-qc.xhscdn.com/1/a!nd_dft_wlteh_webp_3"}]}}}}}</script></html>"#;
+        let info = parse_offline("redbook", html, None).await.unwrap();
+        assert_eq!(info.content_id.as_deref(), Some("abc"));
+        assert_eq!(info.title, "笔记");
+
+        assert!(parse_offline("unknown", "{}", None).await.is_err());
+        assert!(parse_offline("bilibili", "not json", None).await.is_err());
+    }
+
+    /// 回放 `fixtures/<平台>/*.json` 并与快照比对，设置 `UPDATE_GOLDEN=1` 时改为重写快照。
+    /// 合成夹具只能发现代码回归，结束时列出还没有真实录制夹具的平台
+    #[tokio::test]
+    async fn test_golden_fixtures() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut failures = Vec::new();
+        let mut synthetic_only = Vec::new();
+
+        for source in VideoSource::ALL {
+            if get_parser(source).is_err() {
+                continue;
+            }
+            let mut paths: Vec<_> = std::fs::read_dir(root.join(source.as_str()))
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter(|path| !path.to_string_lossy().ends_with(".golden.json"))
+                .collect();
+            paths.sort();
+            if paths.is_empty() {
+                failures.push(format!("{}: 没有夹具", source.as_str()));
+            }
+            let mut recorded = false;
+
+            for path in paths {
+                let name = path.strip_prefix(&root).unwrap_or(&path).display().to_string();
+                let result = match crate::replay::load_fixture(&path) {
+                    Ok(fixture) => {
+                        recorded |= !fixture.synthetic;
+                        crate::replay::replay_fixture(&fixture).await
+                    }
+                    Err(e) => Err(e),
+                };
+                let info = match result {
+                    Ok(info) => serde_json::to_value(&info).unwrap(),
+                    Err(e) => {
+                        failures.push(format!("{}: {}", name, e));
+                        continue;
+                    }
+                };
+
+                let golden_path = crate::replay::golden_path(&path);
+                if update {
+                    std::fs::write(&golden_path, serde_json::to_string_pretty(&info).unwrap() + "\n").unwrap();
+                    continue;
+                }
+                let golden: Option<serde_json::Value> = std::fs::read_to_string(&golden_path)
+                    .ok()
+                    .and_then(|text| serde_json::from_str(&text).ok());
+                if golden.as_ref() != Some(&info) {
+                    failures.push(format!(
+                        "{}: 与快照不一致\n{}",
+                        name,
+                        serde_json::to_string_pretty(&info).unwrap()
+                    ));
+                }
+            }
+            if !recorded {
+                synthetic_only.push(source.as_str());
+            }
+        }
+
+        if !synthetic_only.is_empty() {
+            eprintln!("以下平台只有合成夹具，快照比对不能证明能解析线上响应: {}", synthetic_only.join(", "));
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+}
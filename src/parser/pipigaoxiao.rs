@@ -1,6 +1,7 @@
-use crate::models::VideoParseInfo;
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::models::{VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_str};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::{json, Value};
@@ -31,17 +32,38 @@ impl VideoParser for PipigaoxiaoParser {
             "mid": null
         });
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::PiPiGaoXiao)?;
         let response = client
             .post(req_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; WOW64) AppleWebKit/537.36")
             .header("Referer", req_url)
             .json(&post_data)
-            .send()
+            .send_via(VideoSource::PiPiGaoXiao)
             .await?;
         
         let json: Value = response.json().await?;
         
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::PiPiGaoXiao, video_id);
+        
+        Ok(info)
+    }
+}
+
+/// 内容为 fetch_content 接口的响应
+#[async_trait]
+impl OfflineParser for PipigaoxiaoParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json)?;
+        let id = video_id.map(str::to_string).or_else(|| json_str(json.pointer("/data/post/id")));
+        info.set_content_id(VideoSource::PiPiGaoXiao, id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
+impl PipigaoxiaoParser {
+    fn extract_video_info(json: &Value) -> Result<VideoParseInfo> {
         // 检查是否有错误消息
         if let Some(msg) = json.pointer("/msg") {
             if msg.is_string() {
@@ -1,6 +1,7 @@
-use crate::models::{Author, ImgInfo, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::{create_no_redirect_client, create_http_client};
+use crate::models::{Author, Comment, CommentPage, ContentMetadata, ImgInfo, MusicInfo, VideoParseInfo, VideoSource};
+use crate::parser::{CommentParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_no_redirect_client, create_http_client, json_count, json_duration, json_str, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -10,12 +11,12 @@ pub struct PipixiaParser;
 #[async_trait]
 impl VideoParser for PipixiaParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_no_redirect_client()?;
+        let client = create_no_redirect_client(VideoSource::PiPiXia)?;
         
         let response = client
             .get(share_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::PiPiXia)
             .await?;
         
         let location = response.headers()
@@ -36,20 +37,28 @@ impl VideoParser for PipixiaParser {
     }
     
     async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
-        let req_url = format!(
-            "https://api.pipix.com/bds/cell/cell_comment/?offset=0&cell_type=1&api_version=1&cell_id={}&ac=wifi&channel=huawei_1319_64&aid=1319&app_name=super",
-            video_id
-        );
-        
-        let client = create_http_client()?;
-        let response = client
-            .get(&req_url)
-            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
-            .await?;
+        let json = self.fetch_cell_comment(video_id, 0).await?;
         
-        let json: Value = response.json().await?;
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::PiPiXia, video_id);
         
+        Ok(info)
+    }
+}
+
+/// 内容为 cell_comment 接口的响应，其中没有视频ID，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for PipixiaParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::PiPiXia, video_id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
+impl PipixiaParser {
+    fn extract_video_info(json: &Value) -> Result<VideoParseInfo> {
         let data = json.pointer("/data/cell_comments/0/comment_info/item")
             .ok_or_else(|| anyhow!("无法获取视频数据"))?;
         
@@ -121,7 +130,123 @@ impl VideoParser for PipixiaParser {
         
         info.images = images;
         
+        // 配乐
+        info.music_url = data.pointer("/music/play_url/url_list/0/url")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+        info.music = MusicInfo::from_parts(
+            data.pointer("/music/title").and_then(|v| v.as_str()),
+            data.pointer("/music/author").and_then(|v| v.as_str()),
+        );
+        
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(data.pointer("/video/duration"), false),
+            width: json_u32(data.pointer("/video/video_width")),
+            height: json_u32(data.pointer("/video/video_height")),
+            publish_time: json_timestamp(data.pointer("/create_time")),
+            like_count: json_count(data.pointer("/stats/like_count")),
+            comment_count: json_count(data.pointer("/stats/comment_count")),
+            share_count: json_count(data.pointer("/stats/share_count")),
+            play_count: json_count(data.pointer("/stats/play_count")),
+            ..Default::default()
+        });
+        
         Ok(info)
     }
 }
 
+
+/// 游标为偏移量
+#[async_trait]
+impl CommentParser for PipixiaParser {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+        let offset: u64 = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
+        let json = self.fetch_cell_comment(content_id, offset).await?;
+        Ok(Self::parse_comment_page(&json, offset))
+    }
+}
+
+impl PipixiaParser {
+    /// 作品详情和评论都来自 cell_comment 接口，每条评论附带所属作品
+    async fn fetch_cell_comment(&self, cell_id: &str, offset: u64) -> Result<Value> {
+        let req_url = format!(
+            "https://api.pipix.com/bds/cell/cell_comment/?offset={}&cell_type=1&api_version=1&cell_id={}&ac=wifi&channel=huawei_1319_64&aid=1319&app_name=super",
+            offset, cell_id
+        );
+        
+        let client = create_http_client(VideoSource::PiPiXia)?;
+        let response = client
+            .get(&req_url)
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .send_via(VideoSource::PiPiXia)
+            .await?;
+        
+        Ok(response.json().await?)
+    }
+    
+    fn parse_comment_page(json: &Value, offset: u64) -> CommentPage {
+        let items: Vec<Comment> = json.pointer("/data/cell_comments")
+            .and_then(|v| v.as_array())
+            .map(|comments| {
+                comments.iter()
+                    .filter_map(|c| c.pointer("/comment_info"))
+                    .map(|c| Comment {
+                        id: json_str(c.pointer("/comment_id_str"))
+                            .or_else(|| json_str(c.pointer("/comment_id")))
+                            .unwrap_or_default(),
+                        author: Author {
+                            uid: json_str(c.pointer("/user/id")).unwrap_or_default(),
+                            name: json_str(c.pointer("/user/name")).unwrap_or_default(),
+                            avatar: json_str(c.pointer("/user/avatar/download_list/0/url")).unwrap_or_default(),
+                        },
+                        text: json_str(c.pointer("/text")).unwrap_or_default(),
+                        like_count: json_count(c.pointer("/like_count")),
+                        reply_count: json_count(c.pointer("/reply_count")),
+                        publish_time: json_timestamp(c.pointer("/create_time")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let has_more = json.pointer("/data/has_more").and_then(|v| v.as_bool()).unwrap_or(false)
+            && !items.is_empty();
+        
+        CommentPage {
+            total: json_count(json.pointer("/data/total")),
+            cursor: has_more.then(|| (offset + items.len() as u64).to_string()),
+            has_more,
+            items,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comment_page() {
+        let json = serde_json::json!({
+            "data": {
+                "has_more": true,
+                "cell_comments": [
+                    {"comment_info": {
+                        "comment_id_str": "7100000000000000001", "text": "神评", "create_time": 1700000000,
+                        "like_count": 520, "reply_count": 13,
+                        "user": {"id": "61000", "name": "虾友", "avatar": {"download_list": [{"url": "https://p3.pipix.com/a.jpg"}]}},
+                        "item": {"content": "作品"}
+                    }},
+                    {"comment_info": {"comment_id": 7100000000000000002u64, "text": "第二", "user": {"id": 61001}}}
+                ]
+            }
+        });
+        let page = PipixiaParser::parse_comment_page(&json, 20);
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("22"));
+        assert_eq!(page.items[0].author.avatar, "https://p3.pipix.com/a.jpg");
+        assert_eq!(page.items[1].id, "7100000000000000002");
+        assert_eq!(page.items[1].author.uid, "61001");
+    }
+}
@@ -0,0 +1,450 @@
+//! WASM 解析插件
+//!
+//! 需要真实逻辑、又不值得改动本项目的平台可以编译为 WASM 插件放入插件目录。
+//! 插件与宿主之间的约定（指针与长度均为 i32，返回值把指针放在高 32 位、长度放在低 32 位）：
+//!
+//! 插件导出
+//! - `memory`：线性内存
+//! - `alloc(len) -> ptr`：分配内存，宿主写入参数和 HTTP 响应时调用
+//! - `plugin_info() -> ptr_len`：JSON `{"source": "...", "name": "...", "domains": ["..."]}`
+//! - `parse(ptr, len) -> ptr_len`：参数为分享链接，返回 `VideoParseInfo` JSON 或 `{"error": "..."}`
+//! - `parse_id(ptr, len) -> ptr_len`：可选，按视频ID解析
+//!
+//! 宿主导入（模块名 `host`）
+//! - `http_fetch(ptr, len) -> ptr_len`：请求为 JSON
+//!   `{"url", "method", "headers", "body", "follow_redirects"}`，响应为
+//!   `{"status", "url", "headers", "body"}` 或 `{"error": "..."}`。请求经过宿主的 HTTP 客户端，
+//!   超时、代理以及内置平台的限流和熔断都照常生效
+//! - `log(ptr, len)`：输出一行日志
+
+use crate::config::config;
+use crate::models::{VideoParseInfo, VideoSource};
+use crate::parser::VideoParser;
+use crate::upstream::{UpstreamKey, UpstreamRequest};
+use crate::utils::create_client;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+/// 单次解析可执行的指令量上限
+const FUEL_LIMIT: u64 = 2_000_000_000;
+
+/// 每执行这么多指令让出一次，避免长时间占用异步线程
+const FUEL_YIELD_INTERVAL: u64 = 100_000;
+
+/// 插件线性内存上限
+const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// 单次解析的总时长上限
+const PARSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 单次解析最多发起的 HTTP 请求数
+const MAX_FETCHES: u32 = 20;
+
+/// 交给插件的响应体上限
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// 插件声明的平台信息
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginInfo {
+    /// 平台标识；与内置平台同名时替换其解析器，否则为新增平台
+    pub source: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 分享链接域名，新增平台必填
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+impl PluginInfo {
+    pub fn builtin_source(&self) -> Option<VideoSource> {
+        VideoSource::from_str(&self.source)
+    }
+}
+
+/// 已编译的插件
+pub struct WasmPlugin {
+    pub info: PluginInfo,
+    module: Module,
+}
+
+/// 插件发起的 HTTP 请求
+#[derive(Debug, Deserialize)]
+struct FetchRequest {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default = "default_true")]
+    follow_redirects: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct FetchResponse {
+    status: u16,
+    url: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// 每次解析的宿主状态
+struct HostState {
+    plugin: String,
+    upstream: UpstreamKey,
+    fetches: u32,
+    limits: StoreLimits,
+}
+
+/// 插件运行环境，所有插件共享
+struct PluginHost {
+    engine: Engine,
+    linker: Linker<HostState>,
+    plugins: HashMap<String, Arc<WasmPlugin>>,
+}
+
+impl PluginHost {
+    fn new() -> Result<Self> {
+        let mut wasm_config = wasmtime::Config::new();
+        wasm_config.async_support(true).consume_fuel(true);
+        let engine = Engine::new(&wasm_config)?;
+
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap_async("host", "http_fetch", |mut caller: Caller<'_, HostState>, (ptr, len): (i32, i32)| {
+            Box::new(async move {
+                let memory = guest_memory(&mut caller)?;
+                let request = read_guest(&caller, memory, ptr, len)?;
+                let response = match host_fetch(caller.data_mut(), &request).await {
+                    Ok(response) => serde_json::to_vec(&response)?,
+                    Err(e) => serde_json::to_vec(&serde_json::json!({ "error": e.to_string() }))?,
+                };
+                let alloc = guest_alloc(&mut caller)?;
+                write_guest(&mut caller, memory, &alloc, &response).await
+            })
+        })?;
+        linker.func_wrap("host", "log", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<()> {
+            let memory = guest_memory(&mut caller)?;
+            let message = read_guest(&caller, memory, ptr, len)?;
+            tracing::info!("[插件 {}] {}", caller.data().plugin, String::from_utf8_lossy(&message));
+            Ok(())
+        })?;
+
+        Ok(Self { engine, linker, plugins: HashMap::new() })
+    }
+
+    fn new_store(&self, plugin: &WasmPlugin) -> Result<Store<HostState>> {
+        let state = HostState {
+            plugin: plugin.info.source.clone(),
+            upstream: UpstreamKey::from_source(&plugin.info.source),
+            fetches: 0,
+            limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_LIMIT)?;
+        store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+        Ok(store)
+    }
+
+    /// 编译插件并读取平台信息
+    async fn load(&self, bytes: &[u8]) -> Result<WasmPlugin> {
+        let module = Module::new(&self.engine, bytes)?;
+        let mut store = self.new_store(&WasmPlugin {
+            info: PluginInfo { source: String::new(), name: None, domains: Vec::new() },
+            module: module.clone(),
+        })?;
+        let instance = self.linker.instantiate_async(&mut store, &module).await?;
+        let plugin_info = instance.get_typed_func::<(), i64>(&mut store, "plugin_info")?;
+        let packed = plugin_info.call_async(&mut store, ()).await?;
+        let memory = instance.get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("插件没有导出 memory"))?;
+        let (ptr, len) = unpack(packed);
+        let info: PluginInfo = serde_json::from_slice(&read_guest(&store, memory, ptr, len)?)?;
+
+        if info.source.trim().is_empty() {
+            return Err(anyhow!("插件未声明 source"));
+        }
+        if info.builtin_source().is_none() && info.domains.is_empty() {
+            return Err(anyhow!("新增平台 {} 需要声明 domains", info.source));
+        }
+        Ok(WasmPlugin { info, module })
+    }
+
+    /// 调用插件的 `parse` 或 `parse_id`
+    async fn call(&self, plugin: &WasmPlugin, export: &str, input: &str) -> Result<VideoParseInfo> {
+        let mut store = self.new_store(plugin)?;
+        let instance = self.linker.instantiate_async(&mut store, &plugin.module).await?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&mut store, export)
+            .map_err(|_| anyhow!("插件 {} 不支持 {}", plugin.info.source, export))?;
+        let memory = instance.get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("插件没有导出 memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+
+        let packed = write_guest(&mut store, memory, &alloc, input.as_bytes()).await?;
+        let (ptr, len) = unpack(packed);
+        let packed = tokio::time::timeout(PARSE_TIMEOUT, func.call_async(&mut store, (ptr, len)))
+            .await
+            .map_err(|_| anyhow!("插件 {} 执行超时", plugin.info.source))??;
+        let (ptr, len) = unpack(packed);
+        let output: Value = serde_json::from_slice(&read_guest(&store, memory, ptr, len)?)?;
+
+        if let Some(error) = output.pointer("/error").and_then(|v| v.as_str()) {
+            return Err(anyhow!("{}", error));
+        }
+        let mut info: VideoParseInfo = serde_json::from_value(output)
+            .map_err(|e| anyhow!("插件返回的解析结果无效: {}", e))?;
+        if plugin.info.builtin_source().is_none() {
+            if info.source.is_empty() {
+                info.source = plugin.info.source.clone();
+            }
+            info.infer_content_type();
+        }
+        Ok(info)
+    }
+}
+
+async fn host_fetch(state: &mut HostState, request: &[u8]) -> Result<FetchResponse> {
+    state.fetches += 1;
+    if state.fetches > MAX_FETCHES {
+        return Err(anyhow!("请求次数超过上限 {}", MAX_FETCHES));
+    }
+    let request: FetchRequest = serde_json::from_slice(request)?;
+    let client = create_client(state.upstream.builtin(), request.follow_redirects)?;
+    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+    let mut builder = client.request(method.parse()?, &request.url);
+    for (key, value) in &request.headers {
+        builder = builder.header(key.as_str(), value.as_str());
+    }
+    if let Some(body) = request.body {
+        builder = builder.body(body);
+    }
+
+    let response = builder.send_to(state.upstream.clone()).await?;
+    if response.content_length().is_some_and(|len| len > MAX_BODY_BYTES as u64) {
+        return Err(anyhow!("响应超过 {} bytes", MAX_BODY_BYTES));
+    }
+    let status = response.status().as_u16();
+    let url = response.url().to_string();
+    let headers = response.headers()
+        .iter()
+        .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string())))
+        .collect();
+    // 边读边检查，超过上限立即停止，不把整个响应读进内存
+    let mut bytes = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(anyhow!("响应超过 {} bytes", MAX_BODY_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(FetchResponse { status, url, headers, body: String::from_utf8_lossy(&bytes).into_owned() })
+}
+
+fn unpack(packed: i64) -> (i32, i32) {
+    ((packed as u64 >> 32) as i32, packed as u32 as i32)
+}
+
+fn guest_memory(caller: &mut Caller<'_, HostState>) -> Result<Memory> {
+    caller.get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow!("插件没有导出 memory"))
+}
+
+fn guest_alloc(caller: &mut Caller<'_, HostState>) -> Result<TypedFunc<i32, i32>> {
+    caller.get_export("alloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| anyhow!("插件没有导出 alloc"))?
+        .typed(&caller)
+}
+
+fn read_guest(store: impl wasmtime::AsContext, memory: Memory, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let start = ptr as u32 as usize;
+    let end = start + len as u32 as usize;
+    memory.data(&store)
+        .get(start..end)
+        .map(|bytes| bytes.to_vec())
+        .ok_or_else(|| anyhow!("插件内存访问越界"))
+}
+
+/// 通过插件的 `alloc` 分配内存并写入数据，返回打包的指针和长度
+async fn write_guest(
+    mut store: impl AsContextMut<Data = HostState>,
+    memory: Memory,
+    alloc: &TypedFunc<i32, i32>,
+    bytes: &[u8],
+) -> Result<i64> {
+    let len = i32::try_from(bytes.len())?;
+    let ptr = alloc.call_async(&mut store, len).await?;
+    memory.write(&mut store, ptr as u32 as usize, bytes)?;
+    Ok(((ptr as u32 as u64) << 32 | len as u32 as u64) as i64)
+}
+
+/// 插件运行环境，首次使用时加载插件目录
+fn host() -> &'static Option<PluginHost> {
+    static HOST: OnceLock<Option<PluginHost>> = OnceLock::new();
+    HOST.get_or_init(|| {
+        let dir = config().plugins_dir.clone().unwrap_or_else(|| "plugins".into());
+        let mut host = match PluginHost::new() {
+            Ok(host) => host,
+            Err(e) => {
+                tracing::error!("初始化插件运行环境失败: {}", e);
+                return None;
+            }
+        };
+        // 加载时只执行 plugin_info，不会调用宿主的异步函数，可以就地阻塞等待
+        for (name, plugin) in futures::executor::block_on(load_dir(&host, &dir)) {
+            let title = plugin.info.name.as_deref().unwrap_or(&plugin.info.source);
+            tracing::info!("加载解析插件 {}: {} ({})", name, title, plugin.info.source);
+            host.plugins.insert(plugin.info.source.to_lowercase(), Arc::new(plugin));
+        }
+        Some(host)
+    })
+}
+
+async fn load_dir(host: &PluginHost, dir: &Path) -> Vec<(String, WasmPlugin)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("wasm"))
+        .collect();
+    paths.sort();
+
+    let mut plugins = Vec::new();
+    for path in paths {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let result = match std::fs::read(&path) {
+            Ok(bytes) => host.load(&bytes).await,
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(plugin) => plugins.push((name, plugin)),
+            Err(e) => tracing::warn!("解析插件 {} 无效，已跳过: {}", path.display(), e),
+        }
+    }
+    plugins
+}
+
+/// 启动时加载插件，尽早在日志中暴露无效的插件
+pub fn init() {
+    if let Some(host) = host() {
+        tracing::debug!("已加载 {} 个解析插件", host.plugins.len());
+    }
+}
+
+/// 内置平台的插件
+pub fn find(source: VideoSource) -> Option<Arc<WasmPlugin>> {
+    host().as_ref()?.plugins.get(source.as_str()).cloned()
+}
+
+/// 插件新增的平台中与链接域名匹配的
+pub fn find_by_url(url: &str) -> Option<Arc<WasmPlugin>> {
+    host().as_ref()?
+        .plugins
+        .values()
+        .find(|p| p.info.builtin_source().is_none() && p.info.domains.iter().any(|d| url.contains(d.as_str())))
+        .cloned()
+}
+
+/// 由插件实现的解析器
+pub struct PluginParser {
+    plugin: Arc<WasmPlugin>,
+}
+
+impl PluginParser {
+    pub fn new(plugin: Arc<WasmPlugin>) -> Self {
+        Self { plugin }
+    }
+
+    async fn call(&self, export: &str, input: &str) -> Result<VideoParseInfo> {
+        let host = host().as_ref().ok_or_else(|| anyhow!("插件运行环境不可用"))?;
+        host.call(&self.plugin, export, input).await
+    }
+}
+
+#[async_trait]
+impl VideoParser for PluginParser {
+    async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
+        self.call("parse", share_url).await
+    }
+
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        self.call("parse_id", video_id).await
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用 WAT 写的测试插件：`parse` 返回固定结果，`parse_id` 把宿主的 HTTP 响应原样返回
+    fn test_plugin() -> String {
+        let info = r#"{"source":"demo","domains":["demo.example.com"]}"#;
+        let result = r#"{"author":{"uid":"1","name":"作者","avatar":""},"title":"标题","video_url":"https://cdn.example.com/1.mp4","content_id":"1"}"#;
+        let request = r#"{"url":"http://127.0.0.1:1/"}"#;
+        let escape = |s: &str| s.replace('"', "\\\"");
+        format!(
+            r#"(module
+                (import "host" "http_fetch" (func $fetch (param i32 i32) (result i64)))
+                (memory (export "memory") 2)
+                (global $heap (mut i32) (i32.const 8192))
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $heap))
+                    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                    (local.get $ptr))
+                (data (i32.const 0) "{info}")
+                (data (i32.const 1024) "{result}")
+                (data (i32.const 4096) "{request}")
+                (func (export "plugin_info") (result i64) (i64.const {info_len}))
+                (func (export "parse") (param i32 i32) (result i64)
+                    (i64.or (i64.const {result_ptr}) (i64.const {result_len})))
+                (func (export "parse_id") (param i32 i32) (result i64)
+                    (call $fetch (i32.const 4096) (i32.const {request_len}))))"#,
+            info = escape(info),
+            result = escape(result),
+            request = escape(request),
+            info_len = info.len(),
+            result_ptr = 1024u64 << 32,
+            result_len = result.len(),
+            request_len = request.len(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_plugin_abi() {
+        let host = PluginHost::new().unwrap();
+        let plugin = host.load(test_plugin().as_bytes()).await.unwrap();
+        assert_eq!(plugin.info.source, "demo");
+        assert!(plugin.info.builtin_source().is_none());
+
+        let info = host.call(&plugin, "parse", "https://demo.example.com/v/1").await.unwrap();
+        assert_eq!(info.source, "demo");
+        assert_eq!(info.title, "标题");
+        assert_eq!(info.author.name, "作者");
+        assert_eq!(info.video_url.as_deref(), Some("https://cdn.example.com/1.mp4"));
+
+        // 请求经由宿主发出，连接失败时插件收到 {"error": ...}
+        let err = host.call(&plugin, "parse_id", "1").await.unwrap_err();
+        assert!(!err.to_string().is_empty());
+
+        assert!(host.load(b"(module)").await.is_err());
+    }
+}
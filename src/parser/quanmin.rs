@@ -1,6 +1,7 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::models::{Author, ContentMetadata, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -26,15 +27,35 @@ impl VideoParser for QuanminParser {
             video_id
         );
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::QuanMin)?;
         let response = client
             .get(&req_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::QuanMin)
             .await?;
         
         let json: Value = response.json().await?;
         
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::QuanMin, video_id);
+        
+        Ok(info)
+    }
+}
+
+/// 内容为 immerse 接口的响应，其中没有视频ID，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for QuanminParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::QuanMin, video_id.unwrap_or_default());
+        Ok(info)
+    }
+}
+
+impl QuanminParser {
+    fn extract_video_info(json: &Value) -> Result<VideoParseInfo> {
         let errno = json.pointer("/errno")
             .and_then(|v| v.as_i64())
             .unwrap_or(-1);
@@ -86,6 +107,14 @@ impl VideoParser for QuanminParser {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
         
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(data.pointer("/meta/video_info/duration"), false),
+            like_count: json_count(data.pointer("/meta/likeNum")),
+            comment_count: json_count(data.pointer("/meta/commentNum")),
+            play_count: json_count(data.pointer("/meta/playcnt")),
+            ..Default::default()
+        });
+        
         Ok(info)
     }
 }
@@ -1,6 +1,8 @@
-use crate::models::{Author, ImgInfo, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::{create_http_client, extract_json_from_html};
+use crate::js;
+use crate::models::{Author, ContentMetadata, ImgInfo, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -10,58 +12,43 @@ pub struct RedbookParser;
 #[async_trait]
 impl VideoParser for RedbookParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::RedBook)?;
 
         let html = client
             .get(share_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36 Edg/129.0.0.0")
-            .send()
+            .send_via(VideoSource::RedBook)
             .await?
             .text()
             .await?;
         
-        // 保存HTML用于调试
-        if let Err(e) = std::fs::write("debug_redbook.html", &html) {
-            tracing::warn!("无法保存调试HTML: {}", e);
+        self.extract_page(&html).await
+    }
+    
+    /// 笔记ID可带上 `?xsec_token=...`，部分笔记缺少令牌时无法访问
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        let video_id = video_id.trim();
+        if video_id.is_empty() {
+            return Err(anyhow!("笔记ID不能为空"));
         }
-        
-        // 提取JSON数据
-        let pattern = r"window\.__INITIAL_STATE__\s*=\s*(.*?)</script>";
-        let json_str = extract_json_from_html(&html, pattern)?;
-        
-        tracing::debug!("提取的JSON长度: {} 字符", json_str.len());
-
-        let mut json_str = json_str.trim().trim_start_matches('\u{feff}').to_string();
-
-        use regex::Regex;
-        let undefined_re = Regex::new(r":undefined\b|,undefined\b|\[undefined\b").unwrap();
-        json_str = undefined_re.replace_all(&json_str, |caps: &regex::Captures| {
-            let matched = caps.get(0).unwrap().as_str();
-            if matched.starts_with(':') {
-                ":null"
-            } else if matched.starts_with(',') {
-                ",null"
-            } else {
-                "[null"
-            }
-        }).to_string();
-        
-        tracing::debug!("已将undefined替换为null");
+        self.parse_share_url(&VideoSource::RedBook.canonical_url(video_id)).await
+    }
+}
 
-        if let Err(e) = std::fs::write("debug_redbook_cleaned.json", &json_str) {
-            tracing::warn!("无法保存调试JSON: {}", e);
-        }
+/// 内容为笔记页 HTML
+#[async_trait]
+impl OfflineParser for RedbookParser {
+    async fn parse_content(&self, content: &str, _video_id: Option<&str>) -> Result<VideoParseInfo> {
+        self.extract_page(content).await
+    }
+}
 
-        let json: Value = serde_json::from_str(&json_str).map_err(|e| {
-            tracing::error!("JSON解析失败: {}", e);
-            tracing::error!("JSON前100字符: {}", &json_str.chars().take(100).collect::<String>());
-            if json_str.len() > 100 {
-                let start = e.column().saturating_sub(50);
-                let end = (e.column() + 50).min(json_str.len());
-                tracing::error!("错误位置附近: {}", &json_str[start..end]);
-            }
-            anyhow!("无法解析JSON数据: {}，已保存调试文件到 debug_redbook.html 和 debug_redbook.json", e)
-        })?;
+impl RedbookParser {
+    /// 从笔记页的 `__INITIAL_STATE__` 中提取当前笔记
+    async fn extract_page(&self, html: &str) -> Result<VideoParseInfo> {
+        // 其中的 undefined 会转为 null
+        let json = js::eval_page_global(html, "window.__INITIAL_STATE__").await
+            .map_err(|e| anyhow!("无法解析页面数据: {}", e))?;
         
         tracing::info!("✅ JSON解析成功");
 
@@ -75,11 +62,11 @@ impl VideoParser for RedbookParser {
         let note = json.pointer(&note_path)
             .ok_or_else(|| anyhow!("无法获取note数据，路径: {}", note_path))?;
         
-        self.extract_video_info(note)
+        let mut info = self.extract_video_info(note)?;
+        info.set_content_id(VideoSource::RedBook, note_id);
+        Ok(info)
     }
-}
-
-impl RedbookParser {
+    
     /// 从JSON数据中提取视频信息
     fn extract_video_info(&self, note: &Value) -> Result<VideoParseInfo> {
         let mut info = VideoParseInfo::new();
@@ -149,6 +136,31 @@ impl RedbookParser {
             }
         }
         
+        let hashtags = note.pointer("/tagList")
+            .and_then(|v| v.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.pointer("/name").and_then(|v| v.as_str()))
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(note.pointer("/video/capa/duration"), false),
+            width: json_u32(note.pointer("/video/media/video/width"))
+                .or_else(|| json_u32(note.pointer("/imageList/0/width"))),
+            height: json_u32(note.pointer("/video/media/video/height"))
+                .or_else(|| json_u32(note.pointer("/imageList/0/height"))),
+            publish_time: json_timestamp(note.pointer("/time")),
+            like_count: json_count(note.pointer("/interactInfo/likedCount")),
+            comment_count: json_count(note.pointer("/interactInfo/commentCount")),
+            share_count: json_count(note.pointer("/interactInfo/shareCount")),
+            collect_count: json_count(note.pointer("/interactInfo/collectedCount")),
+            hashtags,
+            ..Default::default()
+        });
+        
         Ok(info)
     }
     
@@ -0,0 +1,512 @@
+//! 声明式解析规则
+//!
+//! 结构简单的平台只需要：从分享链接提取ID、按模板请求接口、检查错误码、
+//! 再把 JSON 指针映射到解析结果。这类平台用规则文件（TOML 或 JSON）描述，
+//! 内置规则编译进程序，启动时再从规则目录加载同名覆盖或新增平台，
+//! 修正字段路径无需重新编译。
+
+use crate::config::config;
+use crate::js;
+use crate::models::{Author, ContentMetadata, MusicInfo, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::{UpstreamKey, UpstreamRequest};
+use crate::utils::{create_client, json_count, json_duration, json_str, json_timestamp, json_u32};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+/// 内置规则，文件名即平台标识
+const BUILTIN_RULES: [(&str, &str); 6] = [
+    ("haokan.toml", include_str!("rules/haokan.toml")),
+    ("doupai.toml", include_str!("rules/doupai.toml")),
+    ("huoshan.toml", include_str!("rules/huoshan.toml")),
+    ("sixroom.toml", include_str!("rules/sixroom.toml")),
+    ("quanminkge.toml", include_str!("rules/quanminkge.toml")),
+    ("weishi.toml", include_str!("rules/weishi.toml")),
+];
+
+/// 一个平台的解析规则
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParserRule {
+    /// 平台标识；与内置平台同名时替换其解析器，否则为新增平台
+    pub source: String,
+    /// 平台名称，用于错误信息
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 分享链接域名，新增平台必填
+    #[serde(default)]
+    pub domains: Vec<String>,
+    /// 先请求分享链接，从重定向地址中提取ID（短链）
+    #[serde(default)]
+    pub redirect: bool,
+    /// ID提取方式，按顺序尝试
+    pub id: Vec<IdRule>,
+    pub request: RequestRule,
+    /// 响应检查，任一不满足即返回错误
+    #[serde(default)]
+    pub checks: Vec<CheckRule>,
+    /// 数据根节点，字段指针相对于它
+    #[serde(default)]
+    pub data: String,
+    /// 新增平台的规范链接模板，如 `https://example.com/v/{id}`
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    pub fields: FieldRules,
+}
+
+/// 从链接中提取ID：取查询参数，或正则的第一个捕获组
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdRule {
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+}
+
+/// 接口请求模板，`{id}` 会被替换为视频ID
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestRule {
+    pub url: String,
+    /// GET（默认）或 POST
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// POST 请求体，`Content-Type` 写在 headers 中
+    #[serde(default)]
+    pub body: Option<String>,
+    /// 响应为 HTML 时，用正则的第一个捕获组提取 JSON
+    #[serde(default)]
+    pub extract: Option<String>,
+    /// 执行响应中的脚本并读取该全局变量，如 `window.__DATA__`；响应为 HTML 时只执行其中的内联脚本
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+/// 响应检查
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckRule {
+    pub pointer: String,
+    /// 字段必须等于该值
+    #[serde(default)]
+    pub equals: Option<Value>,
+    /// 字段必须为空（不存在、null 或空字符串），否则以字段值作为错误信息
+    #[serde(default)]
+    pub empty: bool,
+    /// 检查失败时错误信息所在的指针
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// 一个或多个候选指针，取第一个有值的
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Pointers {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Pointers {
+    fn lookup<'a>(&self, data: &'a Value) -> Option<&'a Value> {
+        let find = |p: &str| data.pointer(p).filter(|v| !v.is_null() && v.as_str() != Some(""));
+        match self {
+            Self::One(p) => find(p),
+            Self::Many(list) => list.iter().find_map(|p| find(p)),
+        }
+    }
+}
+
+/// 字段映射，均为相对数据根节点的 JSON 指针
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FieldRules {
+    pub title: Option<Pointers>,
+    pub video_url: Option<Pointers>,
+    pub cover_url: Option<Pointers>,
+    pub music_url: Option<Pointers>,
+    pub music_title: Option<Pointers>,
+    pub music_author: Option<Pointers>,
+    pub author_uid: Option<Pointers>,
+    pub author_name: Option<Pointers>,
+    pub author_avatar: Option<Pointers>,
+    pub duration: Option<Pointers>,
+    /// 时长单位为毫秒
+    #[serde(default)]
+    pub duration_millis: bool,
+    pub width: Option<Pointers>,
+    pub height: Option<Pointers>,
+    pub publish_time: Option<Pointers>,
+    pub like_count: Option<Pointers>,
+    pub comment_count: Option<Pointers>,
+    pub share_count: Option<Pointers>,
+    pub play_count: Option<Pointers>,
+    pub collect_count: Option<Pointers>,
+}
+
+impl ParserRule {
+    /// 按文件扩展名解析 TOML 或 JSON
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let rule: Self = if name.ends_with(".json") {
+            serde_json::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.source.trim().is_empty() {
+            return Err(anyhow!("source 不能为空"));
+        }
+        if self.builtin_source().is_none() && self.domains.is_empty() {
+            return Err(anyhow!("新增平台 {} 需要配置 domains", self.source));
+        }
+        if self.id.is_empty() {
+            return Err(anyhow!("至少需要一种ID提取方式"));
+        }
+        let regexes = self.id.iter().filter_map(|i| i.regex.as_deref())
+            .chain(self.request.extract.as_deref());
+        for re in regexes {
+            Regex::new(re).map_err(|e| anyhow!("正则 {} 无效: {}", re, e))?;
+        }
+        Ok(())
+    }
+
+    /// 对应的内置平台，新增平台返回 None
+    pub fn builtin_source(&self) -> Option<VideoSource> {
+        VideoSource::from_str(&self.source)
+    }
+
+    fn display_name(&self) -> &str {
+        self.name.as_deref()
+            .or_else(|| self.builtin_source().map(|s| s.display_name()))
+            .unwrap_or(&self.source)
+    }
+
+    pub fn matches_url(&self, url: &str) -> bool {
+        self.domains.iter().any(|d| url.contains(d.as_str()))
+    }
+
+    fn extract_id(&self, url: &str) -> Option<String> {
+        let parsed = url::Url::parse(url).ok();
+        self.id.iter().find_map(|rule| {
+            if let Some(key) = &rule.query {
+                let value = parsed.as_ref()?
+                    .query_pairs()
+                    .find(|(k, _)| k == key.as_str())
+                    .map(|(_, v)| v.to_string());
+                if value.is_some() {
+                    return value.filter(|v| !v.is_empty());
+                }
+            }
+            let re = Regex::new(rule.regex.as_deref()?).ok()?;
+            re.captures(url)?.get(1).map(|m| m.as_str().to_string()).filter(|v| !v.is_empty())
+        })
+    }
+
+    /// 检查响应并把字段映射为解析结果
+    fn build_info(&self, json: &Value, video_id: &str) -> Result<VideoParseInfo> {
+        for check in &self.checks {
+            let value = json.pointer(&check.pointer);
+            let failed = match &check.equals {
+                Some(expected) => value != Some(expected),
+                None => check.empty && value.is_some_and(|v| !v.is_null() && v.as_str() != Some("")),
+            };
+            if failed {
+                let message = check.message.as_deref()
+                    .and_then(|p| json_str(json.pointer(p)))
+                    .or_else(|| check.empty.then(|| json_str(value)).flatten())
+                    .unwrap_or_else(|| "接口返回错误".to_string());
+                return Err(anyhow!("{}API错误: {}", self.display_name(), message));
+            }
+        }
+
+        let data = json.pointer(&self.data)
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| anyhow!("无法获取视频数据"))?;
+        let fields = &self.fields;
+        let get = |pointers: &Option<Pointers>| pointers.as_ref().and_then(|p| p.lookup(data));
+        let text = |pointers: &Option<Pointers>| json_str(get(pointers));
+
+        let mut info = VideoParseInfo::new();
+        info.title = text(&fields.title).unwrap_or_default();
+        info.video_url = text(&fields.video_url);
+        info.cover_url = text(&fields.cover_url);
+        info.music_url = text(&fields.music_url);
+        info.music = MusicInfo::from_parts(
+            text(&fields.music_title).as_deref(),
+            text(&fields.music_author).as_deref(),
+        );
+        info.author = Author {
+            uid: text(&fields.author_uid).unwrap_or_default(),
+            name: text(&fields.author_name).unwrap_or_default(),
+            avatar: text(&fields.author_avatar).unwrap_or_default(),
+        };
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(get(&fields.duration), fields.duration_millis),
+            width: json_u32(get(&fields.width)),
+            height: json_u32(get(&fields.height)),
+            publish_time: json_timestamp(get(&fields.publish_time)),
+            like_count: json_count(get(&fields.like_count)),
+            comment_count: json_count(get(&fields.comment_count)),
+            share_count: json_count(get(&fields.share_count)),
+            play_count: json_count(get(&fields.play_count)),
+            collect_count: json_count(get(&fields.collect_count)),
+            hashtags: Vec::new(),
+        });
+
+        match self.builtin_source() {
+            Some(source) => info.set_content_id(source, video_id),
+            None => {
+                info.source = self.source.clone();
+                if !video_id.is_empty() {
+                    info.canonical_url = self.canonical_url.as_ref().map(|t| t.replace("{id}", video_id));
+                    info.content_id = Some(video_id.to_string());
+                }
+                info.infer_content_type();
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// 按规则解析的平台
+pub struct RuleParser {
+    rule: Arc<ParserRule>,
+}
+
+impl RuleParser {
+    pub fn new(rule: Arc<ParserRule>) -> Self {
+        Self { rule }
+    }
+
+    async fn resolve_redirect(&self, share_url: &str) -> Result<String> {
+        let response = create_client(self.rule.builtin_source(), false)?
+            .get(share_url)
+            .send_to(UpstreamKey::from_source(&self.rule.source))
+            .await?;
+        response.headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("无法获取重定向地址"))
+    }
+
+    async fn fetch(&self, video_id: &str) -> Result<Value> {
+        let request = &self.rule.request;
+        let fill = |template: &str| template.replace("{id}", video_id);
+        let client = create_client(self.rule.builtin_source(), true)?;
+
+        let mut builder = match request.method.as_deref().map(str::to_uppercase).as_deref() {
+            Some("POST") => client.post(fill(&request.url)),
+            None | Some("GET") => client.get(fill(&request.url)),
+            Some(method) => return Err(anyhow!("不支持的请求方法: {}", method)),
+        };
+        for (key, value) in &request.headers {
+            builder = builder.header(key.as_str(), fill(value));
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(fill(body));
+        }
+
+        let text = builder.send_to(UpstreamKey::from_source(&self.rule.source)).await?.text().await?;
+        self.decode(&text).await
+    }
+
+    /// 把响应文本转换为 JSON：执行脚本、正则提取或直接解析
+    async fn decode(&self, text: &str) -> Result<Value> {
+        let request = &self.rule.request;
+        if let Some(global) = &request.script {
+            return if text.trim_start().starts_with('<') {
+                js::eval_page_global(text, global).await
+            } else {
+                js::eval_global(text, global).await
+            };
+        }
+        let json_text = match &request.extract {
+            Some(pattern) => Regex::new(pattern)?
+                .captures(text)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().trim())
+                .ok_or_else(|| anyhow!("无法从HTML中提取数据"))?,
+            None => text,
+        };
+        Ok(serde_json::from_str(json_text)?)
+    }
+}
+
+/// 内容为规则中请求的响应，未传入ID时内容ID和规范链接为空
+#[async_trait]
+impl OfflineParser for RuleParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json = self.decode(content).await?;
+        self.rule.build_info(&json, video_id.unwrap_or(""))
+    }
+}
+
+#[async_trait]
+impl VideoParser for RuleParser {
+    async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
+        let target = if self.rule.redirect {
+            self.resolve_redirect(share_url).await?
+        } else {
+            share_url.to_string()
+        };
+        let video_id = self.rule.extract_id(&target)
+            .ok_or_else(|| anyhow!("无法从分享链接中解析视频ID"))?;
+        self.parse_video_id(&video_id).await
+    }
+
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        let json = self.fetch(video_id).await?;
+        self.rule.build_info(&json, video_id)
+    }
+}
+
+/// 已加载的规则，按平台标识索引
+fn rules() -> &'static HashMap<String, Arc<ParserRule>> {
+    static RULES: OnceLock<HashMap<String, Arc<ParserRule>>> = OnceLock::new();
+    RULES.get_or_init(load_rules)
+}
+
+/// 启动时加载规则，尽早在日志中暴露无效的规则文件
+pub fn init() {
+    tracing::debug!("已加载 {} 条解析规则", rules().len());
+}
+
+fn load_rules() -> HashMap<String, Arc<ParserRule>> {
+    let mut rules = HashMap::new();
+    for (name, content) in BUILTIN_RULES {
+        match ParserRule::parse(name, content) {
+            Ok(rule) => {
+                rules.insert(rule.source.to_lowercase(), Arc::new(rule));
+            }
+            Err(e) => tracing::error!("内置规则 {} 无效: {}", name, e),
+        }
+    }
+
+    let dir = config().rules_dir.clone().unwrap_or_else(|| "rules".into());
+    for (name, rule) in load_dir(&dir) {
+        tracing::info!("加载解析规则 {} ({})", name, rule.source);
+        rules.insert(rule.source.to_lowercase(), Arc::new(rule));
+    }
+    rules
+}
+
+/// 读取目录中的 `.toml`、`.json` 规则，无效文件记录警告后跳过
+fn load_dir(dir: &Path) -> Vec<(String, ParserRule)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("toml" | "json")))
+        .collect();
+    paths.sort();
+
+    paths.into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let result = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| ParserRule::parse(&name, &content));
+            match result {
+                Ok(rule) => Some((name, rule)),
+                Err(e) => {
+                    tracing::warn!("解析规则 {} 无效，已跳过: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// 内置平台的规则
+pub fn find(source: VideoSource) -> Option<Arc<ParserRule>> {
+    rules().get(source.as_str()).cloned()
+}
+
+/// 按平台标识查找规则，包括规则文件新增的平台
+pub fn find_by_name(name: &str) -> Option<Arc<ParserRule>> {
+    rules().get(&name.to_lowercase()).cloned()
+}
+
+/// 规则文件新增的平台中与链接域名匹配的
+pub fn find_by_url(url: &str) -> Option<Arc<ParserRule>> {
+    rules().values()
+        .find(|rule| rule.builtin_source().is_none() && rule.matches_url(url))
+        .cloned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_rules() {
+        for (name, content) in BUILTIN_RULES {
+            let rule = ParserRule::parse(name, content).unwrap();
+            assert_eq!(format!("{}.toml", rule.source), name);
+            assert!(rule.builtin_source().is_some());
+        }
+
+        let rule = ParserRule::parse("sixroom.toml", BUILTIN_RULES[3].1).unwrap();
+        assert_eq!(rule.extract_id("https://m.6.cn/v/abc123").as_deref(), Some("abc123"));
+        assert_eq!(rule.extract_id("https://m.6.cn/watchMini.php?vid=xyz").as_deref(), Some("xyz"));
+    }
+
+    #[test]
+    fn test_build_info() {
+        let rule = ParserRule::parse("demo.json", r#"{
+            "source": "demo",
+            "name": "示例",
+            "domains": ["demo.example.com"],
+            "canonical_url": "https://demo.example.com/v/{id}",
+            "id": [{"query": "vid"}],
+            "request": {"url": "https://demo.example.com/api?vid={id}"},
+            "checks": [{"pointer": "/code", "equals": 0, "message": "/msg"}],
+            "data": "/data",
+            "fields": {
+                "title": ["/desc", "/title"],
+                "video_url": "/play/url",
+                "author_name": "/user/name",
+                "duration": "/duration_ms",
+                "duration_millis": true,
+                "like_count": "/stats/likes"
+            }
+        }"#).unwrap();
+        assert!(rule.matches_url("https://demo.example.com/share?vid=42"));
+        assert_eq!(rule.extract_id("https://demo.example.com/share?vid=42").as_deref(), Some("42"));
+
+        let json = serde_json::json!({
+            "code": 0,
+            "data": {
+                "desc": "",
+                "title": "标题",
+                "play": {"url": "https://cdn.example.com/42.mp4"},
+                "user": {"name": "作者"},
+                "duration_ms": 15000,
+                "stats": {"likes": "12"}
+            }
+        });
+        let info = rule.build_info(&json, "42").unwrap();
+        assert_eq!(info.source, "demo");
+        assert_eq!(info.title, "标题");
+        assert_eq!(info.author.name, "作者");
+        assert_eq!(info.canonical_url.as_deref(), Some("https://demo.example.com/v/42"));
+        let metadata = info.metadata.unwrap();
+        assert_eq!(metadata.duration, Some(15.0));
+        assert_eq!(metadata.like_count, Some(12));
+
+        let err = rule.build_info(&serde_json::json!({"code": 1, "msg": "视频不存在"}), "42").unwrap_err();
+        assert_eq!(err.to_string(), "示例API错误: 视频不存在");
+
+        assert!(ParserRule::parse("bad.toml", "source = \"unknown\"\nid = [{ query = \"id\" }]\n[request]\nurl = \"x\"\n[fields]\n").is_err());
+    }
+}
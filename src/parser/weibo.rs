@@ -1,13 +1,50 @@
-use crate::models::{ImgInfo, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::credentials::{credentials, parse_set_cookie};
+use crate::js;
+use crate::models::{Author, Comment, CommentPage, ContentMetadata, ImgInfo, VideoParseInfo, VideoSource};
+use crate::parser::{CommentParser, OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, extract_hashtags, json_count, json_duration, json_str, json_timestamp};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use regex::Regex;
 use serde_json::Value;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
 
 pub struct WeiboParser;
 
+/// 访客Cookie的最长缓存时间（秒），服务端给出的过期时间更早时以服务端为准
+const VISITOR_COOKIE_MAX_AGE: i64 = 12 * 3600;
+
+/// 通过访客通行证流程获取的 SUB/SUBP Cookie
+#[derive(Debug, Clone)]
+struct VisitorCookies {
+    sub: String,
+    subp: String,
+    expires_at: i64,
+}
+
+impl VisitorCookies {
+    fn header(&self) -> String {
+        format!("SUB={}; SUBP={}", self.sub, self.subp)
+    }
+}
+
+fn visitor_cache() -> &'static Mutex<Option<VisitorCookies>> {
+    static CACHE: OnceLock<Mutex<Option<VisitorCookies>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// 提取 JSONP 响应 `callback({...});` 中的 JSON
+fn parse_jsonp(text: &str) -> Result<Value> {
+    let start = text.find('(').ok_or_else(|| anyhow!("无效的JSONP响应"))?;
+    let end = text.rfind(')').ok_or_else(|| anyhow!("无效的JSONP响应"))?;
+    if end <= start {
+        return Err(anyhow!("无效的JSONP响应"));
+    }
+    Ok(serde_json::from_str(&text[start + 1..end])?)
+}
+
 #[async_trait]
 impl VideoParser for WeiboParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
@@ -39,27 +76,314 @@ impl VideoParser for WeiboParser {
     async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
         let req_url = format!("https://h5.video.weibo.com/api/component?page=/show/{}", video_id);
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::WeiBo)?;
         let body = format!(
             r#"{{"Component_Play_Playinfo":{{"oid":"{}"}}}}"#,
             video_id
         );
         
-        let response = client
+        let mut request = client
             .post(&req_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
             .header("Referer", format!("https://h5.video.weibo.com/show/{}", video_id))
             .header("Content-Type", "application/x-www-form-urlencoded")
-            .header("Cookie", "login_sid_t=6b652c77c1a4bc50cb9d06b24923210d")
-            .body(format!("data={}", body))
-            .send()
+            .body(format!("data={}", body));
+
+        if let Some(cookie) = self.cookie_header().await? {
+            request = request.header("Cookie", cookie);
+        }
+
+        let response = request.send_via(VideoSource::WeiBo).await?;
+        
+        let json = response.json::<Value>().await;
+        if json.is_err() {
+            Self::invalidate_visitor_cookies().await;
+        }
+        let json = json?;
+        
+        let Some(data) = json.pointer("/data/Component_Play_Playinfo") else {
+            // 访客Cookie可能已失效，下次请求重新获取
+            Self::invalidate_visitor_cookies().await;
+            return Err(anyhow!("无法获取视频数据"));
+        };
+        
+        let mut info = Self::extract_play_info(data);
+        info.set_content_id(VideoSource::WeiBo, video_id);
+        
+        Ok(info)
+    }
+}
+
+/// 内容可以是帖子页 HTML、`/statuses/show` 接口响应或视频组件接口响应，
+/// 视频组件接口响应中没有视频ID，未传入时内容ID为空
+#[async_trait]
+impl OfflineParser for WeiboParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        if content.trim_start().starts_with('<') {
+            return self.parse_html_page(content).await;
+        }
+        let json: Value = serde_json::from_str(content)?;
+        if let Some(data) = json.pointer("/data/Component_Play_Playinfo") {
+            let mut info = Self::extract_play_info(data);
+            info.set_content_id(VideoSource::WeiBo, video_id.unwrap_or_default());
+            return Ok(info);
+        }
+        let status = json.pointer("/data")
+            .ok_or_else(|| anyhow!("无法获取微博数据"))?;
+        Ok(self.extract_status(status))
+    }
+}
+
+/// 游标为 `<max_id>_<max_id_type>`，内容ID为微博正文的 mid
+#[async_trait]
+impl CommentParser for WeiboParser {
+    async fn fetch_comments(&self, content_id: &str, cursor: Option<&str>) -> Result<CommentPage> {
+        if content_id.contains(':') {
+            return Err(anyhow!("微博视频ID不支持获取评论，请使用微博正文链接"));
+        }
+        let mut req_url = format!("https://m.weibo.cn/comments/hotflow?id={0}&mid={0}", content_id);
+        match cursor.and_then(|c| c.split_once('_')) {
+            Some((max_id, max_id_type)) => {
+                req_url.push_str(&format!("&max_id={}&max_id_type={}", max_id, max_id_type));
+            }
+            None => req_url.push_str("&max_id_type=0"),
+        }
+        
+        let client = create_http_client(VideoSource::WeiBo)?;
+        let mut request = client
+            .get(&req_url)
+            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 14_0 like Mac OS X) AppleWebKit/605.1.15")
+            .header("Referer", format!("https://m.weibo.cn/detail/{}", content_id))
+            .header("X-Requested-With", "XMLHttpRequest");
+        if let Some(cookie) = self.cookie_header().await? {
+            request = request.header("Cookie", cookie);
+        }
+        
+        let json: Value = request.send_via(VideoSource::WeiBo).await?.json().await?;
+        
+        // 没有评论时 ok 为 0 且不带 data
+        if json.pointer("/ok").and_then(|v| v.as_i64()) != Some(1) {
+            if json.pointer("/data").is_none() && json.pointer("/msg").is_none() {
+                return Ok(CommentPage::default());
+            }
+            return Err(anyhow!(
+                "获取微博评论失败: {}",
+                json.pointer("/msg").and_then(|v| v.as_str()).unwrap_or("")
+            ));
+        }
+        
+        Ok(self.parse_comment_page(&json))
+    }
+}
+
+impl WeiboParser {
+    fn parse_comment_page(&self, json: &Value) -> CommentPage {
+        let items = json.pointer("/data/data")
+            .and_then(|v| v.as_array())
+            .map(|comments| {
+                comments.iter()
+                    .map(|c| Comment {
+                        id: json_str(c.pointer("/id")).unwrap_or_default(),
+                        author: Author {
+                            uid: json_str(c.pointer("/user/id")).unwrap_or_default(),
+                            name: json_str(c.pointer("/user/screen_name")).unwrap_or_default(),
+                            avatar: json_str(c.pointer("/user/profile_image_url")).unwrap_or_default(),
+                        },
+                        text: self.clean_text(c.pointer("/text").and_then(|v| v.as_str()).unwrap_or("")),
+                        like_count: json_count(c.pointer("/like_count")),
+                        reply_count: json_count(c.pointer("/total_number")),
+                        publish_time: created_at(c.pointer("/created_at")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // max_id 为 0 表示最后一页
+        let max_id = json_str(json.pointer("/data/max_id")).filter(|id| id != "0");
+        let max_id_type = json_str(json.pointer("/data/max_id_type")).unwrap_or_else(|| "0".to_string());
+        
+        CommentPage {
+            total: json_count(json.pointer("/data/total_number")),
+            items,
+            has_more: max_id.is_some(),
+            cursor: max_id.map(|id| format!("{}_{}", id, max_id_type)),
+        }
+    }
+    
+    /// 请求微博接口时附带的Cookie：配置了微博账号时由账号Cookie提供，否则使用访客Cookie
+    async fn cookie_header(&self) -> Result<Option<String>> {
+        if credentials().current(VideoSource::WeiBo).is_some() {
+            return Ok(None);
+        }
+
+        let mut cache = visitor_cache().lock().await;
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(cookies) = cache.as_ref().filter(|c| c.expires_at > now) {
+            return Ok(Some(cookies.header()));
+        }
+
+        let cookies = self.bootstrap_visitor_cookies().await?;
+        tracing::info!("🍪 已获取微博访客Cookie");
+        let header = cookies.header();
+        *cache = Some(cookies);
+        Ok(Some(header))
+    }
+
+    async fn invalidate_visitor_cookies() {
+        *visitor_cache().lock().await = None;
+    }
+
+    /// 微博访客通行证流程：genvisitor 获取 tid，再 incarnate 换取 SUB/SUBP
+    async fn bootstrap_visitor_cookies(&self) -> Result<VisitorCookies> {
+        let client = create_http_client(VideoSource::WeiBo)?;
+
+        let fp = r#"{"os":"1","browser":"Chrome120,0,0,0","fonts":"undefined","screenInfo":"1920*1080*24","plugins":""}"#;
+        let text = client
+            .post("https://passport.weibo.com/visitor/genvisitor")
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .header("Referer", "https://passport.weibo.com/visitor/visitor")
+            .form(&[("cb", "gen_callback"), ("fp", fp)])
+            .send_via(VideoSource::WeiBo)
+            .await?
+            .text()
+            .await?;
+
+        let json = parse_jsonp(&text)?;
+        let data = json.pointer("/data")
+            .filter(|_| json.pointer("/retcode").and_then(|v| v.as_i64()) == Some(20000000))
+            .ok_or_else(|| anyhow!("获取微博访客身份失败"))?;
+
+        let tid = data.pointer("/tid")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("获取微博访客身份失败: 缺少tid"))?;
+        let new_tid = data.pointer("/new_tid").and_then(|v| v.as_bool()).unwrap_or(false);
+        let confidence = data.pointer("/confidence").and_then(|v| v.as_i64()).unwrap_or(100);
+
+        let incarnate_url = format!(
+            "https://passport.weibo.com/visitor/visitor?a=incarnate&t={}&w={}&c={:03}&gc=&cb=cross_domain&from=weibo&_rand={}",
+            urlencode(tid),
+            if new_tid { 3 } else { 2 },
+            confidence,
+            rand::random::<f64>()
+        );
+
+        let response = client
+            .get(&incarnate_url)
+            .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
+            .header("Referer", "https://passport.weibo.com/visitor/visitor")
+            .send_via(VideoSource::WeiBo)
             .await?;
+
+        let now = chrono::Utc::now().timestamp();
+        let cookie_url = url::Url::parse("https://passport.weibo.com/")?;
+        let server_expires = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .filter_map(|h| parse_set_cookie(h, &cookie_url, now))
+            .filter(|c| c.name == "SUB")
+            .find_map(|c| c.expires);
+
+        let json = parse_jsonp(&response.text().await?)?;
+        let sub = json.pointer("/data/sub").and_then(|v| v.as_str()).unwrap_or("");
+        let subp = json.pointer("/data/subp").and_then(|v| v.as_str()).unwrap_or("");
+        if sub.is_empty() || subp.is_empty() {
+            return Err(anyhow!("获取微博访客Cookie失败"));
+        }
+
+        Ok(VisitorCookies {
+            sub: sub.to_string(),
+            subp: subp.to_string(),
+            expires_at: server_expires
+                .unwrap_or(i64::MAX)
+                .min(now + VISITOR_COOKIE_MAX_AGE),
+        })
+    }
+
+    /// 解析帖子URL（图集等）
+    async fn parse_post_url(&self, post_id: &str, original_url: &str) -> Result<VideoParseInfo> {
+        let req_url = format!("https://m.weibo.cn/statuses/show?id={}", post_id);
+        let client = create_http_client(VideoSource::WeiBo)?;
         
-        let json: Value = response.json().await?;
+        let mut request = client
+            .get(&req_url)
+            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 14_0 like Mac OS X) AppleWebKit/605.1.15")
+            .header("Referer", "https://m.weibo.cn/")
+            .header("Content-Type", "application/json;charset=UTF-8")
+            .header("X-Requested-With", "XMLHttpRequest");
+
+        if let Some(cookie) = self.cookie_header().await? {
+            request = request.header("Cookie", cookie);
+        }
+
+        let json: Value = request.send_via(VideoSource::WeiBo).await?.json().await?;
+        
+        // 接口没有返回帖子数据时（如需要登录）才退回到解析网页
+        if let Some(data) = json.pointer("/data").filter(|data| data.is_object()) {
+            let mut status = data.clone();
+            self.expand_long_text(&mut status).await;
+            if let Some(retweeted) = status.get_mut("retweeted_status") {
+                self.expand_long_text(retweeted).await;
+            }
+            return Ok(self.extract_status(&status));
+        }
+
+        let response = client
+            .get(original_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .send_via(VideoSource::WeiBo)
+            .await?;
         
-        let data = json.pointer("/data/Component_Play_Playinfo")
-            .ok_or_else(|| anyhow!("无法获取视频数据"))?;
+        let html = response.text().await?;
         
+        self.parse_html_page(&html).await
+    }
+
+    /// 长微博只返回截断的正文，通过 `/statuses/extend` 获取全文
+    async fn expand_long_text(&self, status: &mut Value) {
+        if !status.pointer("/isLongText").and_then(|v| v.as_bool()).unwrap_or(false) {
+            return;
+        }
+        let id = status.pointer("/id")
+            .and_then(|v| v.as_str().map(str::to_string).or_else(|| v.as_i64().map(|n| n.to_string())));
+        let Some(id) = id else {
+            return;
+        };
+
+        let Ok(client) = create_http_client(VideoSource::WeiBo) else {
+            return;
+        };
+        let mut request = client
+            .get(format!("https://m.weibo.cn/statuses/extend?id={}", id))
+            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 14_0 like Mac OS X) AppleWebKit/605.1.15")
+            .header("Referer", "https://m.weibo.cn/")
+            .header("X-Requested-With", "XMLHttpRequest");
+
+        if let Ok(Some(cookie)) = self.cookie_header().await {
+            request = request.header("Cookie", cookie);
+        }
+
+        let long_text = match request.send_via(VideoSource::WeiBo).await {
+            Ok(resp) => resp.json::<Value>().await.ok().and_then(|json| {
+                json.pointer("/data/longTextContent")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            }),
+            Err(e) => {
+                tracing::debug!("获取长微博全文失败: {}", e);
+                None
+            }
+        };
+
+        if let Some(text) = long_text.filter(|t| !t.is_empty()) {
+            status["text"] = Value::String(text);
+        }
+    }
+
+    /// 从视频组件接口的 `Component_Play_Playinfo` 中提取视频信息
+    fn extract_play_info(data: &Value) -> VideoParseInfo {
         // 获取视频URL（第一个最高码率）
         let mut video_url = None;
         if let Some(urls) = data.pointer("/urls").and_then(|v| v.as_object()) {
@@ -94,140 +418,220 @@ impl VideoParser for WeiboParser {
             .map(|s| format!("https:{}", s))
             .unwrap_or_default();
         
-        Ok(info)
-    }
-}
-
-impl WeiboParser {
-    /// 解析帖子URL（图集等）
-    async fn parse_post_url(&self, post_id: &str, original_url: &str) -> Result<VideoParseInfo> {
-        let req_url = format!("https://m.weibo.cn/statuses/show?id={}", post_id);
-        let client = create_http_client()?;
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(data.pointer("/duration_time"), false),
+            publish_time: json_timestamp(data.pointer("/real_date")),
+            like_count: json_count(data.pointer("/attitudes_count")),
+            comment_count: json_count(data.pointer("/comments_count")),
+            share_count: json_count(data.pointer("/reposts_count")),
+            play_count: json_count(data.pointer("/play_count")),
+            hashtags: extract_hashtags(&info.title),
+            ..Default::default()
+        });
         
-        let response = client
-            .get(&req_url)
-            .header("User-Agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 14_0 like Mac OS X) AppleWebKit/605.1.15")
-            .header("Referer", "https://m.weibo.cn/")
-            .header("Content-Type", "application/json;charset=UTF-8")
-            .header("X-Requested-With", "XMLHttpRequest")
-            .send()
-            .await;
-        
-        if let Ok(resp) = response {
-            if let Ok(json) = resp.json::<Value>().await {
-                if let Some(data) = json.pointer("/data") {
-                    if let Ok(result) = self.parse_mobile_api_data(data) {
-                        return Ok(result);
-                    }
-                }
-            }
-        }
+        info
+    }
 
-        let response = client
-            .get(original_url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .send()
-            .await?;
+    /// 从HTML页面中提取信息
+    async fn parse_html_page(&self, html: &str) -> Result<VideoParseInfo> {
+        // 页面脚本为 var $render_data = [{...}][0] || {};
+        let json = js::eval_page_global(html, "$render_data")
+            .await
+            .map_err(|e| anyhow!("无法从HTML中提取微博数据: {}", e))?;
         
-        let html = response.text().await?;
+        let status = json.pointer("/status")
+            .ok_or_else(|| anyhow!("无法从HTML中提取微博数据"))?;
         
-        self.parse_html_page(&html)
+        Ok(self.extract_status(status))
     }
 
-    fn parse_mobile_api_data(&self, data: &Value) -> Result<VideoParseInfo> {
+    /// 从微博正文数据中提取作者、正文和媒体，正文没有媒体时使用转发原文的媒体；
+    /// 纯文字微博返回不含媒体的结果
+    fn extract_status(&self, status: &Value) -> VideoParseInfo {
         let mut info = VideoParseInfo::new();
         
         info.title = self.clean_text(
-            data.pointer("/text")
+            status.pointer("/text")
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
         );
         
-        info.author.name = data.pointer("/user/screen_name")
+        info.author.uid = status.pointer("/user/id")
+            .and_then(|v| v.as_i64().map(|n| n.to_string()).or_else(|| v.as_str().map(str::to_string)))
+            .unwrap_or_default();
+        
+        info.author.name = status.pointer("/user/screen_name")
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
         
-        info.author.avatar = data.pointer("/user/avatar_large")
+        info.author.avatar = status.pointer("/user/avatar_large")
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
         
-        // 提取图片
-        if let Some(pics) = data.pointer("/pics").and_then(|v| v.as_array()) {
-            for pic in pics {
-                let image_url = pic.pointer("/large/url")
-                    .and_then(|v| v.as_str())
-                    .or_else(|| pic.pointer("/original/url").and_then(|v| v.as_str()))
-                    .or_else(|| pic.pointer("/bmiddle/url").and_then(|v| v.as_str()))
-                    .or_else(|| pic.pointer("/url").and_then(|v| v.as_str()));
+        self.extract_media(status, &mut info);
+        
+        if info.video_url.is_none() && info.images.is_empty() {
+            if let Some(retweeted) = status.pointer("/retweeted_status") {
+                self.extract_media(retweeted, &mut info);
                 
-                if let Some(url) = image_url {
-                    if !url.is_empty() {
-                        info.images.push(ImgInfo {
-                            url: url.to_string(),
-                            live_photo_url: None,
-                        });
-                    }
+                if info.title.is_empty() {
+                    info.title = self.clean_text(
+                        retweeted.pointer("/text")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                    );
                 }
             }
         }
         
-        Ok(info)
-    }
-    
-    /// 从HTML页面中提取信息
-    fn parse_html_page(&self, html: &str) -> Result<VideoParseInfo> {
-        // 提取$render_data
-        let pattern = r"\$render_data\s*=\s*(.*?)\[0\]";
-        let re = Regex::new(pattern)?;
-        
-        let json_str = re.captures(html)
-            .and_then(|caps| caps.get(1))
-            .map(|m| format!("{}[0]", m.as_str()))
-            .ok_or_else(|| anyhow!("无法从HTML中提取微博数据"))?;
-        
-        let json: Value = serde_json::from_str(&json_str)?;
-        
-        let mut info = VideoParseInfo::new();
-        
-        info.title = self.clean_text(
-            json.pointer("/status/text")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-        );
+        let media_info = status.pointer("/page_info/media_info")
+            .or_else(|| status.pointer("/retweeted_status/page_info/media_info"));
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(media_info.and_then(|m| m.pointer("/duration")), false),
+            publish_time: created_at(status.pointer("/created_at")),
+            like_count: json_count(status.pointer("/attitudes_count")),
+            comment_count: json_count(status.pointer("/comments_count")),
+            share_count: json_count(status.pointer("/reposts_count")),
+            play_count: json_count(status.pointer("/page_info/play_count")),
+            hashtags: extract_hashtags(&info.title),
+            ..Default::default()
+        });
         
-        info.author.name = json.pointer("/status/user/screen_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+        let mid = status.pointer("/mid")
+            .or_else(|| status.pointer("/id"))
+            .and_then(|v| v.as_str().map(str::to_string).or_else(|| v.as_i64().map(|n| n.to_string())))
+            .unwrap_or_default();
+        info.set_content_id(VideoSource::WeiBo, mid);
         
-        info.author.avatar = json.pointer("/status/user/avatar_large")
+        info
+    }
+    
+    /// 提取图片、LivePhoto、视频以及图文视频混排内容
+    fn extract_media(&self, status: &Value, info: &mut VideoParseInfo) {
+        // pic_video 形如 "0:000abcde,2:000fghij"，表示第几张图片对应的实况视频
+        let pic_videos: Vec<(usize, String)> = status.pointer("/pic_video")
             .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+            .map(|s| {
+                s.split(',')
+                    .filter_map(|item| {
+                        let (index, id) = item.split_once(':')?;
+                        Some((index.trim().parse().ok()?, id.trim().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
         
-        // 提取图片
-        if let Some(pics) = json.pointer("/status/pics").and_then(|v| v.as_array()) {
-            for pic in pics {
-                let image_url = pic.pointer("/large/url")
+        if let Some(pics) = status.pointer("/pics").and_then(|v| v.as_array()) {
+            for (index, pic) in pics.iter().enumerate() {
+                let Some(url) = Self::pic_url(pic) else {
+                    continue;
+                };
+                
+                let live_photo_url = pic.pointer("/videoSrc")
                     .and_then(|v| v.as_str())
-                    .or_else(|| pic.pointer("/original/url").and_then(|v| v.as_str()))
-                    .or_else(|| pic.pointer("/bmiddle/url").and_then(|v| v.as_str()))
-                    .or_else(|| pic.pointer("/url").and_then(|v| v.as_str()));
+                    .filter(|s| !s.is_empty())
+                    .map(Self::normalize_url)
+                    .or_else(|| {
+                        pic_videos.iter()
+                            .find(|(i, _)| *i == index)
+                            .map(|(_, id)| Self::live_photo_url(id))
+                    });
                 
-                if let Some(url) = image_url {
-                    if !url.is_empty() {
-                        info.images.push(ImgInfo {
-                            url: url.to_string(),
-                            live_photo_url: None,
-                        });
+                info.images.push(ImgInfo {
+                    url,
+                    live_photo_url,
+                });
+            }
+        }
+        
+        if let Some(page_info) = status.pointer("/page_info") {
+            if page_info.pointer("/type").and_then(|v| v.as_str()) == Some("video") {
+                info.video_url = Self::page_info_video_url(page_info);
+                info.cover_url = page_info.pointer("/page_pic/url")
+                    .and_then(|v| v.as_str())
+                    .map(Self::normalize_url);
+            }
+        }
+        
+        // 图文视频混排
+        if let Some(items) = status.pointer("/mix_media_info/items").and_then(|v| v.as_array()) {
+            for item in items {
+                match item.pointer("/type").and_then(|v| v.as_str()) {
+                    Some("pic") => {
+                        if let Some(url) = item.pointer("/data").and_then(Self::pic_url) {
+                            info.images.push(ImgInfo {
+                                url,
+                                live_photo_url: None,
+                            });
+                        }
                     }
+                    Some("video") => {
+                        let Some(data) = item.pointer("/data") else {
+                            continue;
+                        };
+                        if info.video_url.is_none() {
+                            info.video_url = Self::page_info_video_url(data);
+                            info.cover_url = data.pointer("/page_pic/url")
+                                .and_then(|v| v.as_str())
+                                .map(Self::normalize_url);
+                        } else {
+                            tracing::debug!("微博混排内容包含多个视频，仅保留第一个");
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
         
-        Ok(info)
+        if info.cover_url.is_none() {
+            info.cover_url = info.images.first().map(|img| img.url.clone());
+        }
+    }
+    
+    fn pic_url(pic: &Value) -> Option<String> {
+        pic.pointer("/large/url")
+            .and_then(|v| v.as_str())
+            .or_else(|| pic.pointer("/original/url").and_then(|v| v.as_str()))
+            .or_else(|| pic.pointer("/bmiddle/url").and_then(|v| v.as_str()))
+            .or_else(|| pic.pointer("/url").and_then(|v| v.as_str()))
+            .filter(|url| !url.is_empty())
+            .map(Self::normalize_url)
+    }
+    
+    /// 按清晰度从高到低选择视频地址
+    fn page_info_video_url(page_info: &Value) -> Option<String> {
+        const URL_KEYS: [&str; 4] = ["mp4_720p_mp4", "mp4_hd_mp4", "mp4_hd_url", "mp4_ld_mp4"];
+        const MEDIA_KEYS: [&str; 5] = ["mp4_720p_mp4", "mp4_hd_url", "stream_url_hd", "mp4_sd_url", "stream_url"];
+        
+        let from_urls = page_info.pointer("/urls").and_then(|urls| {
+            URL_KEYS.iter().find_map(|key| urls.get(*key).and_then(|v| v.as_str()))
+        });
+        let from_media = page_info.pointer("/media_info").and_then(|media| {
+            MEDIA_KEYS.iter().find_map(|key| media.get(*key).and_then(|v| v.as_str()))
+        });
+        
+        from_urls
+            .or(from_media)
+            .filter(|url| !url.is_empty())
+            .map(Self::normalize_url)
+    }
+    
+    /// 实况图片对应的视频地址
+    fn live_photo_url(video_id: &str) -> String {
+        format!(
+            "https://video.weibo.com/media/play?livephoto={}",
+            urlencode(&format!("https://us.sinaimg.cn/{}.mov", video_id))
+        )
+    }
+    
+    fn normalize_url(url: &str) -> String {
+        if url.starts_with("//") {
+            format!("https:{}", url)
+        } else {
+            url.to_string()
+        }
     }
     
     /// 清理HTML标签
@@ -237,3 +641,106 @@ impl WeiboParser {
     }
 }
 
+/// 微博的 `created_at`（如 `Wed May 01 20:00:00 +0800 2024`）转为RFC3339
+fn created_at(value: Option<&Value>) -> Option<String> {
+    value
+        .and_then(|v| v.as_str())
+        .and_then(|s| chrono::DateTime::parse_from_str(s, "%a %b %d %H:%M:%S %z %Y").ok())
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+fn urlencode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jsonp() {
+        let text = r#"window.gen_callback && gen_callback({"retcode":20000000,"data":{"tid":"a+b/c="}});"#;
+        let json = parse_jsonp(text).unwrap();
+        assert_eq!(json.pointer("/data/tid").and_then(|v| v.as_str()), Some("a+b/c="));
+        assert!(parse_jsonp("not jsonp").is_err());
+    }
+
+    #[test]
+    fn test_extract_status_media() {
+        let status = serde_json::json!({
+            "mid": "5001234567890123",
+            "text": "<a href='#'>#话题#</a> 正文",
+            "user": {"id": 123, "screen_name": "作者", "avatar_large": "https://a.jpg"},
+            "pic_video": "1:000live01",
+            "pics": [
+                {"large": {"url": "https://wx1.sinaimg.cn/large/a.jpg"}},
+                {"large": {"url": "https://wx1.sinaimg.cn/large/b.jpg"}}
+            ],
+            "page_info": {
+                "type": "video",
+                "page_pic": {"url": "//wx1.sinaimg.cn/cover.jpg"},
+                "urls": {"mp4_hd_mp4": "//f.video.weibocdn.com/hd.mp4"}
+            }
+        });
+
+        let info = WeiboParser.extract_status(&status);
+        assert_eq!(info.title, "#话题# 正文");
+        assert_eq!(info.author.uid, "123");
+        assert_eq!(info.video_url.as_deref(), Some("https://f.video.weibocdn.com/hd.mp4"));
+        assert_eq!(info.cover_url.as_deref(), Some("https://wx1.sinaimg.cn/cover.jpg"));
+        assert_eq!(info.images[0].live_photo_url, None);
+        assert!(info.images[1].live_photo_url.as_deref().unwrap().contains("000live01.mov"));
+        assert_eq!(info.content_id.as_deref(), Some("5001234567890123"));
+        assert_eq!(info.canonical_url.as_deref(), Some("https://weibo.com/detail/5001234567890123"));
+
+        let retweet = serde_json::json!({
+            "text": "转发",
+            "user": {"id": 1, "screen_name": "转发者"},
+            "retweeted_status": {"text": "原文", "pics": [{"url": "https://wx1.sinaimg.cn/orj360/c.jpg"}]}
+        });
+        let info = WeiboParser.extract_status(&retweet);
+        assert_eq!(info.title, "转发");
+        assert_eq!(info.images.len(), 1);
+
+        let mut info = info;
+        info.finish(VideoSource::WeiBo);
+        assert_eq!(info.source, "weibo");
+        assert_eq!(info.content_type, crate::models::ContentType::Gallery);
+
+        // 纯文字微博
+        let text_only = serde_json::json!({"mid": "5001", "text": "只有文字", "user": {"id": 2, "screen_name": "作者"}});
+        let info = WeiboParser.extract_status(&text_only);
+        assert_eq!(info.title, "只有文字");
+        assert!(info.video_url.is_none() && info.images.is_empty());
+        assert_eq!(info.content_id.as_deref(), Some("5001"));
+    }
+
+    #[test]
+    fn test_parse_comment_page() {
+        let json = serde_json::json!({
+            "ok": 1,
+            "data": {
+                "data": [{
+                    "id": 5001234567890200u64,
+                    "created_at": "Wed May 01 20:00:00 +0800 2024",
+                    "text": "评论<span class=\"url-icon\"><img alt=[赞] src=\"x.png\"></span>",
+                    "like_count": 8,
+                    "total_number": 2,
+                    "user": {"id": 456, "screen_name": "网友", "profile_image_url": "https://tvax1.sinaimg.cn/a.jpg"}
+                }],
+                "total_number": 30,
+                "max_id": 139000000000000u64,
+                "max_id_type": 0
+            }
+        });
+        let page = WeiboParser.parse_comment_page(&json);
+        assert!(page.has_more);
+        assert_eq!(page.cursor.as_deref(), Some("139000000000000_0"));
+        assert_eq!(page.items[0].text, "评论");
+        assert_eq!(page.items[0].author.uid, "456");
+        assert_eq!(page.items[0].publish_time.as_deref(), Some("2024-05-01T20:00:00+08:00"));
+
+        let last = WeiboParser.parse_comment_page(&serde_json::json!({"ok": 1, "data": {"data": [], "max_id": 0}}));
+        assert!(!last.has_more);
+    }
+}
@@ -1,6 +1,8 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::{create_no_redirect_client, create_http_client, extract_json_from_html};
+use crate::js;
+use crate::models::{Author, ContentMetadata, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_no_redirect_client, create_http_client, json_count, json_str, json_duration, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::Value;
@@ -11,12 +13,12 @@ pub struct XiguaParser;
 impl VideoParser for XiguaParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
         // 禁用重定向获取视频ID
-        let client = create_no_redirect_client()?;
+        let client = create_no_redirect_client(VideoSource::XiGua)?;
         
         let response = client
             .get(share_url)
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
-            .send()
+            .send_via(VideoSource::XiGua)
             .await?;
         
         // 获取重定向的Location
@@ -39,30 +41,45 @@ impl VideoParser for XiguaParser {
             video_id
         );
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::XiGua)?;
         let html = client
             .get(&req_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
             .header("Cookie", "MONITOR_WEB_ID=7892c49b-296e-4499-8704-e47c1b150c18; ixigua-a-s=1")
-            .send()
+            .send_via(VideoSource::XiGua)
             .await?
             .text()
             .await?;
         
-        // 提取JSON数据
-        let pattern = r"window._ROUTER_DATA\s*=\s*(.*?)</script>";
-        let json_str = extract_json_from_html(&html, pattern)?;
-        
-        let json: Value = serde_json::from_str(&json_str)?;
+        self.extract_page(&html, video_id).await
+    }
+}
+
+/// 内容为分享页 HTML，视频ID未传入时取数据中的 `aweme_id`
+#[async_trait]
+impl OfflineParser for XiguaParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        self.extract_page(content, video_id.unwrap_or_default()).await
+    }
+}
+
+impl XiguaParser {
+    /// 从分享页的 `_ROUTER_DATA` 中提取视频信息
+    async fn extract_page(&self, html: &str, video_id: &str) -> Result<VideoParseInfo> {
+        let json = js::eval_page_global(html, "window._ROUTER_DATA").await?;
         
         // 提取视频数据
         let video_data = self.find_video_data(&json, video_id)?;
         
-        self.extract_video_info(&video_data)
+        let mut info = self.extract_video_info(&video_data)?;
+        let video_id = match video_id {
+            "" => json_str(video_data.get("aweme_id")).unwrap_or_default(),
+            id => id.to_string(),
+        };
+        info.set_content_id(VideoSource::XiGua, &video_id);
+        Ok(info)
     }
-}
-
-impl XiguaParser {
+    
     /// 从JSON中查找视频数据
     fn find_video_data(&self, json: &Value, video_id: &str) -> Result<Value> {
         // 尝试从loaderData中找到视频数据
@@ -124,6 +141,18 @@ impl XiguaParser {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
         
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(data.pointer("/video/duration"), true),
+            width: json_u32(data.pointer("/video/width")),
+            height: json_u32(data.pointer("/video/height")),
+            publish_time: json_timestamp(data.pointer("/create_time")),
+            like_count: json_count(data.pointer("/statistics/digg_count")),
+            comment_count: json_count(data.pointer("/statistics/comment_count")),
+            share_count: json_count(data.pointer("/statistics/share_count")),
+            play_count: json_count(data.pointer("/statistics/play_count")),
+            ..Default::default()
+        });
+        
         Ok(info)
     }
 }
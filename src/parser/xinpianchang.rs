@@ -1,6 +1,7 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::models::{Author, ContentMetadata, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use scraper::{Html, Selector};
@@ -11,19 +12,42 @@ pub struct XinpianchangParser;
 #[async_trait]
 impl VideoParser for XinpianchangParser {
     async fn parse_share_url(&self, share_url: &str) -> Result<VideoParseInfo> {
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::XinPianChang)?;
         
         let html = client
             .get(share_url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
             .header("Upgrade-Insecure-Requests", "1")
             .header("Referer", "https://www.xinpianchang.com/")
-            .send()
+            .send_via(VideoSource::XinPianChang)
             .await?
             .text()
             .await?;
         
-        let document = Html::parse_document(&html);
+        Self::extract_video_info(&html)
+    }
+    
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        // 文章ID可带 a 前缀，如 a12345678
+        let article_id = video_id.trim().trim_start_matches('a');
+        if article_id.is_empty() || !article_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(anyhow!("无效的新片场视频ID: {}", video_id));
+        }
+        self.parse_share_url(&VideoSource::XinPianChang.canonical_url(article_id)).await
+    }
+}
+
+/// 内容为作品页 HTML
+#[async_trait]
+impl OfflineParser for XinpianchangParser {
+    async fn parse_content(&self, content: &str, _video_id: Option<&str>) -> Result<VideoParseInfo> {
+        Self::extract_video_info(content)
+    }
+}
+
+impl XinpianchangParser {
+    fn extract_video_info(html: &str) -> Result<VideoParseInfo> {
+        let document = Html::parse_document(html);
         
         // 查找 __NEXT_DATA__ script标签
         let script_selector = Selector::parse("#__NEXT_DATA__").unwrap();
@@ -66,6 +90,24 @@ impl VideoParser for XinpianchangParser {
                 .to_string(),
         };
         
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(data.pointer("/duration"), false),
+            width: json_u32(data.pointer("/video/content/progressive/0/width")),
+            height: json_u32(data.pointer("/video/content/progressive/0/height")),
+            publish_time: json_timestamp(data.pointer("/publish_time")),
+            like_count: json_count(data.pointer("/count/count_like")),
+            comment_count: json_count(data.pointer("/count/count_comment")),
+            share_count: json_count(data.pointer("/count/count_share")),
+            play_count: json_count(data.pointer("/count/count_view")),
+            collect_count: json_count(data.pointer("/count/count_collect")),
+            ..Default::default()
+        });
+        
+        let article_id = data.pointer("/id")
+            .and_then(|v| v.as_i64().map(|n| n.to_string()).or_else(|| v.as_str().map(str::to_string)))
+            .unwrap_or_default();
+        info.set_content_id(VideoSource::XinPianChang, article_id);
+        
         Ok(info)
     }
 }
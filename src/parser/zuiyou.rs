@@ -1,6 +1,7 @@
-use crate::models::{Author, VideoParseInfo};
-use crate::parser::VideoParser;
-use crate::utils::create_http_client;
+use crate::models::{Author, ContentMetadata, VideoParseInfo, VideoSource};
+use crate::parser::{OfflineParser, VideoParser};
+use crate::upstream::UpstreamRequest;
+use crate::utils::{create_http_client, json_count, json_duration, json_str, json_timestamp, json_u32};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::{json, Value};
@@ -17,10 +18,26 @@ impl VideoParser for ZuiyouParser {
             .map(|(_, value)| value.to_string())
             .ok_or_else(|| anyhow!("无法从分享链接中解析视频ID"))?;
         
-        let pid_int: i64 = pid.parse()
+        self.parse_video_id(&pid).await
+    }
+    
+    async fn parse_video_id(&self, video_id: &str) -> Result<VideoParseInfo> {
+        let pid: i64 = video_id.trim().parse()
             .map_err(|_| anyhow!("无效的视频ID"))?;
         
-        self.parse_video_by_pid(pid_int).await
+        self.parse_video_by_pid(pid).await
+    }
+}
+
+/// 内容为 detail_h5 接口的响应
+#[async_trait]
+impl OfflineParser for ZuiyouParser {
+    async fn parse_content(&self, content: &str, video_id: Option<&str>) -> Result<VideoParseInfo> {
+        let json: Value = serde_json::from_str(content)?;
+        let mut info = Self::extract_video_info(&json)?;
+        let id = video_id.map(str::to_string).or_else(|| json_str(json.pointer("/data/post/id")));
+        info.set_content_id(VideoSource::ZuiYou, id.unwrap_or_default());
+        Ok(info)
     }
 }
 
@@ -31,16 +48,23 @@ impl ZuiyouParser {
             "pid": pid
         });
         
-        let client = create_http_client()?;
+        let client = create_http_client(VideoSource::ZuiYou)?;
         let response = client
             .post("https://share.xiaochuankeji.cn/planck/share/post/detail_h5")
             .header("User-Agent", crate::utils::DEFAULT_USER_AGENT)
             .json(&post_data)
-            .send()
+            .send_via(VideoSource::ZuiYou)
             .await?;
         
         let json: Value = response.json().await?;
         
+        let mut info = Self::extract_video_info(&json)?;
+        info.set_content_id(VideoSource::ZuiYou, pid.to_string());
+        
+        Ok(info)
+    }
+    
+    fn extract_video_info(json: &Value) -> Result<VideoParseInfo> {
         let data = json.pointer("/data/post")
             .ok_or_else(|| anyhow!("无法获取数据"))?;
         
@@ -115,6 +139,21 @@ impl ZuiyouParser {
             return Err(anyhow!("未找到视频或图片内容"));
         }
         
+        let video = data.get("videos")
+            .and_then(|v| v.as_object())
+            .and_then(|videos| videos.values().next());
+        info.set_metadata(ContentMetadata {
+            duration: json_duration(video.and_then(|v| v.pointer("/dur")), false),
+            width: json_u32(data.pointer("/imgs/0/w")),
+            height: json_u32(data.pointer("/imgs/0/h")),
+            publish_time: json_timestamp(data.pointer("/ct")),
+            like_count: json_count(data.pointer("/likes")),
+            comment_count: json_count(data.pointer("/reviews")),
+            share_count: json_count(data.pointer("/share")),
+            play_count: json_count(video.and_then(|v| v.pointer("/playcnt"))),
+            ..Default::default()
+        });
+        
         Ok(info)
     }
     
@@ -0,0 +1,648 @@
+//! 直播录制：等待开播、拉取 FLV/HLS 流写入分段文件，断流后重连直到下播
+//!
+//! 运行状态以每行一个 JSON 的形式输出到标准输出，便于调度程序监控。
+
+use crate::live::{create_stream_client, read_timeout, with_read_timeout};
+use crate::models::{LiveInfo, LiveStatus, LiveStream, StreamFormat, VideoSource};
+use crate::parser::parse_video_share_url;
+use crate::upstream::is_transient;
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+/// 两次进度输出的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// 断流后重新获取直播流前的等待时间
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+/// 连续多少次重连都没有拉到数据就视为直播结束
+const MAX_EMPTY_RECONNECTS: u32 = 10;
+/// HLS 播放列表连续多少次没有新分片视为断流
+const MAX_STALE_PLAYLISTS: u32 = 10;
+/// 获取直播间信息时连续多少次暂时性失败后放弃
+const MAX_PARSE_RETRIES: u32 = 10;
+
+pub struct RecordOptions {
+    pub output_dir: PathBuf,
+    pub format: StreamFormat,
+    /// 清晰度名称，未指定或不存在时取最高清晰度
+    pub quality: Option<String>,
+    /// 单个分段的最大字节数，0 表示不按大小切分
+    pub segment_bytes: u64,
+    /// 单个分段的最长时长
+    pub segment_duration: Option<Duration>,
+    /// 未开播时检查直播状态的间隔
+    pub poll_interval: Duration,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum RecordEvent<'a> {
+    Waiting { status: LiveStatus },
+    Started { title: &'a str, quality: &'a str, format: StreamFormat, url: &'a str },
+    Segment { index: u32, path: &'a str },
+    Progress { path: &'a str, segment_bytes: u64, total_bytes: u64, elapsed_secs: u64 },
+    Disconnected { reason: String },
+    Error { message: String },
+    Ended { segments: u32, total_bytes: u64, elapsed_secs: u64 },
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: RecordEvent<'a>,
+}
+
+fn emit(event: RecordEvent) {
+    let line = EventLine {
+        time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        event,
+    };
+    if let Ok(json) = serde_json::to_string(&line) {
+        println!("{}", json);
+    }
+}
+
+/// 录制直播间，直到主播下播
+pub async fn record(url: &str, options: RecordOptions) -> Result<()> {
+    let started_at = Instant::now();
+    let mut writer: Option<SegmentWriter> = None;
+    let mut empty_reconnects = 0;
+    // 重连后继续从已录制的 HLS 分片之后开始，避免重复写入
+    let mut hls_sequence: Option<u64> = None;
+    let mut parse_failures = 0;
+
+    loop {
+        let recorded = writer.as_ref().is_some_and(|w| w.total_bytes > 0);
+        let info = match parse_video_share_url(url).await {
+            Ok(info) => {
+                parse_failures = 0;
+                info
+            }
+            Err(e) => {
+                emit(RecordEvent::Error { message: e.to_string() });
+                parse_failures += 1;
+                // 网络、超时、限流和熔断等暂时性错误有限次重试，其他错误（链接无效、直播间不存在、
+                // 下播后解析失败等）不再重试；已录到内容时正常结束，否则返回错误
+                if is_transient(&e) && parse_failures < MAX_PARSE_RETRIES {
+                    tokio::time::sleep(options.poll_interval).await;
+                    continue;
+                }
+                if recorded {
+                    break;
+                }
+                return Err(e);
+            }
+        };
+        let live = info.live.ok_or_else(|| anyhow!("不是直播间链接: {}", url))?;
+
+        let Some(stream) = select_stream(&live, options.format, options.quality.as_deref()) else {
+            if recorded {
+                break;
+            }
+            emit(RecordEvent::Waiting { status: live.status });
+            tokio::time::sleep(options.poll_interval).await;
+            continue;
+        };
+
+        emit(RecordEvent::Started {
+            title: &info.title,
+            quality: &stream.quality,
+            format: stream.format,
+            url: &stream.url,
+        });
+
+        let source = VideoSource::from_str(&info.source);
+        let writer = writer.get_or_insert_with(|| {
+            SegmentWriter::new(&options, &format!("{}_{}", info.source, live.room_id), started_at)
+        });
+        let bytes_before = writer.total_bytes;
+
+        let result = match create_stream_client(source) {
+            Ok(client) => match stream.format {
+                StreamFormat::Flv => record_flv(&client, &stream.url, writer).await,
+                StreamFormat::Hls => record_hls(&client, &stream.url, writer, &mut hls_sequence).await,
+            },
+            Err(e) => Err(e),
+        };
+        // 重连后的数据写入新分段，FLV 需要重新写文件头
+        writer.close().await?;
+
+        emit(RecordEvent::Disconnected {
+            reason: match result {
+                Ok(()) => "直播流结束".to_string(),
+                Err(e) => e.to_string(),
+            },
+        });
+
+        if writer.total_bytes == bytes_before {
+            empty_reconnects += 1;
+            if empty_reconnects >= MAX_EMPTY_RECONNECTS {
+                break;
+            }
+        } else {
+            empty_reconnects = 0;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+
+    emit(RecordEvent::Ended {
+        segments: writer.as_ref().map(|w| w.index).unwrap_or(0),
+        total_bytes: writer.as_ref().map(|w| w.total_bytes).unwrap_or(0),
+        elapsed_secs: started_at.elapsed().as_secs(),
+    });
+    Ok(())
+}
+
+/// 优先选择指定格式和清晰度，没有对应格式时退回另一种格式
+fn select_stream(live: &LiveInfo, format: StreamFormat, quality: Option<&str>) -> Option<LiveStream> {
+    if live.status != LiveStatus::Live {
+        return None;
+    }
+    let preferred: Vec<&LiveStream> = {
+        let same_format: Vec<&LiveStream> = live.streams.iter().filter(|s| s.format == format).collect();
+        if same_format.is_empty() { live.streams.iter().collect() } else { same_format }
+    };
+    quality
+        .and_then(|q| preferred.iter().find(|s| s.quality == q))
+        .or_else(|| preferred.first())
+        .map(|s| (*s).clone())
+}
+
+/// 分段文件写入，按大小或时长切换到下一个文件
+struct SegmentWriter {
+    output_dir: PathBuf,
+    prefix: String,
+    segment_bytes: u64,
+    segment_duration: Option<Duration>,
+    started_at: Instant,
+    file: Option<File>,
+    path: String,
+    index: u32,
+    bytes: u64,
+    opened_at: Instant,
+    total_bytes: u64,
+    last_progress: Instant,
+}
+
+impl SegmentWriter {
+    fn new(options: &RecordOptions, name: &str, started_at: Instant) -> Self {
+        let now = Instant::now();
+        Self {
+            output_dir: options.output_dir.clone(),
+            prefix: format!("{}_{}", name, chrono::Local::now().format("%Y%m%d_%H%M%S")),
+            segment_bytes: options.segment_bytes,
+            segment_duration: options.segment_duration,
+            started_at,
+            file: None,
+            path: String::new(),
+            index: 0,
+            bytes: 0,
+            opened_at: now,
+            total_bytes: 0,
+            last_progress: now,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// 当前分段已达到大小或时长上限
+    fn should_rotate(&self) -> bool {
+        self.is_open()
+            && ((self.segment_bytes > 0 && self.bytes >= self.segment_bytes)
+                || self.segment_duration.is_some_and(|d| self.opened_at.elapsed() >= d))
+    }
+
+    async fn open_next(&mut self, extension: &str) -> Result<()> {
+        self.close().await?;
+        tokio::fs::create_dir_all(&self.output_dir).await?;
+
+        self.index += 1;
+        let path = self.output_dir.join(segment_file_name(&self.prefix, self.index, extension));
+        self.file = Some(File::create(&path).await?);
+        self.path = path.display().to_string();
+        self.bytes = 0;
+        self.opened_at = Instant::now();
+        emit(RecordEvent::Segment { index: self.index, path: &self.path });
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<()> {
+        let file = self.file.as_mut().ok_or_else(|| anyhow!("分段文件未打开"))?;
+        file.write_all(data).await?;
+        self.bytes += data.len() as u64;
+        self.total_bytes += data.len() as u64;
+
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.last_progress = Instant::now();
+            emit(RecordEvent::Progress {
+                path: &self.path,
+                segment_bytes: self.bytes,
+                total_bytes: self.total_bytes,
+                elapsed_secs: self.started_at.elapsed().as_secs(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+fn segment_file_name(prefix: &str, index: u32, extension: &str) -> String {
+    format!("{}_{:03}.{}", prefix, index, extension)
+}
+
+/// 拉取 FLV 流，切分时在关键帧处切换文件并补写文件头和解码头部
+async fn record_flv(client: &Client, url: &str, writer: &mut SegmentWriter) -> Result<()> {
+    let response = with_read_timeout(async { client.get(url).send().await?.error_for_status() }).await?;
+    let mut body = read_timeout(response.bytes_stream());
+    let mut demuxer = FlvDemuxer::default();
+    let mut base_timestamp = None;
+
+    while let Some(chunk) = body.next().await {
+        demuxer.push(&chunk?)?;
+
+        while let Some(mut tag) = demuxer.next_tag()? {
+            if tag.is_init() {
+                // 解码头部保存下来，写在每个分段开头
+                continue;
+            }
+            let at_boundary = tag.keyframe || !demuxer.has_video;
+            if !writer.is_open() || (writer.should_rotate() && at_boundary) {
+                writer.open_next("flv").await?;
+                writer.write(&demuxer.segment_header()).await?;
+                base_timestamp = Some(tag.timestamp);
+            }
+            // 每个分段的时间戳从0开始
+            tag.set_timestamp(tag.timestamp.saturating_sub(base_timestamp.unwrap_or(0)));
+            writer.write(&tag.bytes).await?;
+        }
+    }
+    Ok(())
+}
+
+const FLV_HEADER_LEN: usize = 9;
+const FLV_TAG_HEADER_LEN: usize = 11;
+const FLV_TAG_AUDIO: u8 = 8;
+const FLV_TAG_VIDEO: u8 = 9;
+const FLV_TAG_SCRIPT: u8 = 18;
+
+struct FlvTag {
+    /// tag 头、数据和末尾的 PreviousTagSize
+    bytes: Vec<u8>,
+    kind: u8,
+    timestamp: u32,
+    keyframe: bool,
+    sequence_header: bool,
+}
+
+impl FlvTag {
+    /// 元数据和音视频解码头部
+    fn is_init(&self) -> bool {
+        self.kind == FLV_TAG_SCRIPT || self.sequence_header
+    }
+
+    fn set_timestamp(&mut self, timestamp: u32) {
+        self.bytes[4..7].copy_from_slice(&timestamp.to_be_bytes()[1..]);
+        self.bytes[7] = (timestamp >> 24) as u8;
+        self.timestamp = timestamp;
+    }
+}
+
+/// 把 FLV 字节流拆成完整的 tag，并记录文件头与解码头部
+#[derive(Default)]
+struct FlvDemuxer {
+    buf: Vec<u8>,
+    header: Option<Vec<u8>>,
+    metadata: Option<Vec<u8>>,
+    video_header: Option<Vec<u8>>,
+    audio_header: Option<Vec<u8>>,
+    has_video: bool,
+}
+
+impl FlvDemuxer {
+    fn push(&mut self, data: &[u8]) -> Result<()> {
+        self.buf.extend_from_slice(data);
+        if self.header.is_none() && self.buf.len() >= FLV_HEADER_LEN + 4 {
+            if &self.buf[..3] != b"FLV" {
+                return Err(anyhow!("不是FLV数据"));
+            }
+            let header_len = u32::from_be_bytes([self.buf[5], self.buf[6], self.buf[7], self.buf[8]]) as usize;
+            if self.buf.len() < header_len + 4 {
+                return Ok(());
+            }
+            self.has_video = self.buf[4] & 0x01 != 0;
+            self.header = Some(self.buf[..header_len].to_vec());
+            self.buf.drain(..header_len + 4);
+        }
+        Ok(())
+    }
+
+    fn next_tag(&mut self) -> Result<Option<FlvTag>> {
+        if self.header.is_none() || self.buf.len() < FLV_TAG_HEADER_LEN {
+            return Ok(None);
+        }
+        let data_len = u32::from_be_bytes([0, self.buf[1], self.buf[2], self.buf[3]]) as usize;
+        let total = FLV_TAG_HEADER_LEN + data_len + 4;
+        if self.buf.len() < total {
+            return Ok(None);
+        }
+
+        let bytes: Vec<u8> = self.buf.drain(..total).collect();
+        let kind = bytes[0] & 0x1f;
+        if !matches!(kind, FLV_TAG_AUDIO | FLV_TAG_VIDEO | FLV_TAG_SCRIPT) {
+            return Err(anyhow!("FLV数据损坏，未知的tag类型 {}", kind));
+        }
+        let timestamp = u32::from_be_bytes([bytes[7], bytes[4], bytes[5], bytes[6]]);
+        let data = &bytes[FLV_TAG_HEADER_LEN..FLV_TAG_HEADER_LEN + data_len];
+
+        let (keyframe, sequence_header) = match (kind, data) {
+            // 帧类型1为关键帧；AVC(7)/HEVC(12) 的包类型0为解码头部
+            (FLV_TAG_VIDEO, [first, packet_type, ..]) => (
+                first >> 4 == 1,
+                matches!(first & 0x0f, 7 | 12) && *packet_type == 0,
+            ),
+            // AAC(10) 的包类型0为解码头部
+            (FLV_TAG_AUDIO, [first, packet_type, ..]) => (false, first >> 4 == 10 && *packet_type == 0),
+            _ => (false, false),
+        };
+
+        let tag = FlvTag { bytes, kind, timestamp, keyframe, sequence_header };
+        if tag.is_init() {
+            let mut init = tag.bytes.clone();
+            init[4..8].fill(0);
+            match kind {
+                FLV_TAG_SCRIPT => self.metadata = Some(init),
+                FLV_TAG_VIDEO => {
+                    self.has_video = true;
+                    self.video_header = Some(init);
+                }
+                _ => self.audio_header = Some(init),
+            }
+        }
+        Ok(Some(tag))
+    }
+
+    /// 新分段开头：文件头、PreviousTagSize0、元数据和解码头部
+    fn segment_header(&self) -> Vec<u8> {
+        let mut out = self.header.clone().unwrap_or_default();
+        out.extend_from_slice(&[0; 4]);
+        for init in [&self.metadata, &self.video_header, &self.audio_header].into_iter().flatten() {
+            out.extend_from_slice(init);
+        }
+        out
+    }
+}
+
+/// 下载播放列表或分片，每次读取都有超时
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let response = with_read_timeout(async { client.get(url).send().await?.error_for_status() }).await?;
+    let mut body = read_timeout(response.bytes_stream());
+    let mut data = Vec::new();
+    while let Some(chunk) = body.next().await {
+        data.extend_from_slice(&chunk?);
+    }
+    Ok(data)
+}
+
+/// 轮询 HLS 播放列表，按顺序下载新分片；分片边界处切换文件
+///
+/// `last_sequence` 为已写入的最后一个分片序号，重连时沿用以跳过已录制的分片。
+async fn record_hls(
+    client: &Client,
+    url: &str,
+    writer: &mut SegmentWriter,
+    last_sequence: &mut Option<u64>,
+) -> Result<()> {
+    let mut playlist_url = Url::parse(url)?;
+    let mut stale = 0;
+
+    loop {
+        let text = String::from_utf8_lossy(&download(client, playlist_url.as_str()).await?).into_owned();
+        let playlist = MediaPlaylist::parse(&text, &playlist_url)?;
+
+        // 主播放列表：改为拉取第一个子列表
+        if let Some(variant) = playlist.variant {
+            playlist_url = Url::parse(&variant)?;
+            continue;
+        }
+
+        let mut fresh = 0;
+        for (sequence, segment_url) in playlist.fresh_segments(*last_sequence) {
+            let data = download(client, segment_url).await?;
+
+            if !writer.is_open() || writer.should_rotate() {
+                let extension = if playlist.init.is_some() { "mp4" } else { "ts" };
+                writer.open_next(extension).await?;
+                if let Some(init) = &playlist.init {
+                    let init_data = download(client, init).await?;
+                    writer.write(&init_data).await?;
+                }
+            }
+            writer.write(&data).await?;
+            *last_sequence = Some(*sequence);
+            fresh += 1;
+        }
+
+        if playlist.ended {
+            return Ok(());
+        }
+        if fresh == 0 {
+            stale += 1;
+            if stale >= MAX_STALE_PLAYLISTS {
+                return Err(anyhow!("播放列表长时间没有更新"));
+            }
+        } else {
+            stale = 0;
+        }
+        tokio::time::sleep(Duration::from_secs_f64((playlist.target_duration / 2.0).max(1.0))).await;
+    }
+}
+
+/// HLS 播放列表中录制需要的部分
+#[derive(Debug, Default)]
+struct MediaPlaylist {
+    /// 主播放列表中的第一个子列表
+    variant: Option<String>,
+    target_duration: f64,
+    /// (媒体序号, 分片地址)
+    segments: Vec<(u64, String)>,
+    /// fMP4 的初始化分片
+    init: Option<String>,
+    ended: bool,
+}
+
+impl MediaPlaylist {
+    fn parse(text: &str, base: &Url) -> Result<Self> {
+        if !text.trim_start().starts_with("#EXTM3U") {
+            return Err(anyhow!("不是有效的HLS播放列表"));
+        }
+        let resolve = |uri: &str| base.join(uri).map(|u| u.to_string());
+
+        let mut playlist = MediaPlaylist { target_duration: 2.0, ..Default::default() };
+        let mut sequence = 0;
+        let mut expect_variant = false;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+                playlist.target_duration = value.parse().unwrap_or(2.0);
+            } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+                sequence = value.parse().unwrap_or(0);
+            } else if line.starts_with("#EXT-X-STREAM-INF") {
+                expect_variant = true;
+            } else if let Some(attrs) = line.strip_prefix("#EXT-X-MAP:") {
+                playlist.init = attrs
+                    .split("URI=\"")
+                    .nth(1)
+                    .and_then(|rest| rest.split('"').next())
+                    .map(resolve)
+                    .transpose()?;
+            } else if line == "#EXT-X-ENDLIST" {
+                playlist.ended = true;
+            } else if !line.starts_with('#') {
+                if expect_variant {
+                    playlist.variant = Some(resolve(line)?);
+                    return Ok(playlist);
+                }
+                playlist.segments.push((sequence, resolve(line)?));
+                sequence += 1;
+            }
+        }
+        Ok(playlist)
+    }
+
+    /// 序号在 `last_sequence` 之后的分片；整个列表落后超过一个窗口时视为直播流重新开始，全部返回
+    fn fresh_segments(&self, last_sequence: Option<u64>) -> impl Iterator<Item = &(u64, String)> {
+        let window = self.segments.len() as u64;
+        let restarted = match (last_sequence, self.segments.last()) {
+            (Some(last), Some((newest, _))) => newest.saturating_add(window) < last,
+            _ => false,
+        };
+        let last = last_sequence.filter(|_| !restarted);
+        self.segments
+            .iter()
+            .filter(move |(sequence, _)| last.is_none_or(|last| *sequence > last))
+    }
+}
+
+/// 输出目录不存在时提前创建，尽早发现权限问题
+pub async fn prepare_output_dir(dir: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| anyhow!("无法创建输出目录 {}: {}", dir.display(), e))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flv_tag(kind: u8, timestamp: u32, data: &[u8]) -> Vec<u8> {
+        let mut tag = vec![kind];
+        tag.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+        tag.extend_from_slice(&timestamp.to_be_bytes()[1..]);
+        tag.push((timestamp >> 24) as u8);
+        tag.extend_from_slice(&[0, 0, 0]);
+        tag.extend_from_slice(data);
+        tag.extend_from_slice(&((FLV_TAG_HEADER_LEN + data.len()) as u32).to_be_bytes());
+        tag
+    }
+
+    #[test]
+    fn test_flv_demuxer() {
+        let mut stream = b"FLV\x01\x05\x00\x00\x00\x09\x00\x00\x00\x00".to_vec();
+        stream.extend(flv_tag(FLV_TAG_SCRIPT, 0, b"meta"));
+        stream.extend(flv_tag(FLV_TAG_VIDEO, 0, &[0x17, 0x00, 1]));
+        stream.extend(flv_tag(FLV_TAG_AUDIO, 0, &[0xaf, 0x00, 2]));
+        stream.extend(flv_tag(FLV_TAG_VIDEO, 5000, &[0x17, 0x01, 3]));
+        stream.extend(flv_tag(FLV_TAG_VIDEO, 5040, &[0x27, 0x01, 4]));
+
+        // 分成小块送入，tag 跨块也要能拼出来
+        let mut demuxer = FlvDemuxer::default();
+        let mut tags = Vec::new();
+        for chunk in stream.chunks(7) {
+            demuxer.push(chunk).unwrap();
+            while let Some(tag) = demuxer.next_tag().unwrap() {
+                tags.push(tag);
+            }
+        }
+
+        assert_eq!(tags.len(), 5);
+        assert!(tags[..3].iter().all(|t| t.is_init()));
+        assert!(tags[3].keyframe && !tags[3].is_init());
+        assert!(!tags[4].keyframe);
+        assert_eq!(tags[4].timestamp, 5040);
+
+        let header = demuxer.segment_header();
+        assert_eq!(header.len(), 13 + 19 + 18 + 18);
+        assert_eq!(&header[..3], b"FLV");
+
+        let mut tag = tags.remove(4);
+        tag.set_timestamp(40);
+        assert_eq!(&tag.bytes[4..8], &[0, 0, 40, 0]);
+    }
+
+    #[test]
+    fn test_parse_media_playlist() {
+        let base = Url::parse("https://cdn.example.com/live/index.m3u8").unwrap();
+        let playlist = MediaPlaylist::parse(
+            "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXT-X-MEDIA-SEQUENCE:120\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:4.0,\n120.m4s\n#EXTINF:4.0,\n121.m4s\n",
+            &base,
+        ).unwrap();
+        assert_eq!(playlist.target_duration, 4.0);
+        assert_eq!(playlist.init.as_deref(), Some("https://cdn.example.com/live/init.mp4"));
+        assert_eq!(playlist.segments[1], (121, "https://cdn.example.com/live/121.m4s".to_string()));
+        assert!(!playlist.ended);
+
+        let master = MediaPlaylist::parse("#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1\nhd/index.m3u8\n", &base).unwrap();
+        assert_eq!(master.variant.as_deref(), Some("https://cdn.example.com/live/hd/index.m3u8"));
+    }
+
+    #[test]
+    fn test_fresh_segments() {
+        let base = Url::parse("https://cdn.example.com/live/index.m3u8").unwrap();
+        let playlist = MediaPlaylist::parse(
+            "#EXTM3U\n#EXT-X-MEDIA-SEQUENCE:120\n120.ts\n121.ts\n122.ts\n",
+            &base,
+        ).unwrap();
+        let sequences = |last| playlist.fresh_segments(last).map(|(s, _)| *s).collect::<Vec<_>>();
+
+        assert_eq!(sequences(None), [120, 121, 122]);
+        // 重连后跳过已录制的分片
+        assert_eq!(sequences(Some(121)), [122]);
+        assert_eq!(sequences(Some(122)), Vec::<u64>::new());
+        assert_eq!(sequences(Some(123)), Vec::<u64>::new());
+        // 序号大幅回退说明直播流重新开始
+        assert_eq!(sequences(Some(500)), [120, 121, 122]);
+    }
+
+    #[test]
+    fn test_select_stream() {
+        let stream = |quality: &str, format| LiveStream { quality: quality.to_string(), format, url: String::new() };
+        let live = LiveInfo {
+            room_id: "1".to_string(),
+            status: LiveStatus::Live,
+            online_count: None,
+            streams: vec![stream("原画", StreamFormat::Flv), stream("高清", StreamFormat::Flv), stream("原画", StreamFormat::Hls)],
+        };
+
+        assert_eq!(select_stream(&live, StreamFormat::Flv, Some("高清")).map(|s| s.quality), Some("高清".to_string()));
+        assert_eq!(select_stream(&live, StreamFormat::Hls, Some("高清")).map(|s| s.format), Some(StreamFormat::Hls));
+        assert_eq!(segment_file_name("huya_1_20240101_000000", 2, "flv"), "huya_1_20240101_000000_002.flv");
+
+        let offline = LiveInfo { status: LiveStatus::Offline, ..live };
+        assert!(select_stream(&offline, StreamFormat::Flv, None).is_none());
+    }
+}
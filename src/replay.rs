@@ -0,0 +1,365 @@
+//! 上游请求录制与回放
+//!
+//! 在 [`scope`] 中执行的解析，其所有经 [`UpstreamRequest`](crate::upstream::UpstreamRequest)
+//! 发出的请求都交给当前的 [`Cassette`]：
+//! - 录制：照常发送，同时把请求和响应记下来，保存时去掉 Cookie、令牌和签名等敏感信息
+//! - 回放：不发起任何网络请求，按方法、域名和路径依次匹配记录中的响应
+//!
+//! 录制结果和解析结果一起保存为夹具（`<name>.json` 与 `<name>.golden.json`），
+//! `cargo test` 回放全部夹具并与快照比对，以此区分平台改版和我们自己的回归。
+
+use crate::credentials::{credentials, parse_cookie_header};
+use crate::models::{VideoParseInfo, VideoSource};
+use crate::upstream::UpstreamKey;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use reqwest::header::{CONTENT_TYPE, SET_COOKIE};
+use reqwest::{RequestBuilder, Response, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// 替换敏感信息时使用的占位符
+pub const SCRUBBED: &str = "SCRUBBED";
+
+/// 参数名包含这些片段时视为敏感参数，值会被替换
+const SECRET_PARAMS: [&str; 9] = [
+    "token", "sign", "bogus", "sessdata", "ticket", "cookie", "w_rid", "access_key", "session",
+];
+
+/// 保存到夹具中的响应头，其余响应头回放时用不到
+const KEPT_HEADERS: [&str; 3] = ["content-type", "location", "set-cookie"];
+
+tokio::task_local! {
+    static CASSETTE: Arc<Cassette>;
+}
+
+/// 一次请求与响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    /// 跟随重定向后的地址，与请求地址相同时省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// JSON 响应直接保存为对象，其余为文本，二进制内容为 base64
+    #[serde(default)]
+    pub body: Value,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+}
+
+/// 夹具文件：解析的输入链接和过程中的全部请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    pub url: String,
+    /// 按接口结构手工整理、并非真实录制的夹具，只能发现代码回归，不能证明能解析线上响应
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub synthetic: bool,
+    pub exchanges: Vec<Exchange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug)]
+pub struct Cassette {
+    mode: Mode,
+    exchanges: Mutex<Vec<Exchange>>,
+    /// 回放时已使用过的记录
+    used: Mutex<Vec<bool>>,
+}
+
+impl Cassette {
+    pub fn record() -> Self {
+        Self {
+            mode: Mode::Record,
+            exchanges: Mutex::new(Vec::new()),
+            used: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn replay(exchanges: Vec<Exchange>) -> Self {
+        let used = vec![false; exchanges.len()];
+        Self {
+            mode: Mode::Replay,
+            exchanges: Mutex::new(exchanges),
+            used: Mutex::new(used),
+        }
+    }
+
+    /// 已录制的请求，敏感信息已去除
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 回放中没有被使用的记录数
+    pub fn unused(&self) -> usize {
+        self.used.lock().unwrap_or_else(|e| e.into_inner()).iter().filter(|used| !**used).count()
+    }
+
+    pub async fn send(&self, builder: RequestBuilder, key: UpstreamKey) -> Result<Response> {
+        let (client, request) = builder.build_split();
+        let request = request?;
+        let method = request.method().to_string();
+        let url = request.url().clone();
+
+        if self.mode == Mode::Replay {
+            return self.find(&method, &url);
+        }
+
+        let source = key.builtin();
+        let response = crate::upstream::send_direct(RequestBuilder::from_parts(client, request), key).await?;
+        let status = response.status();
+        let final_url = response.url().clone();
+        let headers = response.headers().clone();
+        let bytes = response.bytes().await?;
+
+        let mut secrets = secrets_for(source, &url);
+        // 访客通行证等接口会在正文中重复 Set-Cookie 的值
+        secrets.extend(
+            headers
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .filter_map(|value| value.split(';').next()?.split_once('=').map(|(_, v)| v.trim().to_string()))
+                .filter(|value| value.len() >= 8),
+        );
+        let (body, base64) = match std::str::from_utf8(&bytes) {
+            Ok(text) => {
+                let text = scrub_text(text, &secrets);
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(json) if json.is_object() || json.is_array() => (json, false),
+                    _ => (Value::String(text), false),
+                }
+            }
+            Err(_) => (Value::String(base64::engine::general_purpose::STANDARD.encode(&bytes)), true),
+        };
+        let exchange = Exchange {
+            method,
+            url: scrub_url(&url),
+            status: status.as_u16(),
+            final_url: (final_url != url).then(|| scrub_url(&final_url)),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+                .filter_map(|(name, value)| {
+                    let value = value.to_str().ok()?;
+                    let value = match name.as_str() {
+                        "set-cookie" => scrub_set_cookie(value),
+                        "location" => Url::parse(value).map(|u| scrub_url(&u)).unwrap_or_else(|_| value.to_string()),
+                        _ => value.to_string(),
+                    };
+                    Some((name.to_string(), value))
+                })
+                .collect(),
+            body,
+            base64,
+        };
+        self.exchanges.lock().unwrap_or_else(|e| e.into_inner()).push(exchange);
+
+        // 调用方拿到的是未去除敏感信息的原始响应
+        let mut builder = http::Response::builder().status(status).url(final_url);
+        for (name, value) in headers.iter() {
+            builder = builder.header(name, value);
+        }
+        Ok(Response::from(builder.body(bytes)?))
+    }
+
+    /// 按方法、域名和路径找到第一条未使用的记录，查询参数中常有时间戳和签名，不参与匹配
+    fn find(&self, method: &str, url: &Url) -> Result<Response> {
+        let exchanges = self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let index = exchanges
+            .iter()
+            .enumerate()
+            .position(|(i, exchange)| {
+                !used[i]
+                    && exchange.method == method
+                    && Url::parse(&exchange.url)
+                        .is_ok_and(|u| u.host_str() == url.host_str() && u.path() == url.path())
+            })
+            .ok_or_else(|| anyhow!("回放记录中没有请求 {} {}", method, url))?;
+        used[index] = true;
+        exchanges[index].to_response()
+    }
+}
+
+impl Exchange {
+    fn to_response(&self) -> Result<Response> {
+        let url = Url::parse(self.final_url.as_deref().unwrap_or(&self.url))?;
+        let mut builder = http::Response::builder().status(self.status).url(url);
+        let has_content_type = self.headers.iter().any(|(name, _)| name == CONTENT_TYPE.as_str());
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        let body = match &self.body {
+            Value::String(text) if self.base64 => base64::engine::general_purpose::STANDARD.decode(text)?,
+            Value::String(text) => text.clone().into_bytes(),
+            Value::Null => Vec::new(),
+            json => {
+                if !has_content_type {
+                    builder = builder.header(CONTENT_TYPE, "application/json");
+                }
+                json.to_string().into_bytes()
+            }
+        };
+        Ok(Response::from(builder.body(body)?))
+    }
+}
+
+/// 当前任务使用的录制或回放器
+pub fn active() -> Option<Arc<Cassette>> {
+    CASSETTE.try_with(|cassette| cassette.clone()).ok()
+}
+
+/// 在录制或回放下执行 `f`，其中发出的上游请求都经过 `cassette`
+pub async fn scope<F: Future>(cassette: Arc<Cassette>, f: F) -> F::Output {
+    CASSETTE.scope(cassette, f).await
+}
+
+/// 夹具对应的解析结果快照路径，`douyin/video.json` 对应 `douyin/video.golden.json`
+pub fn golden_path(fixture: &Path) -> PathBuf {
+    fixture.with_extension("golden.json")
+}
+
+pub fn load_fixture(path: &Path) -> Result<Fixture> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("读取夹具 {} 失败: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("夹具 {} 格式错误: {}", path.display(), e))
+}
+
+/// 保存录制结果和解析结果快照
+pub fn save_fixture(path: &Path, fixture: &Fixture, info: &VideoParseInfo) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(fixture)? + "\n")?;
+    std::fs::write(golden_path(path), serde_json::to_string_pretty(info)? + "\n")?;
+    Ok(())
+}
+
+/// 回放夹具，所有请求都必须命中记录
+pub async fn replay_fixture(fixture: &Fixture) -> Result<VideoParseInfo> {
+    let cassette = Arc::new(Cassette::replay(fixture.exchanges.clone()));
+    let info = scope(cassette.clone(), crate::parser::parse_video_share_url(&fixture.url)).await?;
+    if cassette.unused() > 0 {
+        tracing::debug!("夹具中有 {} 条记录未被使用", cassette.unused());
+    }
+    Ok(info)
+}
+
+/// 请求时携带的账号 Cookie 值，可能出现在响应正文中
+fn secrets_for(source: Option<VideoSource>, url: &Url) -> Vec<String> {
+    source
+        .and_then(|source| credentials().current(source))
+        .and_then(|account| account.cookie_header(url))
+        .map(|header| {
+            parse_cookie_header(&header)
+                .into_iter()
+                .map(|cookie| cookie.value)
+                .filter(|value| value.len() >= 8)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn scrub_text(text: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), SCRUBBED))
+}
+
+fn is_secret_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SECRET_PARAMS.iter().any(|part| name.contains(part))
+}
+
+/// 替换查询参数中的令牌和签名
+pub fn scrub_url(url: &Url) -> String {
+    if !url.query_pairs().any(|(name, _)| is_secret_param(&name)) {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_secret_param(&name) { SCRUBBED.to_string() } else { value.into_owned() };
+            (name.into_owned(), value)
+        })
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+/// 只保留 `Set-Cookie` 的名称和属性
+fn scrub_set_cookie(header: &str) -> String {
+    let (pair, attributes) = header.split_once(';').unwrap_or((header, ""));
+    let name = pair.split_once('=').map(|(name, _)| name).unwrap_or(pair).trim();
+    if attributes.is_empty() {
+        format!("{}={}", name, SCRUBBED)
+    } else {
+        format!("{}={};{}", name, SCRUBBED, attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub() {
+        let url = Url::parse("https://www.douyin.com/aweme/v1/web/aweme/detail/?aweme_id=1&msToken=abc&a_bogus=xyz").unwrap();
+        assert_eq!(
+            scrub_url(&url),
+            "https://www.douyin.com/aweme/v1/web/aweme/detail/?aweme_id=1&msToken=SCRUBBED&a_bogus=SCRUBBED"
+        );
+        let url = Url::parse("https://api.bilibili.com/x/web-interface/view?bvid=BV1").unwrap();
+        assert_eq!(scrub_url(&url), "https://api.bilibili.com/x/web-interface/view?bvid=BV1");
+
+        assert_eq!(scrub_set_cookie("SUB=secret; Path=/; HttpOnly"), "SUB=SCRUBBED; Path=/; HttpOnly");
+        assert_eq!(scrub_set_cookie("ttwid=1%7Cabc"), "ttwid=SCRUBBED");
+        assert_eq!(scrub_text("uid=12345678 ok", &["12345678".to_string()]), "uid=SCRUBBED ok");
+    }
+
+    #[tokio::test]
+    async fn test_replay_matching() {
+        let exchange = |url: &str, body: Value| Exchange {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            status: 200,
+            final_url: None,
+            headers: Vec::new(),
+            body,
+            base64: false,
+        };
+        let cassette = Arc::new(Cassette::replay(vec![
+            exchange("https://example.com/api?page=1", serde_json::json!({"page": 1})),
+            exchange("https://example.com/api?page=2", serde_json::json!({"page": 2})),
+            exchange("https://example.com/page", Value::String("<html></html>".to_string())),
+        ]));
+
+        let client = reqwest::Client::new();
+        let result = scope(cassette.clone(), async {
+            let first: Value = cassette.send(client.get("https://example.com/api?t=9"), UpstreamKey::from_source("example")).await?.json().await?;
+            let second: Value = cassette.send(client.get("https://example.com/api?t=10"), UpstreamKey::from_source("example")).await?.json().await?;
+            let missing = cassette.send(client.get("https://example.com/api"), UpstreamKey::from_source("example")).await;
+            anyhow::Ok((first, second, missing.is_err()))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result, (serde_json::json!({"page": 1}), serde_json::json!({"page": 2}), true));
+        assert_eq!(cassette.unused(), 1);
+        assert!(active().is_none());
+    }
+}
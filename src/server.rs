@@ -1,10 +1,16 @@
-use crate::models::{HttpResponse, VideoParseInfo, VideoSource};
-use crate::parser::{parse_video_id, parse_video_share_url, get_supported_platforms};
+use crate::capture;
+use crate::config::config;
+use crate::danmaku::{to_ass, AssOptions};
+use crate::subtitle::{render, select_track, SubtitleFormat};
+use crate::live::{create_stream_client, is_hls_url, read_timeout, rewrite_hls_playlist, with_read_timeout};
+use crate::models::{CommentPage, HttpResponse, ListingPage, VideoParseInfo, VideoSource};
+use crate::parser::{canonical_share_url, collect_listing, fetch_comments, fetch_comments_by_url, fetch_danmaku, fetch_danmaku_by_url, is_listing_url, parse_video_id, parse_video_share_url, get_supported_platforms};
+use crate::upstream::{apply_proxy, rotate_proxy, upstream_status, UpstreamStatus};
 use crate::utils::extract_url_from_string;
 use axum::{
     body::Body,
     extract::{Query, ConnectInfo},
-    http::{header, StatusCode, Method, Uri},
+    http::{header, HeaderMap, StatusCode, Method, Uri},
     middleware::{self, Next},
     response::{Html, IntoResponse, Json, Response},
     routing::get,
@@ -68,17 +74,40 @@ pub async fn start_server(port: u16) -> anyhow::Result<()> {
     println!("[RUST-debug] GET    /                              --> index_handler");
     println!("[RUST-debug] GET    /api/video/share/url/parse     --> parse_share_url_handler");
     println!("[RUST-debug] GET    /api/video/id/parse            --> parse_video_id_handler");
+    println!("[RUST-debug] GET    /api/video/id/share_url        --> share_url_handler");
+    println!("[RUST-debug] GET    /api/user/works                --> list_handler");
+    println!("[RUST-debug] GET    /api/list                      --> list_handler");
+    println!("[RUST-debug] GET    /api/comments                  --> comments_handler");
+    println!("[RUST-debug] GET    /api/danmaku                   --> danmaku_handler");
+    println!("[RUST-debug] GET    /api/subtitle                  --> subtitle_handler");
     println!("[RUST-debug] GET    /api/platforms                 --> platforms_handler");
     println!("[RUST-debug] GET    /api/proxy/video               --> proxy_video_handler");
-    println!("[RUST-debug] GET    /api/proxy/image               --> proxy_image_handler\n");
+    println!("[RUST-debug] GET    /api/proxy/audio               --> proxy_audio_handler");
+    println!("[RUST-debug] GET    /api/proxy/image               --> proxy_image_handler");
+    println!("[RUST-debug] GET    /api/proxy/live                --> proxy_live_handler");
+    println!("[RUST-debug] GET    /api/admin/upstream            --> upstream_status_handler");
+    println!("[RUST-debug] GET    /api/admin/captures            --> captures_handler");
+    println!("[RUST-debug] GET    /api/admin/captures/download   --> capture_download_handler\n");
     
     // API 路由（带 /api 前缀）
     let api_routes = Router::new()
         .route("/video/share/url/parse", get(parse_share_url_handler))
         .route("/video/id/parse", get(parse_video_id_handler))
+        .route("/video/id/share_url", get(share_url_handler))
+        // 旧的创作者作品接口，与 /api/list 相同
+        .route("/user/works", get(list_handler))
+        .route("/list", get(list_handler))
+        .route("/comments", get(comments_handler))
+        .route("/danmaku", get(danmaku_handler))
+        .route("/subtitle", get(subtitle_handler))
         .route("/platforms", get(platforms_handler))
         .route("/proxy/video", get(proxy_video_handler))
-        .route("/proxy/image", get(proxy_image_handler));
+        .route("/proxy/audio", get(proxy_audio_handler))
+        .route("/proxy/image", get(proxy_image_handler))
+        .route("/proxy/live", get(proxy_live_handler))
+        .route("/admin/upstream", get(upstream_status_handler))
+        .route("/admin/captures", get(captures_handler))
+        .route("/admin/captures/download", get(capture_download_handler));
     
     // 主应用路由
     let app = Router::new()
@@ -131,6 +160,9 @@ async fn title_image_handler() -> impl IntoResponse {
 #[derive(Debug, Deserialize)]
 struct ParseShareUrlQuery {
     url: String,
+    /// 解析失败时保存上游请求记录
+    #[serde(default)]
+    debug: bool,
 }
 
 async fn parse_share_url_handler(
@@ -143,9 +175,13 @@ async fn parse_share_url_handler(
         }
     };
     
-    match parse_video_share_url(&url).await {
-        Ok(info) => Json(HttpResponse::success(info)),
-        Err(e) => Json(HttpResponse::error(format!("视频解析失败: {}", e))),
+    if is_listing_url(&url) {
+        return Json(HttpResponse::error("这是合集、主页或音乐等列表链接，请使用 /api/list 接口".to_string()));
+    }
+    
+    match capture::run(params.debug, &url, parse_video_share_url(&url)).await {
+        (Ok(info), _) => Json(HttpResponse::success(info)),
+        (Err(e), capture_id) => Json(HttpResponse::error(format!("视频解析失败: {}", e)).with_capture(capture_id)),
     }
 }
 
@@ -153,6 +189,9 @@ async fn parse_share_url_handler(
 struct ParseVideoIdQuery {
     source: String,
     video_id: String,
+    /// 解析失败时保存上游请求记录
+    #[serde(default)]
+    debug: bool,
 }
 
 async fn parse_video_id_handler(
@@ -169,13 +208,214 @@ async fn parse_video_id_handler(
         }
     };
 
-    // 解析视频
-    match parse_video_id(source, &params.video_id).await {
-        Ok(info) => Json(HttpResponse::success(info)),
-        Err(e) => Json(HttpResponse::error(format!("视频解析失败: {}", e))),
+    // 解析失败记录中保存规范链接，以便按分享链接回放
+    let url = canonical_share_url(source, &params.video_id).unwrap_or_else(|_| params.video_id.clone());
+    match capture::run(params.debug, &url, parse_video_id(source, &params.video_id)).await {
+        (Ok(info), _) => Json(HttpResponse::success(info)),
+        (Err(e), capture_id) => Json(HttpResponse::error(format!("视频解析失败: {}", e)).with_capture(capture_id)),
     }
 }
 
+#[derive(Debug, Serialize)]
+struct ShareUrlInfo {
+    source: String,
+    video_id: String,
+    url: String,
+}
+
+async fn share_url_handler(
+    Query(params): Query<ParseVideoIdQuery>,
+) -> Json<HttpResponse<ShareUrlInfo>> {
+    let source = match VideoSource::from_str(&params.source) {
+        Some(s) => s,
+        None => {
+            return Json(HttpResponse::error(format!(
+                "不支持的平台: {}",
+                params.source
+            )));
+        }
+    };
+
+    match canonical_share_url(source, &params.video_id) {
+        Ok(url) => Json(HttpResponse::success(ShareUrlInfo {
+            source: source.as_str().to_string(),
+            video_id: params.video_id,
+            url,
+        })),
+        Err(e) => Json(HttpResponse::error(e.to_string())),
+    }
+}
+
+/// 单次请求最多返回的列表项数
+const MAX_LIST_LIMIT: usize = 200;
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    url: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn list_handler(
+    Query(params): Query<ListQuery>,
+) -> Json<HttpResponse<ListingPage>> {
+    let url = match extract_url_from_string(&params.url) {
+        Ok(url) => url,
+        Err(e) => {
+            return Json(HttpResponse::error(format!("URL提取失败: {}", e)));
+        }
+    };
+
+    let limit = params.limit.unwrap_or(20).clamp(1, MAX_LIST_LIMIT);
+
+    match collect_listing(&url, params.cursor.as_deref(), limit).await {
+        Ok(page) => Json(HttpResponse::success(page)),
+        Err(e) => Json(HttpResponse::error(format!("列表获取失败: {}", e))),
+    }
+}
+
+/// 通过分享链接，或平台加内容ID指定作品
+#[derive(Debug, Deserialize)]
+struct CommentsQuery {
+    url: Option<String>,
+    source: Option<String>,
+    id: Option<String>,
+    cursor: Option<String>,
+}
+
+async fn comments_handler(
+    Query(params): Query<CommentsQuery>,
+) -> Json<HttpResponse<CommentPage>> {
+    let cursor = params.cursor.as_deref();
+    let result = match (&params.source, &params.id, &params.url) {
+        (Some(source), Some(id), _) => match VideoSource::from_str(source) {
+            Some(source) => fetch_comments(source, id, cursor).await,
+            None => return Json(HttpResponse::error(format!("不支持的平台: {}", source))),
+        },
+        (_, _, Some(url)) => match extract_url_from_string(url) {
+            Ok(url) => fetch_comments_by_url(&url, cursor).await,
+            Err(e) => return Json(HttpResponse::error(format!("URL提取失败: {}", e))),
+        },
+        _ => return Json(HttpResponse::error("请提供 url，或 source 和 id 参数".to_string())),
+    };
+
+    match result {
+        Ok(page) => Json(HttpResponse::success(page)),
+        Err(e) => Json(HttpResponse::error(format!("评论获取失败: {}", e))),
+    }
+}
+
+/// 弹幕查询参数，作品的指定方式与评论相同；`format=json` 时返回原始弹幕列表
+#[derive(Debug, Deserialize)]
+struct DanmakuQuery {
+    url: Option<String>,
+    source: Option<String>,
+    id: Option<String>,
+    format: Option<String>,
+    font_size: Option<u32>,
+    duration: Option<f64>,
+    density: Option<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+async fn danmaku_handler(Query(params): Query<DanmakuQuery>) -> Response {
+    let result = match (&params.source, &params.id, &params.url) {
+        (Some(source), Some(id), _) => match VideoSource::from_str(source) {
+            Some(source) => fetch_danmaku(source, id).await.map(|items| (id.trim().to_string(), items)),
+            None => return Json(HttpResponse::<()>::error(format!("不支持的平台: {}", source))).into_response(),
+        },
+        (_, _, Some(url)) => match extract_url_from_string(url) {
+            Ok(url) => fetch_danmaku_by_url(&url).await.map(|(_, id, items)| (id, items)),
+            Err(e) => return Json(HttpResponse::<()>::error(format!("URL提取失败: {}", e))).into_response(),
+        },
+        _ => return Json(HttpResponse::<()>::error("请提供 url，或 source 和 id 参数".to_string())).into_response(),
+    };
+    let (content_id, items) = match result {
+        Ok(result) => result,
+        Err(e) => return Json(HttpResponse::<()>::error(format!("弹幕获取失败: {}", e))).into_response(),
+    };
+
+    if params.format.as_deref() == Some("json") {
+        return Json(HttpResponse::success(items)).into_response();
+    }
+
+    // 参数来自请求，限制范围后再分配轨道
+    let defaults = AssOptions::default();
+    let options = AssOptions {
+        width: params.width.unwrap_or(defaults.width),
+        height: params.height.unwrap_or(defaults.height),
+        font_size: params.font_size.unwrap_or(defaults.font_size),
+        scroll_duration: params.duration.unwrap_or(defaults.scroll_duration),
+        density: params.density.unwrap_or(defaults.density),
+        ..defaults
+    }
+    .clamped();
+    (
+        [
+            (header::CONTENT_TYPE, "text/x-ssa; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, attachment_disposition(&format!("{}.ass", content_id))),
+        ],
+        to_ass(&items, &options),
+    )
+        .into_response()
+}
+
+/// 字幕下载参数，作品的指定方式与评论相同
+#[derive(Debug, Deserialize)]
+struct SubtitleQuery {
+    url: Option<String>,
+    source: Option<String>,
+    id: Option<String>,
+    /// 语言代码，默认优先人工字幕
+    lang: Option<String>,
+    /// srt（默认）或 vtt
+    format: Option<String>,
+}
+
+async fn subtitle_handler(Query(params): Query<SubtitleQuery>) -> Response {
+    let format = match params.format.as_deref() {
+        None => SubtitleFormat::Srt,
+        Some(f) => match SubtitleFormat::from_str(f) {
+            Some(format) => format,
+            None => return Json(HttpResponse::<()>::error(format!("不支持的字幕格式: {}", f))).into_response(),
+        },
+    };
+    let result = match (&params.source, &params.id, &params.url) {
+        (Some(source), Some(id), _) => match VideoSource::from_str(source) {
+            Some(source) => parse_video_id(source, id).await,
+            None => return Json(HttpResponse::<()>::error(format!("不支持的平台: {}", source))).into_response(),
+        },
+        (_, _, Some(url)) => match extract_url_from_string(url) {
+            Ok(url) => parse_video_share_url(&url).await,
+            Err(e) => return Json(HttpResponse::<()>::error(format!("URL提取失败: {}", e))).into_response(),
+        },
+        _ => return Json(HttpResponse::<()>::error("请提供 url，或 source 和 id 参数".to_string())).into_response(),
+    };
+    let info = match result {
+        Ok(info) => info,
+        Err(e) => return Json(HttpResponse::<()>::error(format!("视频解析失败: {}", e))).into_response(),
+    };
+    let Some(track) = select_track(&info.subtitles, params.lang.as_deref()) else {
+        return Json(HttpResponse::<()>::error("没有找到对应语言的字幕".to_string())).into_response();
+    };
+
+    let filename = format!(
+        "{}.{}.{}",
+        info.content_id.as_deref().unwrap_or("subtitle"),
+        track.lang,
+        format.extension()
+    );
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, attachment_disposition(&filename)),
+        ],
+        render(&track.cues, format),
+    )
+        .into_response()
+}
+
 #[derive(Debug, Serialize)]
 struct PlatformInfo {
     source: String,
@@ -196,19 +436,136 @@ async fn platforms_handler() -> Json<HttpResponse<Vec<PlatformInfo>>> {
     Json(HttpResponse::success(platforms))
 }
 
+#[derive(Debug, Deserialize)]
+struct AdminQuery {
+    token: Option<String>,
+}
+
+/// 校验管理接口令牌，未通过时返回拒绝响应；未配置 ADMIN_TOKEN 时管理接口关闭
+fn check_admin_token(headers: &HeaderMap, query_token: Option<&str>) -> Option<Response> {
+    let Some(expected) = config().admin_token.as_deref() else {
+        return Some((
+            StatusCode::FORBIDDEN,
+            Json(HttpResponse::<()>::error("未配置 ADMIN_TOKEN，管理接口已关闭".to_string())),
+        )
+            .into_response());
+    };
+
+    let header_token = headers
+        .get("x-admin-token")
+        .and_then(|v| v.to_str().ok());
+
+    match header_token.or(query_token) {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some((
+            StatusCode::UNAUTHORIZED,
+            Json(HttpResponse::<()>::error("管理令牌无效".to_string())),
+        )
+            .into_response()),
+    }
+}
+
+/// 比较令牌，耗时与内容无关，避免按响应时间逐字节猜出令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        diff |= usize::from(a.get(i).copied().unwrap_or(0) ^ b.get(i).copied().unwrap_or(0));
+    }
+    diff == 0
+}
+
+async fn upstream_status_handler(
+    headers: HeaderMap,
+    Query(params): Query<AdminQuery>,
+) -> Response {
+    if let Some(response) = check_admin_token(&headers, params.token.as_deref()) {
+        return response;
+    }
+
+    Json(HttpResponse::<Vec<UpstreamStatus>>::success(upstream_status())).into_response()
+}
+
+/// 最近的解析失败记录
+async fn captures_handler(
+    headers: HeaderMap,
+    Query(params): Query<AdminQuery>,
+) -> Response {
+    if let Some(response) = check_admin_token(&headers, params.token.as_deref()) {
+        return response;
+    }
+
+    match capture::list().await {
+        Ok(captures) => Json(HttpResponse::success(captures)).into_response(),
+        Err(e) => Json(HttpResponse::<()>::error(format!("读取记录失败: {}", e))).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CaptureQuery {
+    id: String,
+    token: Option<String>,
+}
+
+/// 下载解析失败记录，可用 `parse --replay` 回放
+async fn capture_download_handler(
+    headers: HeaderMap,
+    Query(params): Query<CaptureQuery>,
+) -> Response {
+    if let Some(response) = check_admin_token(&headers, params.token.as_deref()) {
+        return response;
+    }
+
+    match capture::load(&params.id).await {
+        Ok(content) => (
+            [
+                (header::CONTENT_TYPE, "application/json; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, attachment_disposition(&format!("{}.json", params.id))),
+            ],
+            content,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::error(e.to_string())),
+        )
+            .into_response(),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ProxyQuery {
     url: String,
+    /// 资源所属平台，用于选择平台专用的上游代理
+    source: Option<String>,
+    /// 下载时保存的文件名
+    filename: Option<String>,
+}
+
+/// 创建媒体代理使用的HTTP客户端
+fn create_media_client(source: Option<VideoSource>, timeout_secs: u64) -> anyhow::Result<Client> {
+    let builder = Client::builder()
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+        .timeout(std::time::Duration::from_secs(timeout_secs));
+
+    Ok(apply_proxy(builder, source)?.build()?)
 }
 
 async fn proxy_video_handler(Query(params): Query<ProxyQuery>) -> impl IntoResponse {
     tracing::info!("🎬 代理视频请求: {}", params.url);
+    proxy_media(params, "视频", "video/mp4").await
+}
+
+/// 代理背景音乐，便于单独下载音频
+async fn proxy_audio_handler(Query(params): Query<ProxyQuery>) -> impl IntoResponse {
+    tracing::info!("🎵 代理音频请求: {}", params.url);
+    proxy_media(params, "音频", "audio/mpeg").await
+}
+
+/// 转发视频或音频，`kind` 用于日志和错误信息
+async fn proxy_media(params: ProxyQuery, kind: &str, default_content_type: &str) -> Response {
     
-    let client = match Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-    {
+    let source = params.source.as_deref().and_then(VideoSource::from_str);
+    let client = match create_media_client(source, 30) {
         Ok(c) => c,
         Err(e) => {
             tracing::error!("创建HTTP客户端失败: {}", e);
@@ -224,11 +581,12 @@ async fn proxy_video_handler(Query(params): Query<ProxyQuery>) -> impl IntoRespo
     let response = match client.get(&params.url).send().await {
         Ok(r) => r,
         Err(e) => {
-            tracing::error!("获取视频失败: {}", e);
+            tracing::error!("获取{}失败: {}", kind, e);
+            rotate_proxy(source);
             return (
                 StatusCode::BAD_GATEWAY,
                 [(header::CONTENT_TYPE, "text/plain")],
-                format!("获取视频失败: {}", e),
+                format!("获取{}失败: {}", kind, e),
             )
                 .into_response();
         }
@@ -239,7 +597,7 @@ async fn proxy_video_handler(Query(params): Query<ProxyQuery>) -> impl IntoRespo
         .headers()
         .get(reqwest::header::CONTENT_TYPE)
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("video/mp4")
+        .unwrap_or(default_content_type)
         .to_string();
 
     let content_length = response
@@ -253,38 +611,51 @@ async fn proxy_video_handler(Query(params): Query<ProxyQuery>) -> impl IntoRespo
     let bytes = match response.bytes().await {
         Ok(b) => b,
         Err(e) => {
-            tracing::error!("读取视频数据失败: {}", e);
+            tracing::error!("读取{}数据失败: {}", kind, e);
             return (
                 StatusCode::BAD_GATEWAY,
                 [(header::CONTENT_TYPE, "text/plain")],
-                format!("读取视频数据失败: {}", e),
+                format!("读取{}数据失败: {}", kind, e),
             )
                 .into_response();
         }
     };
 
-    tracing::info!("✅ 成功代理视频，大小: {} bytes", bytes.len());
+    tracing::info!("✅ 成功代理{}，大小: {} bytes", kind, bytes.len());
     
-    Response::builder()
+    let mut builder = Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, content_type)
         .header(header::CONTENT_LENGTH, content_length)
         .header(header::CACHE_CONTROL, "public, max-age=31536000")
-        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ACCEPT_RANGES, "bytes");
+    // 指定文件名时作为附件下载
+    if let Some(filename) = params.filename.as_deref().filter(|f| !f.is_empty()) {
+        builder = builder.header(
+            header::CONTENT_DISPOSITION,
+            attachment_disposition(filename),
+        );
+    }
+    builder
         .body(Body::from(bytes))
         .unwrap()
         .into_response()
 }
 
+/// 作为附件下载的 Content-Disposition，文件名按 RFC 5987 编码
+fn attachment_disposition(filename: &str) -> String {
+    format!(
+        "attachment; filename*=UTF-8''{}",
+        url::form_urlencoded::byte_serialize(filename.as_bytes()).collect::<String>().replace('+', "%20")
+    )
+}
+
 async fn proxy_image_handler(Query(params): Query<ProxyQuery>) -> impl IntoResponse {
     tracing::debug!("🖼️ 代理图片请求: {}", params.url);
 
     // 创建HTTP客户端
-    let client = match Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-    {
+    let source = params.source.as_deref().and_then(VideoSource::from_str);
+    let client = match create_media_client(source, 15) {
         Ok(c) => c,
         Err(e) => {
             tracing::error!("创建HTTP客户端失败: {}", e);
@@ -301,6 +672,7 @@ async fn proxy_image_handler(Query(params): Query<ProxyQuery>) -> impl IntoRespo
         Ok(r) => r,
         Err(e) => {
             tracing::error!("获取图片失败: {}", e);
+            rotate_proxy(source);
             return (
                 StatusCode::BAD_GATEWAY,
                 [(header::CONTENT_TYPE, "text/plain")],
@@ -339,3 +711,100 @@ async fn proxy_image_handler(Query(params): Query<ProxyQuery>) -> impl IntoRespo
         .into_response()
 }
 
+/// 转发直播流：FLV 边拉边发，HLS 播放列表中的地址改写为经由本接口
+async fn proxy_live_handler(Query(params): Query<ProxyQuery>) -> impl IntoResponse {
+    tracing::info!("📡 代理直播流: {}", params.url);
+
+    // source 会写回改写后的播放列表，只接受已知平台
+    let source = match params.source.as_deref().map(|s| (s, VideoSource::from_str(s))) {
+        None => None,
+        Some((_, Some(source))) => Some(source),
+        Some((name, None)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                [(header::CONTENT_TYPE, "text/plain")],
+                format!("不支持的平台: {}", name.escape_debug()),
+            )
+                .into_response();
+        }
+    };
+    let client = match create_stream_client(source) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("创建HTTP客户端失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(header::CONTENT_TYPE, "text/plain")],
+                "创建HTTP客户端失败",
+            )
+                .into_response();
+        }
+    };
+
+    let response = match with_read_timeout(async {
+        client.get(&params.url).send().await.and_then(|r| r.error_for_status())
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("获取直播流失败: {}", e);
+            rotate_proxy(source);
+            return (
+                StatusCode::BAD_GATEWAY,
+                [(header::CONTENT_TYPE, "text/plain")],
+                format!("获取直播流失败: {}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    if is_hls_url(response.url().as_str()) || content_type.contains("mpegurl") {
+        let base = response.url().clone();
+        let playlist = match with_read_timeout(response.text()).await {
+            Ok(t) => t,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    [(header::CONTENT_TYPE, "text/plain")],
+                    format!("读取播放列表失败: {}", e),
+                )
+                    .into_response();
+            }
+        };
+        let source_param = source
+            .map(|s| format!("&source={}", s.as_str()))
+            .unwrap_or_default();
+        let body = rewrite_hls_playlist(&playlist, &base, |url| {
+            format!(
+                "/api/proxy/live?url={}{}",
+                url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>(),
+                source_param
+            )
+        });
+
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/vnd.apple.mpegurl")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::from(body))
+            .unwrap()
+            .into_response();
+    }
+
+    let content_type = if content_type.is_empty() { "video/x-flv".to_string() } else { content_type };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from_stream(read_timeout(response.bytes_stream())))
+        .unwrap()
+        .into_response()
+}
@@ -0,0 +1,109 @@
+//! 字幕导出：把解析结果中的字幕轨道转换为 SRT 或 WebVTT
+
+use crate::models::{SubtitleCue, SubtitleTrack};
+use std::fmt::Write;
+
+/// 字幕文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Srt => "application/x-subrip; charset=utf-8",
+            Self::Vtt => "text/vtt; charset=utf-8",
+        }
+    }
+}
+
+/// 按语言代码选择轨道，未指定时优先人工字幕
+pub fn select_track<'a>(tracks: &'a [SubtitleTrack], lang: Option<&str>) -> Option<&'a SubtitleTrack> {
+    match lang.filter(|l| !l.is_empty()) {
+        Some(lang) => tracks.iter().find(|t| t.lang.eq_ignore_ascii_case(lang)),
+        None => tracks.iter().find(|t| !t.ai).or_else(|| tracks.first()),
+    }
+}
+
+/// 生成字幕文件文本，跳过空白字幕
+pub fn render(cues: &[SubtitleCue], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+    if format == SubtitleFormat::Vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+    let cues = cues.iter().filter(|cue| !cue.content.trim().is_empty());
+    for (index, cue) in cues.enumerate() {
+        if format == SubtitleFormat::Srt {
+            let _ = writeln!(out, "{}", index + 1);
+        }
+        let _ = writeln!(
+            out,
+            "{} --> {}\n{}\n",
+            timestamp(cue.from, format),
+            timestamp(cue.to.max(cue.from), format),
+            cue.content.trim()
+        );
+    }
+    out
+}
+
+/// SRT 为 `00:00:01,500`，WebVTT 为 `00:00:01.500`
+fn timestamp(secs: f64, format: SubtitleFormat) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    let separator = if format == SubtitleFormat::Srt { ',' } else { '.' };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let cues = vec![
+            SubtitleCue { from: 0.5, to: 2.0, content: "第一句".to_string() },
+            SubtitleCue { from: 3661.25, to: 3663.0, content: "第二句\n换行".to_string() },
+        ];
+        assert_eq!(
+            render(&cues, SubtitleFormat::Srt),
+            "1\n00:00:00,500 --> 00:00:02,000\n第一句\n\n2\n01:01:01,250 --> 01:01:03,000\n第二句\n换行\n\n"
+        );
+        assert_eq!(
+            render(&cues, SubtitleFormat::Vtt),
+            "WEBVTT\n\n00:00:00.500 --> 00:00:02.000\n第一句\n\n01:01:01.250 --> 01:01:03.000\n第二句\n换行\n\n"
+        );
+
+        let tracks = vec![
+            SubtitleTrack { lang: "ai-zh".to_string(), ai: true, ..Default::default() },
+            SubtitleTrack { lang: "en-US".to_string(), ..Default::default() },
+        ];
+        assert_eq!(select_track(&tracks, None).unwrap().lang, "en-US");
+        assert_eq!(select_track(&tracks, Some("AI-ZH")).unwrap().lang, "ai-zh");
+        assert!(select_track(&tracks, Some("ja")).is_none());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::credentials::credentials;
+use crate::models::VideoSource;
+use crate::upstream::apply_proxy;
 use anyhow::{anyhow, Result};
 use regex::Regex;
-use reqwest::{Client, redirect::Policy};
+use reqwest::{Client, ClientBuilder, redirect::Policy};
 use std::time::Duration;
 
 
@@ -16,19 +19,51 @@ pub fn extract_url_from_string(text: &str) -> Result<String> {
         .ok_or_else(|| anyhow!("字符串中未找到URL"))
 }
 
-pub fn create_http_client() -> Result<Client> {
-    Client::builder()
+/// 创建访问平台接口的HTTP客户端，按平台配置上游代理和账号Cookie
+pub fn create_http_client(source: VideoSource) -> Result<Client> {
+    let builder = Client::builder()
         .timeout(Duration::from_secs(30))
-        .user_agent(DEFAULT_USER_AGENT)
-        .build()
-        .map_err(|e| anyhow!("创建HTTP客户端失败: {}", e))
+        .user_agent(DEFAULT_USER_AGENT);
+
+    build_platform_client(builder, source)
 }
 
-pub fn create_no_redirect_client() -> Result<Client> {
-    Client::builder()
+pub fn create_no_redirect_client(source: VideoSource) -> Result<Client> {
+    let builder = Client::builder()
         .timeout(Duration::from_secs(30))
         .user_agent(DEFAULT_USER_AGENT)
-        .redirect(Policy::none())
+        .redirect(Policy::none());
+
+    build_platform_client(builder, source)
+}
+
+/// 按可选平台创建客户端，规则或插件新增的平台只使用全局代理
+pub fn create_client(source: Option<VideoSource>, follow_redirects: bool) -> Result<Client> {
+    match (source, follow_redirects) {
+        (Some(source), true) => create_http_client(source),
+        (Some(source), false) => create_no_redirect_client(source),
+        (None, _) => {
+            let mut builder = Client::builder()
+                .timeout(Duration::from_secs(30))
+                .user_agent(DEFAULT_USER_AGENT);
+            if !follow_redirects {
+                builder = builder.redirect(Policy::none());
+            }
+            apply_proxy(builder, None)?
+                .build()
+                .map_err(|e| anyhow!("创建HTTP客户端失败: {}", e))
+        }
+    }
+}
+
+fn build_platform_client(builder: ClientBuilder, source: VideoSource) -> Result<Client> {
+    let mut builder = apply_proxy(builder, Some(source))?;
+
+    if let Some(account) = credentials().current(source) {
+        builder = builder.cookie_provider(account);
+    }
+
+    builder
         .build()
         .map_err(|e| anyhow!("创建HTTP客户端失败: {}", e))
 }
@@ -58,13 +93,483 @@ pub fn generate_random_string(length: usize) -> String {
         .collect()
 }
 
-pub fn extract_json_from_html(html: &str, pattern: &str) -> Result<String> {
-    let re = Regex::new(pattern)?;
-    
-    re.captures(html)
-        .and_then(|caps| caps.get(1))
-        .map(|m| m.as_str().trim().to_string())
-        .ok_or_else(|| anyhow!("无法从HTML中提取JSON数据"))
+/// 将Unix时间戳转换为RFC3339格式，自动识别秒和毫秒
+pub fn timestamp_to_rfc3339(ts: i64) -> Option<String> {
+    if ts <= 0 {
+        return None;
+    }
+    let secs = if ts > 100_000_000_000 { ts / 1000 } else { ts };
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// 读取JSON中的时间戳（数字或数字字符串）并转换为RFC3339格式
+pub fn json_timestamp(value: Option<&serde_json::Value>) -> Option<String> {
+    let value = value?;
+    let ts = value.as_i64()
+        .or_else(|| value.as_f64().map(|f| f as i64))
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))?;
+    timestamp_to_rfc3339(ts)
+}
+
+/// 读取JSON中的计数，支持数字以及 "1.2万"、"3亿"、"10w+" 这类展示文本
+pub fn json_count(value: Option<&serde_json::Value>) -> Option<u64> {
+    let value = value?;
+    if let Some(n) = value.as_u64() {
+        return Some(n);
+    }
+    if let Some(f) = value.as_f64() {
+        return (f >= 0.0).then_some(f as u64);
+    }
+
+    // 去掉 "次播放"、"次观看" 之类的后缀
+    let text = value.as_str()?.trim();
+    let end = text
+        .char_indices()
+        .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '万' | '亿' | 'w' | 'W' | '+')))
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let text = text[..end].trim_end_matches('+');
+    let (number, unit) = if let Some(n) = text.strip_suffix('万').or_else(|| text.strip_suffix(['w', 'W'])) {
+        (n, 10_000.0)
+    } else if let Some(n) = text.strip_suffix('亿') {
+        (n, 100_000_000.0)
+    } else {
+        (text, 1.0)
+    };
+
+    let number: f64 = number.trim().replace(',', "").parse().ok()?;
+    (number >= 0.0).then(|| (number * unit).round() as u64)
+}
+
+/// 读取JSON中的非空文本，数字ID也转为文本
+pub fn json_str(value: Option<&serde_json::Value>) -> Option<String> {
+    match value? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 读取JSON中的尺寸等正整数
+pub fn json_u32(value: Option<&serde_json::Value>) -> Option<u32> {
+    let value = value?;
+    value.as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .filter(|n| *n > 0)
+        .and_then(|n| u32::try_from(n).ok())
+}
+
+/// 读取JSON中的时长并换算为秒，`millis` 表示上游单位为毫秒
+pub fn json_duration(value: Option<&serde_json::Value>, millis: bool) -> Option<f64> {
+    let value = value?;
+    let n = value.as_f64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))?;
+    (n > 0.0).then(|| if millis { n / 1000.0 } else { n })
+}
+
+/// 从正文中提取 `#话题#` 形式的话题标签
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let re = Regex::new(r"#([^#\s][^#]*?)#").unwrap();
+    let mut tags: Vec<String> = Vec::new();
+    for caps in re.captures_iter(text) {
+        let tag = caps[1].trim().trim_end_matches("[话题]").to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// JS 字面量的嵌套层数上限，避免异常页面导致栈溢出
+const JS_LITERAL_MAX_DEPTH: usize = 256;
+
+/// 在页面或脚本中找到对 `name` 的赋值（如 `window._ROUTER_DATA = {...}`），把右侧的 JS 字面量转换为 JSON
+///
+/// 按词法扫描，字符串中的 `</script>`、括号和转义不影响边界判断。支持单引号和模板字符串、
+/// 未加引号的键、尾随逗号、注释、十六进制数、`!0`/`!1`、`new Date(...)`（毫秒时间戳转为 ISO 字符串），
+/// `undefined`、`NaN`、`Infinity` 转为 `null`。右侧不是纯字面量（如 `[...][0] || {}`）时返回错误。
+pub fn extract_js_literal(text: &str, name: &str) -> Result<serde_json::Value> {
+    let mut last_error = None;
+    for start in js_assignments(text, name) {
+        match JsLiteral::new(&text[start..]).convert() {
+            Ok(json) => return Ok(serde_json::from_str(&json)?),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow!("没有找到 {} 的赋值", name)))
+}
+
+/// 所有 `name =` 赋值中等号之后的位置，排除 `==`、`=>` 和更长的标识符
+fn js_assignments<'a>(text: &'a str, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+    text.match_indices(name).filter_map(move |(index, _)| {
+        let before = text[..index].chars().next_back();
+        if before.is_some_and(|c| is_js_ident_char(c) || c == '.') {
+            return None;
+        }
+        let after = text[index + name.len()..].trim_start().strip_prefix('=')?;
+        if after.starts_with('=') || after.starts_with('>') {
+            return None;
+        }
+        Some(text.len() - after.len())
+    })
+}
+
+fn is_js_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// JS 字面量到 JSON 文本的转换器
+struct JsLiteral<'a> {
+    src: &'a str,
+    pos: usize,
+    out: String,
+}
+
+impl<'a> JsLiteral<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0, out: String::new() }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(anyhow!("位置 {} 应为 {:?}，实际为 {:?}", self.pos, expected, c)),
+            None => Err(anyhow!("应为 {:?}，但已到结尾", expected)),
+        }
+    }
+
+    /// 跳过空白和注释，返回其间是否有换行
+    fn skip_trivia(&mut self) -> Result<bool> {
+        let mut newline = false;
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    newline |= c == '\n';
+                    self.bump();
+                }
+                Some('/') if self.rest().starts_with("//") => {
+                    let len = self.rest().find('\n').unwrap_or(self.rest().len());
+                    self.pos += len;
+                }
+                Some('/') if self.rest().starts_with("/*") => {
+                    let len = self.rest()[2..].find("*/").ok_or_else(|| anyhow!("注释未结束"))?;
+                    newline |= self.rest()[2..2 + len].contains('\n');
+                    self.pos += len + 4;
+                }
+                _ => return Ok(newline),
+            }
+        }
+    }
+
+    /// 转换一个完整的赋值右侧，之后只能是语句结束
+    fn convert(mut self) -> Result<String> {
+        self.value(0)?;
+        let newline = self.skip_trivia()?;
+        match self.peek() {
+            None | Some(';' | ',' | ')' | '}' | '<') => Ok(self.out),
+            Some(c) if newline && (is_js_ident_char(c) || c == '(') => Ok(self.out),
+            Some(c) => Err(anyhow!("赋值不是纯字面量，位置 {} 之后为 {:?}", self.pos, c)),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<()> {
+        if depth > JS_LITERAL_MAX_DEPTH {
+            return Err(anyhow!("嵌套层数超过 {}", JS_LITERAL_MAX_DEPTH));
+        }
+        self.skip_trivia()?;
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some(quote @ ('"' | '\'' | '`')) => {
+                let s = self.string(quote)?;
+                self.push_string(&s);
+                Ok(())
+            }
+            Some('!') => {
+                self.bump();
+                self.skip_trivia()?;
+                match self.bump() {
+                    Some('0') => self.out.push_str("true"),
+                    Some('1') => self.out.push_str("false"),
+                    _ => return Err(anyhow!("位置 {} 的 ! 表达式不是字面量", self.pos)),
+                }
+                Ok(())
+            }
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.number(),
+            Some(c) if is_js_ident_char(c) => self.keyword(depth),
+            Some(c) => Err(anyhow!("位置 {} 的 {:?} 不是字面量", self.pos, c)),
+            None => Err(anyhow!("意外的结尾")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<()> {
+        self.expect('{')?;
+        self.out.push('{');
+        let mut first = true;
+        loop {
+            self.skip_trivia()?;
+            let key = match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    break;
+                }
+                Some(quote @ ('"' | '\'' | '`')) => self.string(quote)?,
+                Some(c) if is_js_ident_char(c) => self.ident().to_string(),
+                Some(c) => return Err(anyhow!("位置 {} 的 {:?} 不是对象键", self.pos, c)),
+                None => return Err(anyhow!("对象未结束")),
+            };
+            if !first {
+                self.out.push(',');
+            }
+            first = false;
+            self.push_string(&key);
+            self.skip_trivia()?;
+            self.expect(':')?;
+            self.out.push(':');
+            self.value(depth + 1)?;
+            self.skip_trivia()?;
+            match self.bump() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(anyhow!("位置 {} 的对象成员之间缺少逗号", self.pos)),
+            }
+        }
+        self.out.push('}');
+        Ok(())
+    }
+
+    /// 数组中的空位（`[1,,2]`）转为 `null`
+    fn array(&mut self, depth: usize) -> Result<()> {
+        self.expect('[')?;
+        self.out.push('[');
+        let mut count = 0;
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(']') => {
+                    self.bump();
+                    break;
+                }
+                Some(',') => {
+                    self.bump();
+                    if count > 0 {
+                        self.out.push(',');
+                    }
+                    self.out.push_str("null");
+                    count += 1;
+                    continue;
+                }
+                None => return Err(anyhow!("数组未结束")),
+                _ => {}
+            }
+            if count > 0 {
+                self.out.push(',');
+            }
+            self.value(depth + 1)?;
+            count += 1;
+            self.skip_trivia()?;
+            match self.bump() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(anyhow!("位置 {} 的数组元素之间缺少逗号", self.pos)),
+            }
+        }
+        self.out.push(']');
+        Ok(())
+    }
+
+    fn ident(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_js_ident_char) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn keyword(&mut self, depth: usize) -> Result<()> {
+        match self.ident() {
+            word @ ("true" | "false" | "null") => self.out.push_str(word),
+            "undefined" | "NaN" | "Infinity" => self.out.push_str("null"),
+            "new" => {
+                self.skip_trivia()?;
+                if self.ident() != "Date" {
+                    return Err(anyhow!("位置 {} 只支持 new Date(...)", self.pos));
+                }
+                self.skip_trivia()?;
+                self.expect('(')?;
+                self.skip_trivia()?;
+                if self.peek() == Some(')') {
+                    self.out.push_str("null");
+                } else {
+                    // 与 JSON.stringify 一致，毫秒时间戳转为 ISO 字符串
+                    let start = self.out.len();
+                    self.value(depth + 1)?;
+                    let iso = self.out[start..].parse::<f64>().ok()
+                        .and_then(|ms| chrono::DateTime::from_timestamp_millis(ms as i64))
+                        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+                    if let Some(iso) = iso {
+                        self.out.truncate(start);
+                        self.push_string(&iso);
+                    }
+                    self.skip_trivia()?;
+                }
+                self.expect(')')?;
+            }
+            word => return Err(anyhow!("{} 不是字面量", word)),
+        }
+        Ok(())
+    }
+
+    /// 十进制整数原样保留，十六进制、八进制、二进制转为十进制，非有限值转为 `null`
+    fn number(&mut self) -> Result<()> {
+        let mut negative = false;
+        while let Some(sign @ ('-' | '+')) = self.peek() {
+            negative ^= sign == '-';
+            self.bump();
+        }
+        if self.rest().starts_with("Infinity") {
+            self.pos += "Infinity".len();
+            self.out.push_str("null");
+            return Ok(());
+        }
+
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let exponent_sign = matches!(c, '+' | '-')
+                && self.src[start..self.pos].ends_with(['e', 'E'])
+                && !self.src[start..self.pos].starts_with("0x");
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let token = self.src[start..self.pos].replace('_', "");
+        let lower = token.to_ascii_lowercase();
+        let sign = if negative { "-" } else { "" };
+
+        let radix = match lower.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            let n = u128::from_str_radix(&token[2..], radix)
+                .map_err(|_| anyhow!("无效的数字 {}", token))?;
+            self.out.push_str(&format!("{}{}", sign, n));
+            return Ok(());
+        }
+        if !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit()) {
+            let n: i128 = format!("{}{}", sign, token).parse()?;
+            self.out.push_str(&n.to_string());
+            return Ok(());
+        }
+        if !token.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return Err(anyhow!("无效的数字 {}", token));
+        }
+        let n: f64 = format!("{}{}", sign, token)
+            .parse()
+            .map_err(|_| anyhow!("无效的数字 {}", token))?;
+        match serde_json::Number::from_f64(n) {
+            Some(n) => self.out.push_str(&n.to_string()),
+            None => self.out.push_str("null"),
+        }
+        Ok(())
+    }
+
+    /// 解码字符串字面量，模板字符串中不能有 `${}` 表达式
+    fn string(&mut self, quote: char) -> Result<String> {
+        self.expect(quote)?;
+        let mut s = String::new();
+        loop {
+            match self.bump().ok_or_else(|| anyhow!("字符串未结束"))? {
+                c if c == quote => return Ok(s),
+                '\\' => self.escape(&mut s)?,
+                '\n' | '\r' if quote != '`' => return Err(anyhow!("字符串中有换行")),
+                '$' if quote == '`' && self.peek() == Some('{') => {
+                    return Err(anyhow!("模板字符串中有表达式"));
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self, s: &mut String) -> Result<()> {
+        let c = self.bump().ok_or_else(|| anyhow!("字符串未结束"))?;
+        match c {
+            'n' => s.push('\n'),
+            't' => s.push('\t'),
+            'r' => s.push('\r'),
+            'b' => s.push('\u{8}'),
+            'f' => s.push('\u{c}'),
+            'v' => s.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => s.push('\0'),
+            'x' => {
+                let code = self.hex(2)?;
+                s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            'u' => {
+                let code = if self.peek() == Some('{') {
+                    self.bump();
+                    let end = self.rest().find('}').ok_or_else(|| anyhow!("\\u{{}} 未结束"))?;
+                    let code = u32::from_str_radix(&self.rest()[..end], 16)?;
+                    self.pos += end + 1;
+                    code
+                } else {
+                    self.hex(4)?
+                };
+                // 代理对由两个 \uXXXX 组成
+                if (0xD800..0xDC00).contains(&code) && self.rest().starts_with("\\u") {
+                    let saved = self.pos;
+                    self.pos += 2;
+                    match self.hex(4) {
+                        Ok(low) if (0xDC00..0xE000).contains(&low) => {
+                            let combined = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            s.push(char::from_u32(combined).unwrap_or('\u{fffd}'));
+                            return Ok(());
+                        }
+                        _ => self.pos = saved,
+                    }
+                }
+                s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            // 续行
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.bump();
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            c => s.push(c),
+        }
+        Ok(())
+    }
+
+    fn hex(&mut self, len: usize) -> Result<u32> {
+        let digits = self.rest().get(..len).ok_or_else(|| anyhow!("转义序列不完整"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| anyhow!("无效的转义序列 {}", digits))?;
+        self.pos += len;
+        Ok(code)
+    }
+
+    fn push_string(&mut self, s: &str) {
+        self.out.push_str(&serde_json::Value::String(s.to_string()).to_string());
+    }
 }
 
 #[cfg(test)]
@@ -83,5 +588,183 @@ mod tests {
         let s = generate_random_string(10);
         assert_eq!(s.len(), 10);
     }
-}
 
+    #[test]
+    fn test_metadata_helpers() {
+        use serde_json::json;
+
+        assert_eq!(timestamp_to_rfc3339(1700000000).as_deref(), Some("2023-11-14T22:13:20Z"));
+        assert_eq!(timestamp_to_rfc3339(1700000000123).as_deref(), Some("2023-11-14T22:13:20Z"));
+        assert_eq!(json_count(Some(&json!("1.2万"))), Some(12000));
+        assert_eq!(json_count(Some(&json!("10w+"))), Some(100000));
+        assert_eq!(json_count(Some(&json!(42))), Some(42));
+        assert_eq!(json_count(Some(&json!("3.5亿次播放"))), Some(350000000));
+        assert_eq!(json_count(Some(&json!("赞"))), None);
+        assert_eq!(json_duration(Some(&json!(15300)), true), Some(15.3));
+        assert_eq!(extract_hashtags("#旅行# 去看海 #海边[话题]# #旅行#"), vec!["旅行", "海边"]);
+    }
+
+    #[test]
+    fn test_extract_js_literal() {
+        use serde_json::json;
+
+        let html = r#"<script>var s = "window.__DATA__ = 1"; window.__DATA__ = {
+            // 注释
+            a: 'it\'s "ok"', 'b': [1,,0x1F, -2.5e3, .5, NaN, undefined, -Infinity,],
+            "c": "</script>{[", d: new Date(0), e: !0, f: `tpl`, /* 注释 */ g: "中\x41😀",
+        };</script>"#;
+        assert_eq!(
+            extract_js_literal(html, "window.__DATA__").unwrap(),
+            json!({
+                "a": "it's \"ok\"",
+                "b": [1, null, 31, -2500.0, 0.5, null, null, null],
+                "c": "</script>{[",
+                "d": "1970-01-01T00:00:00.000Z",
+                "e": true,
+                "f": "tpl",
+                "g": "中A😀",
+            })
+        );
+
+        assert!(extract_js_literal("var a = [{x: 1}][0] || {};", "a").is_err());
+        assert!(extract_js_literal("var a = {x: foo};", "a").is_err());
+        assert!(extract_js_literal("var a = `${x}`;", "a").is_err());
+        assert!(extract_js_literal("if (a == {}) {}", "a").is_err());
+        assert!(extract_js_literal("var data = {};", "a").is_err());
+        assert_eq!(extract_js_literal("var a = 1\nvar b = 2", "b").unwrap(), json!(2));
+    }
+
+    /// 随机生成 JSON，以各种 JS 写法嵌入页面后应能原样还原；随机破坏后的输入不能导致 panic
+    #[test]
+    fn test_extract_js_literal_fuzz() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+        use serde_json::{json, Value};
+
+        const NOISE: &[char] = &['{', '}', '[', ']', '\'', '"', '`', '\\', ',', ':', '/', '*', '!'];
+        const CHARS: &[&str] = &["a", "Z", "中", "😀", "\"", "'", "`", "\\", "\n", "\t", "\u{1}", "$", "{", "}", "</script>", "\u{2028}", " "];
+
+        fn random_string(rng: &mut StdRng) -> String {
+            (0..rng.gen_range(0..8)).map(|_| CHARS[rng.gen_range(0..CHARS.len())]).collect()
+        }
+
+        fn random_value(rng: &mut StdRng, depth: usize) -> Value {
+            match rng.gen_range(0..if depth > 3 { 5 } else { 7 }) {
+                0 => Value::Null,
+                1 => Value::Bool(rng.gen()),
+                2 => json!(rng.gen_range(-1_000_000_000i64..1_000_000_000)),
+                3 => json!(rng.gen_range(-1e6..1e6)),
+                4 => Value::String(random_string(rng)),
+                5 => Value::Array((0..rng.gen_range(0..4)).map(|_| random_value(rng, depth + 1)).collect()),
+                _ => Value::Object(
+                    (0..rng.gen_range(0..4)).map(|_| (random_string(rng), random_value(rng, depth + 1))).collect(),
+                ),
+            }
+        }
+
+        fn trivia(rng: &mut StdRng, out: &mut String) {
+            out.push_str(["", " ", "\n  ", "/* c */", "// c\n"][rng.gen_range(0..5)]);
+        }
+
+        fn write_string(rng: &mut StdRng, s: &str, out: &mut String) {
+            let quote = ['"', '\'', '`'][rng.gen_range(0..3)];
+            out.push(quote);
+            for c in s.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str(if rng.gen() { "\\t" } else { "\\x09" }),
+                    '$' if quote == '`' => out.push_str("\\$"),
+                    c if c == quote => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                    '😀' if rng.gen() => out.push_str("\\uD83D\\uDE00"),
+                    c if rng.gen_range(0..8) == 0 => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                    c => out.push(c),
+                }
+            }
+            out.push(quote);
+        }
+
+        fn write_js(rng: &mut StdRng, value: &Value, out: &mut String) {
+            trivia(rng, out);
+            match value {
+                Value::Null => out.push_str(["null", "undefined", "NaN"][rng.gen_range(0..3)]),
+                Value::Bool(b) => out.push_str(match (b, rng.gen::<bool>()) {
+                    (true, true) => "!0",
+                    (false, true) => "!1",
+                    (b, false) => if *b { "true" } else { "false" },
+                }),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) if i >= 0 && rng.gen() => out.push_str(&format!("0x{:X}", i)),
+                    _ => out.push_str(&n.to_string()),
+                },
+                Value::String(s) => write_string(rng, s, out),
+                Value::Array(items) => {
+                    out.push('[');
+                    for (i, item) in items.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        write_js(rng, item, out);
+                    }
+                    if !items.is_empty() && rng.gen() {
+                        out.push(',');
+                    }
+                    out.push(']');
+                }
+                Value::Object(map) => {
+                    out.push('{');
+                    for (i, (key, item)) in map.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        trivia(rng, out);
+                        let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic());
+                        if bare && rng.gen() {
+                            out.push_str(key);
+                        } else {
+                            write_string(rng, key, out);
+                        }
+                        out.push(':');
+                        write_js(rng, item, out);
+                    }
+                    if !map.is_empty() && rng.gen() {
+                        out.push(',');
+                    }
+                    out.push('}');
+                }
+            }
+            trivia(rng, out);
+        }
+
+        let mut rng = StdRng::seed_from_u64(20240611);
+        for _ in 0..500 {
+            let value = random_value(&mut rng, 0);
+            let mut js = String::new();
+            write_js(&mut rng, &value, &mut js);
+            let html = format!("<script>var s = 'window.__DATA__ = x';\nwindow.__DATA__ = {};</script>", js);
+            // serde_json 默认的浮点解析不保证往返一致，期望值同样经过一次解析
+            let expected: Value = serde_json::from_str(&value.to_string()).unwrap();
+            assert_eq!(extract_js_literal(&html, "window.__DATA__").unwrap(), expected, "{}", html);
+
+            // 截断或替换字符后只要求不 panic
+            let chars: Vec<char> = html.chars().collect();
+            for _ in 0..4 {
+                let mut mutated = chars.clone();
+                let index = rng.gen_range(0..mutated.len());
+                if rng.gen() {
+                    mutated.truncate(index);
+                } else {
+                    mutated[index] = NOISE[rng.gen_range(0..NOISE.len())];
+                }
+                let _ = extract_js_literal(&mutated.into_iter().collect::<String>(), "window.__DATA__");
+            }
+        }
+
+        let deep = format!("var a = {};", "[".repeat(100_000));
+        assert!(extract_js_literal(&deep, "a").is_err());
+    }
+}